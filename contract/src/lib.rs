@@ -3,19 +3,86 @@
 use concordium_std::*; // Import Concordium standard library.
 use concordium_cis2::*; // Import Concordium CIS-2 library.
 
-/// The initial value of APR
+/// The initial value of APR (1.39%)
 const INITIAL_APR: u64 = 139;
 
-/// The default denominator of APR
-const APR_DENOMINATOR: u128 = 1_000_000_00;
+/// The denominator of APR. The APR is expressed in basis points, so an APR
+/// of `APR_DENOMINATOR` pays out the staked amount once per year.
+const APR_DENOMINATOR: u128 = 10_000;
+
+/// The version of the contract, bumped by the migration of each upgrade
+const CONTRACT_VERSION: u16 = 1;
+
+/// The number of seconds in a year used for reward accrual
+const SECONDS_PER_YEAR: u128 = 365 * 24 * 60 * 60;
+
+/// The number of seconds in a day used for lock durations
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// The fixed-point precision of the reward per token accumulator
+const REWARD_PER_TOKEN_PRECISION: u128 = 1_000_000_000_000_000_000;
+
+/// The fixed-point precision used to compound the APR into the APY
+const APY_PRECISION: u128 = 1_000_000_000_000;
 
 /// The ID of the EUROe token
 const TOKEN_ID_EUROE: ContractTokenId = TokenIdUnit();
 
+/// The ID of the receipt token issued 1:1 for staked principal
+const TOKEN_ID_RECEIPT: ContractTokenId = TokenIdUnit();
+
+/// The denominator of values expressed in basis points (1% = 100)
+const BASIS_POINTS_DENOMINATOR: u64 = 10_000;
+
+/// The maximum number of items accepted by batch entrypoints
+const MAX_BATCH_SIZE: usize = 50;
+
+/// The maximum number of items returned by paginated views
+const MAX_PAGE_SIZE: u64 = 100;
+
+/// The maximum number of stakers returned by `topStakers`
+const MAX_TOP_STAKERS: u16 = 50;
+
+/// The maximum number of pending unbonding entries per staker
+const MAX_UNBONDING_ENTRIES: usize = 32;
+
+/// The maximum number of positions per staker
+const MAX_POSITIONS: usize = 32;
+
+/// The maximum number of APR changes kept in the APR history
+const MAX_APR_HISTORY: usize = 100;
+
+/// The maximum number of slashes kept in the slash history
+const MAX_SLASH_HISTORY: usize = 100;
+
+/// The denominator of the EUROe price returned by the price oracle
+const PRICE_DENOMINATOR: u128 = 1_000_000;
+
+/// The entrypoint of the price oracle returning the EUROe price
+const PRICE_ORACLE_ENTRYPOINT: EntrypointName = EntrypointName::new_unchecked(
+    "getPrice"
+);
+
 /// List of entrypoints supported by the `permit` function (CIS3)
-const SUPPORTS_PERMIT_ENTRYPOINTS: [EntrypointName; 2] = [
+const SUPPORTS_PERMIT_ENTRYPOINTS: [EntrypointName; 7] = [
     EntrypointName::new_unchecked("unstake"),
     EntrypointName::new_unchecked("claimRewards"),
+    EntrypointName::new_unchecked("completeUnstake"),
+    EntrypointName::new_unchecked("restake"),
+    EntrypointName::new_unchecked("fundRewards"),
+    EntrypointName::new_unchecked("withdrawEuroe"),
+    EntrypointName::new_unchecked("slash"),
+];
+
+/// The standard identifier for the CIS-3: Permit standard
+const CIS3_STANDARD_IDENTIFIER: StandardIdentifier<'static> =
+    StandardIdentifier::new_unchecked("CIS-3");
+
+/// List of standards supported by this contract
+const SUPPORTS_STANDARDS: [StandardIdentifier<'static>; 3] = [
+    CIS0_STANDARD_IDENTIFIER,
+    CIS2_STANDARD_IDENTIFIER,
+    CIS3_STANDARD_IDENTIFIER,
 ];
 
 /// Upgrade parameters
@@ -32,6 +99,7 @@ pub struct UpgradeParams {
 #[derive(Serialize, SchemaType)]
 pub struct InitContractParams {
     /// The admin role of concordium liquid staking smart contract.
+    /// Must be an account; the zero address is rejected.
     pub admin: AccountAddress,
 
     /// Address of the CIS-2 EUROe token contract.
@@ -40,7 +108,7 @@ pub struct InitContractParams {
     /// Unbonding period in seconds
     pub unbonding_period: u64,
 
-    /// Slashing rate in basis points (1% = 100)
+    /// Slashing rate in basis points (1% = 100), at most 10000
     pub slashing_rate: u64,
 }
 
@@ -51,14 +119,58 @@ pub struct UnstakeParams {
     pub amount: TokenAmountU64,
 }
 
+/// CancelUnbonding parameters
+#[derive(Serialize, SchemaType)]
+pub struct CancelUnbondingParams {
+    /// The index of the unbonding entry to cancel
+    pub index: u32,
+}
+
+/// ClaimRewards parameters
+#[derive(Serial, SchemaType, Default)]
+pub struct ClaimRewardsParams {
+    /// The amount of rewards to claim, or `None` to claim all rewards
+    pub amount: Option<TokenAmountU64>,
+
+    /// The account receiving the rewards, or `None` for the claimer. May be
+    /// omitted.
+    pub recipient: Option<AccountAddress>,
+}
+
+/// Parameters predating `recipient` omit it, so a missing trailing
+/// `recipient` is parsed as `None`.
+impl Deserial for ClaimRewardsParams {
+    fn deserial<R: Read>(source: &mut R) -> ParseResult<Self> {
+        let amount = source.get()?;
+        let recipient = match source.read_u8() {
+            Err(_) => None,
+            Ok(0) => None,
+            Ok(1) => Some(source.get()?),
+            Ok(_) => return Err(ParseError::default()),
+        };
+        Ok(ClaimRewardsParams {
+            amount,
+            recipient,
+        })
+    }
+}
+
+/// CompleteUnstake parameters
+#[derive(Serialize, SchemaType, Default)]
+pub struct CompleteUnstakeParams {
+    /// The index of the unbonding entry to withdraw, or `None` to withdraw
+    /// all matured entries
+    pub index: Option<u32>,
+}
+
 /// Withdraw parameters
 #[derive(Serialize, SchemaType)]
 pub struct WithdrawEuroEParams {
     /// The address of withdrawable
-    withdraw_address: AccountAddress,
+    pub withdraw_address: AccountAddress,
 
     /// The amount to withdraw
-    amount: TokenAmountU64,
+    pub amount: TokenAmountU64,
 }
 
 /// Set paused parameters
@@ -69,11 +181,213 @@ pub struct SetPausedParams {
     pub paused: bool,
 }
 
+/// Pause flags, each halting a group of operations
+#[derive(Debug, Serialize, SchemaType, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PauseFlags {
+    /// Halts stake, restake and cancelUnbonding.
+    pub stake_paused: bool,
+
+    /// Halts unstake and allows emergencyWithdraw.
+    pub unstake_paused: bool,
+
+    /// Halts claimRewards.
+    pub claim_paused: bool,
+
+    /// Halts claimRewards and restake. Set by the contract when the rewards
+    /// pool runs dry and `autoPauseOnDepletion` is enabled.
+    pub rewards_paused: bool,
+}
+
+/// Implementation of pause flags
+impl PauseFlags {
+    /// Set all flags to `paused`
+    pub fn all(paused: bool) -> Self {
+        PauseFlags {
+            stake_paused: paused,
+            unstake_paused: paused,
+            claim_paused: paused,
+            rewards_paused: paused,
+        }
+    }
+
+    /// Whether any operation is halted
+    pub fn any(&self) -> bool {
+        self.stake_paused || self.unstake_paused || self.claim_paused || self.rewards_paused
+    }
+}
+
 /// UpdateApr parameters
 #[derive(Serialize, SchemaType, Clone)]
 pub struct UpdateAprParams {
-    /// The new apr value.
-    new_apr: u64,
+    /// The new apr value in basis points.
+    pub new_apr: u64,
+}
+
+/// Epoch-based unbonding configuration
+#[derive(Debug, Serialize, SchemaType, Clone, Copy, PartialEq, Eq)]
+pub struct EpochConfig {
+    /// Length of an epoch in seconds
+    pub epoch_length: u64,
+
+    /// Start of the first epoch in seconds
+    pub genesis_time: u64,
+
+    /// Number of full epochs to wait after the next epoch boundary
+    pub min_unbonding_epochs: u64,
+}
+
+/// SetEpochConfig parameters
+#[derive(Serialize, SchemaType, Clone)]
+pub struct SetEpochConfigParams {
+    /// The epoch configuration, or `None` for seconds-based unbonding.
+    pub epoch_config: Option<EpochConfig>,
+}
+
+/// SetMaxRewardPerClaim parameters
+#[derive(Serialize, SchemaType, Clone)]
+pub struct SetMaxRewardPerClaimParams {
+    /// The maximum amount paid by a single claim, or `None` for no cap.
+    pub max_reward_per_claim: Option<TokenAmountU64>,
+}
+
+/// A lock duration and the bonus APR paid for committing to it
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct LockTier {
+    /// The lock duration in days.
+    pub lock_days: u64,
+
+    /// The bonus APR in basis points paid on top of the APR while locked.
+    pub bonus_bps: u64,
+}
+
+/// SetLockTiers parameters
+#[derive(Serialize, SchemaType, Clone)]
+pub struct SetLockTiersParams {
+    /// The available lock tiers, at most `MAX_BATCH_SIZE`.
+    #[concordium(size_length = 2)]
+    pub lock_tiers: Vec<LockTier>,
+}
+
+/// Data passed along with a stake transfer
+#[derive(Serial, SchemaType, Default)]
+pub struct StakeData {
+    /// The number of days to lock the deposit for, matching a lock tier, or
+    /// 0 for no lock.
+    pub lock_days: u64,
+
+    /// The account the stake is credited to, or `None` for the sender of
+    /// the transfer.
+    pub beneficiary: Option<AccountAddress>,
+}
+
+/// Data from before beneficiaries were supported ends after `lock_days`, so
+/// a missing beneficiary is read as `None`.
+impl Deserial for StakeData {
+    fn deserial<R: Read>(source: &mut R) -> ParseResult<Self> {
+        let lock_days = source.get()?;
+        let beneficiary = match source.read_u8() {
+            Err(_) => None,
+            Ok(0) => None,
+            Ok(1) => Some(source.get()?),
+            Ok(_) => return Err(ParseError::default()),
+        };
+        Ok(StakeData {
+            lock_days,
+            beneficiary,
+        })
+    }
+}
+
+/// Rounding of rewards to token units. The remainder is carried forward to
+/// the next accrual, so either mode is off by less than one unit in total.
+#[derive(Debug, Serialize, SchemaType, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round down, never paying more than earned.
+    #[default]
+    Floor,
+
+    /// Round to the nearest token unit, halves up.
+    Nearest,
+}
+
+/// SetMaxAccrualSeconds parameters
+#[derive(Serialize, SchemaType, Clone)]
+#[repr(transparent)]
+pub struct SetMaxAccrualSecondsParams {
+    /// The maximum number of seconds rewards accrue for between
+    /// interactions, or `None` for no cap.
+    pub max_accrual_seconds: Option<u64>,
+}
+
+/// Purpose of a transfer to `onReceivingCIS2`, passed as its additional data
+#[derive(Serialize, SchemaType)]
+pub enum DepositKind {
+    /// Stake the tokens, like a transfer to `stake`.
+    Stake(StakeData),
+
+    /// Add the tokens to the rewards pool.
+    FundRewards,
+
+    /// Add the tokens to the rewards pool gradually until the given
+    /// timestamp.
+    FundRewardsVested(u64),
+}
+
+/// FundRewardsVested parameters
+#[derive(Serialize, SchemaType, Clone)]
+pub struct FundRewardsVestedParams {
+    /// The amount of tokens to fund.
+    pub amount: TokenAmountU64,
+
+    /// Timestamp at which the whole amount is claimable.
+    pub vest_until: u64,
+}
+
+/// SetRewardFee parameters
+#[derive(Serialize, SchemaType, Clone)]
+pub struct SetRewardFeeParams {
+    /// Protocol fee on claimed rewards in basis points, at most 10000.
+    pub reward_fee_bps: u64,
+
+    /// The account receiving the fee.
+    pub fee_recipient: AccountAddress,
+}
+
+/// SetAprChangeLimits parameters
+#[derive(Serialize, SchemaType, Clone)]
+pub struct SetAprChangeLimitsParams {
+    /// The maximum change of the APR per update in basis points, or `None`
+    /// for no limit.
+    pub max_apr_change_bps: Option<u64>,
+
+    /// The minimum number of seconds between APR updates.
+    pub min_apr_update_interval: u64,
+}
+
+/// SetAprBounds parameters
+#[derive(Serialize, SchemaType, Clone)]
+pub struct SetAprBoundsParams {
+    /// The maximum APR `updateApr` accepts in basis points, or `None` for no
+    /// limit.
+    pub max_apr: Option<u64>,
+
+    /// Whether `updateApr` rejects an APR of zero.
+    pub forbid_zero_apr: bool,
+}
+
+/// SetAprLocking parameters
+#[derive(Serialize, SchemaType, Clone)]
+#[repr(transparent)]
+pub struct SetAprLockingParams {
+    /// Whether new stakes lock in the APR at deposit time.
+    pub apr_locking: bool,
+}
+
+/// SetPriceOracle parameters
+#[derive(Serialize, SchemaType, Clone)]
+pub struct SetPriceOracleParams {
+    /// The price oracle contract, or `None` to disable price lookups.
+    pub price_oracle: Option<ContractAddress>,
 }
 
 /// Part of the parameter type for the contract function `permit`.
@@ -86,7 +400,8 @@ pub struct PermitMessage {
     /// A nonce to prevent replay attacks.
     pub nonce: u64,
 
-    /// A timestamp to make signatures expire.
+    /// A timestamp to make signatures expire, in milliseconds since the
+    /// Unix epoch. The signature is valid until the block time reaches it.
     pub timestamp: Timestamp,
 
     /// The entry_point that the signature is intended for.
@@ -120,6 +435,95 @@ pub struct PermitParamPartial {
     pub signer: AccountAddress,
 }
 
+/// The parameter type for the contract function `hashPermitMessage`.
+#[derive(Serialize, SchemaType)]
+pub struct HashPermitMessageParams {
+    /// Account that will sign the message.
+    pub signer: AccountAddress,
+
+    /// Message to hash.
+    pub message: PermitMessage,
+}
+
+/// RewardsForecast parameters
+#[derive(Debug, Serialize, SchemaType)]
+pub struct RewardsForecastParams {
+    /// The user to forecast the rewards of.
+    pub user: AccountAddress,
+
+    /// The future timestamp in seconds to forecast the rewards at.
+    pub at_timestamp: u64,
+}
+
+/// SetFrozen parameters
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SetFrozenParams {
+    /// The account to freeze or unfreeze.
+    pub account: AccountAddress,
+
+    /// Whether the account is frozen.
+    pub frozen: bool,
+}
+
+/// Slash parameters
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SlashParams {
+    /// The staker to slash.
+    pub staker: AccountAddress,
+
+    /// The amount of active stake to slash.
+    pub amount: TokenAmountU64,
+
+    /// Why the staker is slashed, defined off-chain.
+    pub reason_code: u16,
+}
+
+/// SetSlashExempt parameters
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SetSlashExemptParams {
+    /// The account to exempt or stop exempting.
+    pub account: AccountAddress,
+
+    /// Whether the account cannot be slashed.
+    pub exempt: bool,
+}
+
+/// A slash kept in the slash history
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct SlashRecord {
+    /// The slashed staker.
+    pub staker: AccountAddress,
+
+    /// The amount of active stake slashed.
+    pub amount: TokenAmountU64,
+
+    /// Why the staker was slashed.
+    pub reason_code: u16,
+
+    /// Timestamp of the slash.
+    pub timestamp: u64,
+}
+
+/// IsNonceUsed parameters
+#[derive(Debug, Serialize, SchemaType)]
+pub struct IsNonceUsedParams {
+    /// The signer of the permit.
+    pub account: AccountAddress,
+
+    /// The nonce of the permit.
+    pub nonce: u64,
+}
+
+/// Pagination parameters
+#[derive(Debug, Serialize, SchemaType)]
+pub struct PageParams {
+    /// The number of entries to skip.
+    pub skip: u64,
+
+    /// The number of entries to return, capped at `MAX_PAGE_SIZE`.
+    pub take: u64,
+}
+
 /// The parameter type for the contract function `supportsPermit`.
 #[derive(Debug, Serialize, SchemaType)]
 pub struct SupportsPermitQueryParams {
@@ -128,10 +532,51 @@ pub struct SupportsPermitQueryParams {
     pub queries: Vec<OwnedEntrypointName>,
 }
 
+/// The parameter type for the contract function `batchGetStakeInfo`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct BatchGetStakeInfoParams {
+    /// The accounts to look up, at most `MAX_BATCH_SIZE`.
+    #[concordium(size_length = 2)]
+    pub accounts: Vec<AccountAddress>,
+}
+
+/// The parameter type for the contract function `simulateUnstake`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct SimulateUnstakeParams {
+    /// The staker to simulate the unstake for.
+    pub user: AccountAddress,
+
+    /// The amount to unstake.
+    pub amount: TokenAmountU64,
+}
+
+/// The parameter type for the contract function `getStakeInfoAt`.
+#[derive(Debug, Serialize, SchemaType)]
+pub struct GetStakeInfoAtParams {
+    /// The staker to look up.
+    pub user: AccountAddress,
+
+    /// The timestamp to accrue the rewards up to, in seconds.
+    pub at_timestamp: u64,
+}
+
+/// The return type of the contract function `simulateUnstake`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct SimulateUnstakeResult {
+    /// The unbonding entry the unstake would create.
+    pub unbonding: UnbondingInfo,
+
+    /// The rewards the unstake would move to the pending rewards.
+    pub rewards_crystallized: TokenAmountU64,
+
+    /// The active stake left after the unstake.
+    pub remaining_stake: u64,
+}
+
 /// View results
 #[derive(Serialize, SchemaType)]
 pub struct ViewResult {
-    /// Paused state for stopping relevant contract operations.
+    /// Whether any contract operation is paused.
     pub paused: bool,
 
     /// The admin role of concordium liquid staking smart contract.
@@ -154,1096 +599,4952 @@ pub struct ViewResult {
 
     /// Track available rewards
     pub rewards_pool: u64,
-}
 
-/// Information about a stake.
-#[derive(Debug, Serialize, SchemaType, Clone, PartialEq, Eq)]
-pub struct StakeInfo {
-    /// The staked amount of user.
-    pub amount: u64,
+    /// Unbonding period in seconds
+    pub unbonding_period: u64,
 
-    /// Timestamp when the stake was made.
-    pub timestamp: u64,
+    /// Slashing rate in basis points (1% = 100)
+    pub slashing_rate: u64,
 
-    /// Unbonding information
-    pub unbonding: Vec<UnbondingInfo>,
+    /// The individual pause flags.
+    pub pause_flags: PauseFlags,
 
-    /// Whether the stake is slashed
-    pub slashed: bool,
+    /// Total amount of tokens in unbonding queues.
+    pub total_unbonding: u64,
 
-    /// Pending rewards
-    pub pending_rewards: u64,
+    /// Rewards funded with vesting that are not claimable yet.
+    pub locked_rewards_pool: u64,
+
+    /// Timestamp of the last state change.
+    pub last_mutation_time: u64,
 }
 
-/// Unbonding information
-#[derive(Debug, Serialize, SchemaType, Clone, PartialEq, Eq)]
-pub struct UnbondingInfo {
-    /// Amount to unbond
-    pub amount: TokenAmountU64,
+/// Unbonding view results
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct UnbondingResult {
+    /// The pending unbonding entries of the user.
+    pub unbonding: Vec<UnbondingInfo>,
 
-    /// Unlock time in seconds
-    pub unlock_time: u64,
+    /// The current unbonding period in seconds.
+    pub unbonding_period: u64,
 }
 
-/// State of the contract.
-#[derive(Serial, DeserialWithState)]
-#[concordium(state_parameter = "S")]
-struct State<S = StateApi> {
-    /// Paused state for stopping relevant contract operations.
-    paused: bool,
+/// Metadata results
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct MetadataResult {
+    /// Address of the EUROe token contract.
+    pub token_address: ContractAddress,
 
-    /// The admin role of concordium liquid staking smart contract.
-    admin: AccountAddress,
+    /// The denominator of the APR, which is in basis points.
+    pub apr_denominator: u128,
 
-    /// The total amount of staked tokens.
-    total_staked: TokenAmountU64,
+    /// The entrypoints that can be invoked through `permit`.
+    pub supported_permit_entrypoints: Vec<OwnedEntrypointName>,
 
-    /// The annual percentage rate.
-    apr: u64,
+    /// The version of the contract.
+    pub version: u16,
+}
 
-    /// Mapping of staker addresses to their stake info.
-    stakes: StateMap<AccountAddress, StakeInfo, S>,
+/// Message hash results
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct MessageHashResult {
+    /// The bytes prepended to the message: the signer and 8 zero bytes.
+    pub prepend: [u8; 40],
 
-    /// Address of the EUROe token contract.
-    token_address: ContractAddress,
+    /// The length of the serialized `PermitMessage` in bytes.
+    pub message_length: u32,
 
-    /// The total number of participants
-    total_participants: u64,
+    /// The SHA-256 hash of the prepend and the message.
+    pub message_hash: [u8; 32],
+}
 
-    /// A registry to link an account to its next nonce.
-    nonces_registry: StateMap<AccountAddress, u64, S>,
+/// Solvency results
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct SolvencyResult {
+    /// The EUROe balance of the contract.
+    pub balance: TokenAmountU64,
 
-    /// Unbonding period in seconds
-    unbonding_period: u64,
+    /// The staked, unbonding and reward funds owed by the contract.
+    pub obligations: TokenAmountU64,
 
-    /// Slashing rate in basis points (1% = 100)
-    slashing_rate: u64,
+    /// Whether the balance covers the obligations.
+    pub solvent: bool,
+}
 
-    /// Track available rewards
-    rewards_pool: TokenAmountU64,
+/// Global statistics results
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct GlobalStatsResult {
+    /// The current APR in basis points.
+    pub apr: u64,
 
-    /// Track total rewards paid to users
-    total_rewards_paid: TokenAmountU64,
+    /// The APR compounded daily over a year, in basis points.
+    pub apy: u64,
+
+    /// The share of all funded rewards already paid out, in basis points.
+    pub utilization: u64,
+
+    /// The average amount staked per participant.
+    pub average_stake: TokenAmountU64,
 }
 
-/// Implementation of state
-impl State {
-    /// Get user stake info
-    #[allow(dead_code)]
-    pub fn get_user_stake(
-        &self,
-        user: &AccountAddress
-    ) -> (TokenAmountU64, u64) {
-        self.stakes.get(user).map_or_else(
-            || (TokenAmountU64(0), 0),
-            |s| (TokenAmountU64(s.amount), s.timestamp)
-        )
-    }
+/// Rewards value results
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct RewardsValueResult {
+    /// The accrued rewards of the user in EUROe.
+    pub rewards: TokenAmountU64,
 
-    /// Get currrent nonce of a user
-    pub fn get_user_nonce(&self, user: &AccountAddress) -> u64 {
-        self.nonces_registry.get(user).map_or_else(
-            || 0,
-            |n| n.clone()
-        )
-    }
+    /// The accrued rewards in the display currency of the price oracle.
+    /// Zero when the price is unavailable.
+    pub value: u64,
+
+    /// Whether the price oracle returned a price.
+    pub price_available: bool,
 }
 
-/// The concordium liquid staking smart contract errors.
-#[derive(Debug, PartialEq, Eq, Clone, Reject, Serialize, SchemaType)]
-pub enum Error {
-    /// Failed Parsing The Parameter.
-    #[from(ParseError)]
-    ParseParams, // -1
+/// Stake info results
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct StakeInfoResult {
+    /// The stake with the rewards accrued up to the current time.
+    pub stake: StakeInfo,
 
-    /// Prevent Unauthorized Access
-    UnAuthorized, // -2
+    /// The principal earning rewards, equal to `stake.amount`.
+    pub earning_amount: u64,
 
-    /// Invalid Stake Amount
-    InvalidStakeAmount, // -3
+    /// The principal in the unbonding queue, which earns no rewards.
+    pub unbonding_amount: u64,
+}
 
-    /// No Stake Found
-    NoStakeFound, // -4
+/// A single deposit within a stake.
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct Position {
+    /// The staked amount of the deposit.
+    pub amount: u64,
 
-    /// OnlyAccount
-    OnlyAccount, // -5
+    /// Timestamp when the deposit was made.
+    pub timestamp: u64,
 
-    /// Only Admin Access
-    OnlyAdmin, // -6
+    /// Rewards accrued by the deposit and not claimed yet
+    pub pending_rewards: u64,
 
-    /// Raised when the invocation of the cis2 token contract fails.
-    InvokeContractError, //-7
+    /// Timestamp until which the deposit cannot be unstaked
+    pub lock_until: u64,
 
-    /// Raised when the parsing of the result from the cis2 token contract
-    /// fails.
-    ParseResult, //-8
+    /// Bonus APR in basis points earned until `lock_until`
+    pub bonus_bps: u64,
 
-    /// Raised when the response of the cis2 token contract is invalid.
-    InvalidResponse, //-9
+    /// Fraction of a token unit accrued but not added to `pending_rewards`,
+    /// scaled by `REWARD_PER_TOKEN_PRECISION`. Negative after rounding up.
+    pub reward_remainder: i128,
+}
 
-    /// Failed logging: Log is full.
-    LogFull, // -10
+/// The rewards owed to all stakers, tracked on every change of a stake so the
+/// total is known without iterating over the stakes. It covers the accrual of
+/// every stake at its full rate and one token unit per position for rounding,
+/// so it is an upper bound of the rewards that accrual caps, ended lock
+/// bonuses and rounding actually pay.
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct RewardLiability {
+    /// Pending rewards of all stakes and their allowance for rounding
+    pending_rewards: u64,
+
+    /// Principal earning at the global APR
+    accumulator_amount: u64,
+
+    /// Rewards accrued at the global APR since the stakes were last
+    /// checkpointed, scaled by `REWARD_PER_TOKEN_PRECISION`
+    accumulator_rewards: u128,
+
+    /// Principal times APR in basis points of stakes earning at a locked APR
+    /// or a lock bonus
+    fixed_rate: u128,
+
+    /// Rewards accrued at locked APRs and lock bonuses since the stakes were
+    /// last checkpointed, scaled by `SECONDS_PER_YEAR * APR_DENOMINATOR`
+    fixed_rewards: u128,
+}
 
-    /// Failed logging: Log is malformed.
-    LogMalformed, // -11
+/// Implementation of the reward liability
+impl RewardLiability {
+    /// Accrue the liability over `elapsed` seconds in which the reward per
+    /// token accumulator grew by `reward_per_token_delta`
+    pub fn advance(&mut self, reward_per_token_delta: u128, elapsed: u64) {
+        self.accumulator_rewards = self.accumulator_rewards.saturating_add(
+            (self.accumulator_amount as u128).saturating_mul(reward_per_token_delta)
+        );
+        self.fixed_rewards = self.fixed_rewards.saturating_add(
+            self.fixed_rate.saturating_mul(elapsed as u128)
+        );
+    }
 
-    /// Upgrade failed because the new module does not exist.
-    FailedUpgradeMissingModule, // -12
+    /// Start tracking `stake`, given the reward per token accumulator at
+    /// `current_time`
+    pub fn add(&mut self, stake: &StakeInfo, reward_per_token: u128, current_time: u64) {
+        let (accumulator_amount, fixed_rate) = stake.reward_rates();
+        self.pending_rewards = self.pending_rewards.saturating_add(stake.owed_rewards());
+        self.accumulator_amount = self.accumulator_amount.saturating_add(accumulator_amount);
+        self.accumulator_rewards = self.accumulator_rewards.saturating_add(
+            (accumulator_amount as u128).saturating_mul(
+                reward_per_token.saturating_sub(stake.reward_per_token_paid)
+            )
+        );
+        self.fixed_rate = self.fixed_rate.saturating_add(fixed_rate);
+        self.fixed_rewards = self.fixed_rewards.saturating_add(
+            fixed_rate.saturating_mul(current_time.saturating_sub(stake.timestamp) as u128)
+        );
+    }
 
-    /// Upgrade failed because the new module does not contain a contract with a
-    /// matching name.
-    FailedUpgradeMissingContract, // -13
+    /// Stop tracking `stake`, given the reward per token accumulator at
+    /// `current_time`. Must be called before the stake changes.
+    pub fn remove(&mut self, stake: &StakeInfo, reward_per_token: u128, current_time: u64) {
+        let (accumulator_amount, fixed_rate) = stake.reward_rates();
+        self.pending_rewards = self.pending_rewards.saturating_sub(stake.owed_rewards());
+        self.accumulator_amount = self.accumulator_amount.saturating_sub(accumulator_amount);
+        self.accumulator_rewards = self.accumulator_rewards.saturating_sub(
+            (accumulator_amount as u128).saturating_mul(
+                reward_per_token.saturating_sub(stake.reward_per_token_paid)
+            )
+        );
+        self.fixed_rate = self.fixed_rate.saturating_sub(fixed_rate);
+        self.fixed_rewards = self.fixed_rewards.saturating_sub(
+            fixed_rate.saturating_mul(current_time.saturating_sub(stake.timestamp) as u128)
+        );
+    }
 
-    /// Upgrade failed because the smart contract version of the module is not
-    /// supported.
-    FailedUpgradeUnsupportedModuleVersion, // -14
+    /// Get the rewards owed after accruing as in `advance`, rounded up
+    pub fn total(&self, reward_per_token_delta: u128, elapsed: u64) -> u64 {
+        let mut liability = self.clone();
+        liability.advance(reward_per_token_delta, elapsed);
+        let accumulator_rewards = liability.accumulator_rewards.div_ceil(
+            REWARD_PER_TOKEN_PRECISION
+        );
+        let fixed_rewards = liability.fixed_rewards.div_ceil(SECONDS_PER_YEAR * APR_DENOMINATOR);
+        (liability.pending_rewards as u128)
+            .saturating_add(accumulator_rewards)
+            .saturating_add(fixed_rewards)
+            .try_into()
+            .unwrap_or(u64::MAX)
+    }
+}
 
-    // Contract is paused.
-    ContractPaused, // -15
+/// Information about a stake.
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct StakeInfo {
+    /// The staked amount of user, the sum over all positions.
+    pub amount: u64,
 
-    /// Insufficient funds
-    InsufficientFunds, // -16
+    /// Timestamp when rewards were last moved into `pending_rewards`.
+    pub timestamp: u64,
 
-    /// Raised when someone else than the cis2 token contract invokes the `stake`
-    /// entry point.
-    NotTokenContract, //-17
+    /// Unbonding information. Unbonding funds are not part of `amount` or of
+    /// any position, so they earn no rewards.
+    pub unbonding: Vec<UnbondingInfo>,
 
-    /// Failed to verify signature because signer account does not exist on
-    /// chain.
-    MissingAccount, // -18
+    /// Whether the stake is slashed
+    pub slashed: bool,
 
-    /// Failed to verify signature because data was malformed.
-    MalformedData, // -19
+    /// Pending rewards, the sum over all positions
+    pub pending_rewards: u64,
 
-    /// Failed signature verification: Invalid signature.
-    WrongSignature, // -20
+    /// APR locked in at deposit time, overriding the global APR
+    pub locked_apr: Option<u64>,
 
-    /// Failed signature verification: A different nonce is expected.
-    NonceMismatch, // -21
+    /// Value of the reward per token accumulator when rewards were last
+    /// moved into `pending_rewards`
+    pub reward_per_token_paid: u128,
 
-    /// Failed signature verification: Signature was intended for a different
-    /// contract.
-    WrongContract, // -22
+    /// The deposits making up the stake, oldest first
+    pub positions: Vec<Position>,
 
-    /// Failed signature verification: Signature was intended for a different
-    /// entry_point.
-    WrongEntryPoint, // -23
+    /// Rewards claimed or restaked over the lifetime of the account
+    pub lifetime_rewards_claimed: u64,
 
-    /// Failed signature verification: Signature is expired.
-    Expired, // -24
+    /// Timestamp of the last claim
+    pub last_claim_time: u64,
+}
 
-    /// Invalid unstake amount
-    InvalidUnstakeAmount,
+/// Implementation of stake info
+impl StakeInfo {
+    /// Create an empty stake checkpointed at `current_time`
+    pub fn new(current_time: u64, lifetime_rewards_claimed: u64) -> Self {
+        StakeInfo {
+            amount: 0,
+            timestamp: current_time,
+            unbonding: Vec::new(),
+            slashed: false,
+            pending_rewards: 0,
+            locked_apr: None,
+            reward_per_token_paid: 0,
+            positions: Vec::new(),
+            lifetime_rewards_claimed,
+            last_claim_time: 0,
+        }
+    }
 
-    /// Unbonding period not met
-    UnbondingPeriodNotMet,
+    /// Get the APR the stake earns at, given the global APR
+    pub fn effective_apr(&self, global_apr: u64) -> u64 {
+        self.locked_apr.unwrap_or(global_apr)
+    }
 
-    /// Already slashed
-    AlreadySlashed,
+    /// Get the amount in the unbonding queue
+    pub fn unbonding_amount(&self) -> u64 {
+        self.unbonding.iter().map(|u| u.amount.0).fold(0, u64::saturating_add)
+    }
 
-    /// Insufficient rewards pool
-    InsufficientRewardsPool,
+    /// Get the rewards accrued by `position` since the last checkpoint, given
+    /// the reward per token accumulator at `current_time`, and the remainder
+    /// to carry forward. Stakes with a locked APR accrue at their own rate
+    /// instead of the accumulator. Accrual stops `max_accrual_seconds` after
+    /// the last checkpoint. The rewards and the remainder carried so far are
+    /// rounded to token units as given by `rounding`. Only positions earn
+    /// rewards, funds in `unbonding` never do.
+    fn position_rewards(
+        &self,
+        position: &Position,
+        current_time: u64,
+        reward_per_token: u128,
+        max_accrual_seconds: Option<u64>,
+        rounding: RoundingMode
+    ) -> (u64, i128) {
+        let accrual_end = max_accrual_seconds.map_or(current_time, |max_accrual_seconds| {
+            current_time.min(self.timestamp.saturating_add(max_accrual_seconds))
+        });
+        let rewards = match self.locked_apr {
+            Some(apr) => calculate_reward(position.amount, self.timestamp, accrual_end, apr),
+            None => {
+                // The accumulator is only known at `current_time`, so keep the
+                // share of the rewards earned up to `accrual_end`
+                let rewards = calculate_accumulated_reward(
+                    position.amount,
+                    self.reward_per_token_paid,
+                    reward_per_token
+                );
+                let elapsed = current_time.saturating_sub(self.timestamp) as u128;
+                let accrued = accrual_end.saturating_sub(self.timestamp) as u128;
+                if accrued < elapsed {
+                    (rewards / elapsed) * accrued + (rewards % elapsed) * accrued / elapsed
+                } else {
+                    rewards
+                }
+            }
+        };
+        // Committed deposits earn the bonus until the lock ends
+        let bonus = calculate_reward(
+            position.amount,
+            self.timestamp,
+            accrual_end.min(position.lock_until),
+            position.bonus_bps
+        );
+        let rewards = i128::try_from(rewards.saturating_add(bonus)).unwrap_or(i128::MAX);
+        round_rewards(position.reward_remainder.saturating_add(rewards), rounding)
+    }
 
-    /// No rewards available to claim
-    NoRewardsAvailable,
-}
+    /// Get the rewards accrued by all positions since the last checkpoint
+    pub fn new_rewards(
+        &self,
+        current_time: u64,
+        reward_per_token: u128,
+        max_accrual_seconds: Option<u64>,
+        rounding: RoundingMode
+    ) -> u64 {
+        self.positions
+            .iter()
+            .map(|position| {
+                self.position_rewards(
+                    position,
+                    current_time,
+                    reward_per_token,
+                    max_accrual_seconds,
+                    rounding
+                ).0
+            })
+            .fold(0, u64::saturating_add)
+    }
 
-/// Mapping the logging errors to Error.
-impl From<LogError> for Error {
-    fn from(le: LogError) -> Self {
-        match le {
-            LogError::Full => Self::LogFull,
-            LogError::Malformed => Self::LogMalformed,
+    /// Move the rewards accrued up to `current_time` into `pending_rewards`
+    /// and restart accrual from `current_time`
+    pub fn accrue_rewards(
+        &mut self,
+        current_time: u64,
+        reward_per_token: u128,
+        max_accrual_seconds: Option<u64>,
+        rounding: RoundingMode
+    ) {
+        let new_rewards: Vec<(u64, i128)> = self.positions
+            .iter()
+            .map(|position| {
+                self.position_rewards(
+                    position,
+                    current_time,
+                    reward_per_token,
+                    max_accrual_seconds,
+                    rounding
+                )
+            })
+            .collect();
+        for (position, (new_rewards, remainder)) in self.positions.iter_mut().zip(new_rewards) {
+            position.pending_rewards = position.pending_rewards.saturating_add(new_rewards);
+            position.reward_remainder = remainder;
+            self.pending_rewards = self.pending_rewards.saturating_add(new_rewards);
         }
+        self.timestamp = current_time;
+        self.reward_per_token_paid = reward_per_token;
     }
-}
 
-/// Mapping Cis2ClientError<Error> to Error.
-impl From<Cis2ClientError<Error>> for Error {
-    fn from(e: Cis2ClientError<Error>) -> Self {
-        match e {
-            Cis2ClientError::InvokeContractError(_) =>
-                Self::InvokeContractError,
-            Cis2ClientError::ParseResult => Self::ParseResult,
-            Cis2ClientError::InvalidResponse => Self::InvalidResponse,
+    /// Add a deposit of `amount` made at `current_time`, locked until
+    /// `lock_until` for `bonus_bps`. Rewards must be accrued first.
+    /// Unlocked positions earn alike, so they are merged into one and only
+    /// locked deposits count towards `MAX_POSITIONS`.
+    pub fn add_position(
+        &mut self,
+        amount: u64,
+        current_time: u64,
+        lock_until: u64,
+        bonus_bps: u64
+    ) -> ContractResult<()> {
+        self.merge_unlocked_positions(current_time);
+        let unlocked = self.positions
+            .iter_mut()
+            .find(|position| position.lock_until <= current_time);
+        match unlocked {
+            Some(position) if lock_until <= current_time => {
+                position.amount = position.amount.saturating_add(amount);
+            }
+            _ => {
+                ensure!(self.positions.len() < MAX_POSITIONS, Error::TooManyPositions);
+                self.positions.push(Position {
+                    amount,
+                    timestamp: current_time,
+                    pending_rewards: 0,
+                    lock_until,
+                    bonus_bps,
+                    reward_remainder: 0,
+                });
+            }
         }
+        self.amount = self.amount.saturating_add(amount);
+        Ok(())
     }
-}
 
-/// Mapping UpgradeError to Error
-impl From<UpgradeError> for Error {
-    #[inline(always)]
-    fn from(ue: UpgradeError) -> Self {
-        match ue {
-            UpgradeError::MissingModule => Self::FailedUpgradeMissingModule,
-            UpgradeError::MissingContract => Self::FailedUpgradeMissingContract,
-            UpgradeError::UnsupportedModuleVersion =>
-                Self::FailedUpgradeUnsupportedModuleVersion,
-        }
+    /// Merge the positions whose lock has ended by `current_time` into the
+    /// oldest of them. Rewards must be accrued first.
+    fn merge_unlocked_positions(&mut self, current_time: u64) {
+        let (unlocked, locked): (Vec<Position>, Vec<Position>) = self.positions
+            .drain(..)
+            .partition(|position| position.lock_until <= current_time);
+        let merged = unlocked.into_iter().reduce(|mut merged, position| {
+            merged.amount = merged.amount.saturating_add(position.amount);
+            merged.pending_rewards = merged.pending_rewards.saturating_add(
+                position.pending_rewards
+            );
+            merged.reward_remainder = merged.reward_remainder.saturating_add(
+                position.reward_remainder
+            );
+            merged
+        });
+        self.positions = merged.into_iter().chain(locked).collect();
     }
-}
 
-/// Mapping of errors related to contract invocations to Error.
-impl<T> From<CallContractError<T>> for Error {
-    fn from(_cce: CallContractError<T>) -> Self {
-        Self::InvokeContractError
+    /// Get the principal of positions whose lock has ended by `current_time`
+    pub fn unlocked_amount(&self, current_time: u64) -> u64 {
+        self.positions
+            .iter()
+            .filter(|position| position.lock_until <= current_time)
+            .fold(0, |acc, position| acc.saturating_add(position.amount))
     }
-}
 
-/// Mapping account signature error to CustomContractError
-impl From<CheckAccountSignatureError> for Error {
-    fn from(e: CheckAccountSignatureError) -> Self {
-        match e {
-            CheckAccountSignatureError::MissingAccount => Self::MissingAccount,
-            CheckAccountSignatureError::MalformedData => Self::MalformedData,
+    /// Remove `amount` of principal, taking from the newest unlocked
+    /// positions first and then from locked ones. Positions keep their
+    /// pending rewards until claimed.
+    pub fn remove_principal(&mut self, amount: u64, current_time: u64) {
+        let mut remaining = amount;
+        for locked in [false, true] {
+            for position in self.positions.iter_mut().rev() {
+                if (position.lock_until > current_time) == locked {
+                    let taken = position.amount.min(remaining);
+                    position.amount -= taken;
+                    remaining -= taken;
+                }
+            }
         }
+        self.amount = self.amount.saturating_sub(amount);
+        self.prune_positions();
     }
-}
-
-/// Enum for different event types in the contract.
-#[derive(Debug, Serial, Deserial, PartialEq, Eq, SchemaType)]
-#[concordium(repr(u8))]
-pub enum Event {
-    /// Event for when tokens are staked.
-    Staked(StakeEvent),
 
-    /// Event for when tokens are unstaked.
-    Unstaked(UnstakeEvent),
+    /// Remove `amount` of pending rewards, taking from the oldest positions
+    /// first.
+    pub fn take_rewards(&mut self, amount: u64) {
+        let mut remaining = amount;
+        for position in self.positions.iter_mut() {
+            let taken = position.pending_rewards.min(remaining);
+            position.pending_rewards -= taken;
+            remaining -= taken;
+        }
+        self.pending_rewards = self.pending_rewards.saturating_sub(amount);
+        self.prune_positions();
+    }
 
-    /// Event for when rewards are claimed.
-    Claimed(ClaimEvent),
+    /// Drop positions without principal or pending rewards
+    fn prune_positions(&mut self) {
+        self.positions.retain(|position| position.amount > 0 || position.pending_rewards > 0);
+    }
 
-    /// Event for when APR is updated.
-    AprUpdated(UpdateAprEvent),
-    /// Cis3 event.
-    /// The event tracks the nonce used by the signer of the `PermitMessage`
-    /// whenever the `permit` function is invoked.
-    #[concordium(tag = 250)]
-    Nonce(NonceEvent),
-}
+    /// Get the principal earning at the global APR and the principal times
+    /// APR earning at a locked APR or lock bonus, as tracked by
+    /// `RewardLiability`. Bonuses count while the lock outlasts the last
+    /// checkpoint.
+    fn reward_rates(&self) -> (u64, u128) {
+        let accumulator_amount = if self.locked_apr.is_none() { self.amount } else { 0 };
+        let locked_rate = self.locked_apr.map_or(0, |apr| (self.amount as u128) * (apr as u128));
+        let bonus_rate = self.positions
+            .iter()
+            .filter(|position| position.lock_until > self.timestamp)
+            .fold(0u128, |acc, position| {
+                acc.saturating_add((position.amount as u128) * (position.bonus_bps as u128))
+            });
+        (accumulator_amount, locked_rate.saturating_add(bonus_rate))
+    }
 
-/// Event structure for staking.
-#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
-pub struct StakeEvent {
-    /// Address of the user who staked.
-    user: AccountAddress,
+    /// Get the pending rewards and one token unit per position, the most the
+    /// carried remainders can add once rounded, as tracked by
+    /// `RewardLiability`
+    fn owed_rewards(&self) -> u64 {
+        self.pending_rewards.saturating_add(self.positions.len() as u64)
+    }
 
-    /// Amount of tokens staked.
-    stake_amount: TokenAmountU64,
+    /// Whether the staker counts towards `total_participants`, i.e. has an
+    /// active stake or funds still unbonding
+    pub fn is_participant(&self) -> bool {
+        self.amount > 0 || !self.unbonding.is_empty()
+    }
 
-    /// Timestamp when the stake was made.
-    staked_timestamp: u64,
+    /// Whether nothing is left in the stake, neither funds nor rewards. A
+    /// slashed stake is never empty, so the account stays barred from staking.
+    pub fn is_empty(&self) -> bool {
+        !self.slashed && !self.is_participant() && self.pending_rewards == 0
+    }
 }
 
-/// Event structure for unstaking.
-#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
-pub struct UnstakeEvent {
-    /// Address of the user who unstaked.
-    user: AccountAddress,
-
-    /// Amount of tokens unstaked.
-    unstaked_amount: TokenAmountU64,
-
-    /// Timestamp when the unstake was made.
-    unix_timestamp: u64,
+/// Unbonding information
+#[derive(Debug, Serialize, SchemaType, Clone, PartialEq, Eq)]
+pub struct UnbondingInfo {
+    /// Amount to unbond
+    pub amount: TokenAmountU64,
 
-    /// Rewards earned by the user.
-    rewards_earned: TokenAmountU64,
+    /// Unlock time in seconds
+    pub unlock_time: u64,
 }
 
-/// Event structure for claiming rewards.
-#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
-pub struct ClaimEvent {
-    /// Address of the user who claimed rewards.
-    user: AccountAddress,
+/// State of the contract.
+#[derive(Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+struct State<S = StateApi> {
+    /// Paused state for stopping relevant contract operations.
+    paused: PauseFlags,
 
-    /// Amount of rewards claimed.
-    rewards_claimed: TokenAmountU64,
+    /// The admin role of concordium liquid staking smart contract.
+    admin: AccountAddress,
 
-    /// Timestamp when the claim was made.
-    claim_timestamp: u64,
-}
+    /// The total amount of staked tokens.
+    total_staked: TokenAmountU64,
 
-/// Event structure for updating APR.
-#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
-pub struct UpdateAprEvent {
-    /// New APR value.
-    new_apr: u64,
+    /// The annual percentage rate in basis points (see `APR_DENOMINATOR`).
+    apr: u64,
 
-    /// Timestamp when the APR was updated.
-    update_timestamp: u64,
-}
+    /// Mapping of staker addresses to their stake info.
+    stakes: StateMap<AccountAddress, StakeInfo, S>,
 
-/// The NonceEvent is logged when the `permit` function is invoked. The event
-/// tracks the nonce used by the signer of the `PermitMessage`.
-#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
-pub struct NonceEvent {
-    /// The nonce that was used in the `PermitMessage`.
-    pub nonce: u64,
-    /// Account that signed the `PermitMessage`.
-    pub account: AccountAddress,
-}
+    /// Address of the EUROe token contract.
+    token_address: ContractAddress,
 
-/// Contract token ID type. It has to be the `ContractTokenId` from the cis2
-/// token contract.
-pub type ContractTokenId = TokenIdUnit;
+    /// The total number of participants
+    total_participants: u64,
 
-/// ContractResult type.
-pub type ContractResult<A> = Result<A, Error>;
+    /// A registry to link an account to its next nonce.
+    nonces_registry: StateMap<AccountAddress, u64, S>,
 
-/// Initialization function for the contract.
+    /// Unbonding period in seconds
+    unbonding_period: u64,
+
+    /// Slashing rate in basis points (1% = 100)
+    slashing_rate: u64,
+
+    /// Track available rewards
+    rewards_pool: TokenAmountU64,
+
+    /// Track total rewards paid to users
+    total_rewards_paid: TokenAmountU64,
+
+    /// Optional price oracle used to value rewards in a display currency
+    price_oracle: Option<ContractAddress>,
+
+    /// Whether new stakes lock in the APR at deposit time
+    apr_locking: bool,
+
+    /// Epoch configuration used for unbonding instead of `unbonding_period`
+    epoch_config: Option<EpochConfig>,
+
+    /// The total amount of tokens queued for unbonding.
+    total_unbonding: TokenAmountU64,
+
+    /// The account proposed to become the next admin.
+    pending_admin: Option<AccountAddress>,
+
+    /// The maximum amount of rewards paid by a single claim
+    max_reward_per_claim: Option<TokenAmountU64>,
+
+    /// Rewards accrued per staked token at the global APR up to
+    /// `last_update_time`, scaled by `REWARD_PER_TOKEN_PRECISION`
+    reward_per_token_stored: u128,
+
+    /// Timestamp when `reward_per_token_stored` was last updated
+    last_update_time: u64,
+
+    /// The minimum active stake an account must hold after staking
+    minimum_stake: TokenAmountU64,
+
+    /// Set while a token transfer is in progress to reject reentrant calls
+    locked: bool,
+
+    /// The lock durations stakers can commit to for a bonus APR
+    lock_tiers: Vec<LockTier>,
+
+    /// The version of the contract, see `CONTRACT_VERSION`
+    version: u16,
+
+    /// The maximum number of seconds rewards accrue for without the staker
+    /// interacting, bounding the rewards owed by the contract
+    max_accrual_seconds: Option<u64>,
+
+    /// Lifetime claimed rewards of accounts whose stake was removed, restored
+    /// when they stake again
+    lifetime_rewards_archive: StateMap<AccountAddress, u64, S>,
+
+    /// Protocol fee on claimed rewards in basis points
+    reward_fee_bps: u64,
+
+    /// The account receiving the protocol fee
+    fee_recipient: AccountAddress,
+
+    /// The APR changes as (effective from, APR), oldest first and bounded by
+    /// `MAX_APR_HISTORY`
+    apr_history: Vec<(u64, u64)>,
+
+    /// The maximum total stake accepted by `stake`, 0 for unlimited
+    max_total_staked: TokenAmountU64,
+
+    /// The maximum change of the APR per update in basis points
+    max_apr_change_bps: Option<u64>,
+
+    /// The minimum number of seconds between APR updates
+    min_apr_update_interval: u64,
+
+    /// Timestamp of the last APR update
+    last_apr_update: u64,
+
+    /// Rewards funded with vesting that are not in the rewards pool yet
+    locked_rewards_pool: TokenAmountU64,
+
+    /// Timestamp up to which vested rewards were released
+    vesting_checkpoint: u64,
+
+    /// Timestamp at which all locked rewards are released
+    vest_until: u64,
+
+    /// The minimum number of seconds between two claims of a staker
+    claim_cooldown_seconds: u64,
+
+    /// Accounts frozen by the admin, which cannot use the contract
+    frozen: StateSet<AccountAddress, S>,
+
+    /// Timestamp of the last successful call of a mutable entrypoint
+    last_mutation_time: u64,
+
+    /// Rounding of the rewards computed from an APR
+    reward_rounding: RoundingMode,
+
+    /// The maximum APR accepted by `updateApr`, `None` for no limit
+    max_apr: Option<u64>,
+
+    /// Whether `updateApr` rejects an APR of zero
+    forbid_zero_apr: bool,
+
+    /// Number of events logged, the sequence number of the next event
+    event_seq: u64,
+
+    /// The latest slashes, oldest first and bounded by `MAX_SLASH_HISTORY`
+    slash_history: Vec<SlashRecord>,
+
+    /// Accounts that cannot be slashed, besides the admin
+    slash_exempt: StateSet<AccountAddress, S>,
+
+    /// Whether a claim or restake the rewards pool cannot cover pauses reward
+    /// payouts instead of failing
+    auto_pause_on_depletion: bool,
+
+    /// Operators of the receipt token as (owner, operator)
+    operators: StateSet<(AccountAddress, Address), S>,
+
+    /// The metadata URL of the receipt token
+    receipt_metadata_url: MetadataUrl,
+
+    /// The rewards owed to all stakers
+    reward_liability: RewardLiability,
+}
+
+/// Information about a stake in the layout of the first module, which
+/// `migrate` converts. Frozen: it must not change with `StakeInfo`.
+#[derive(Serialize, Clone)]
+struct StakeInfoV0 {
+    amount: u64,
+    timestamp: u64,
+    unbonding: Vec<UnbondingInfo>,
+    slashed: bool,
+    pending_rewards: u64,
+}
+
+/// Implementation of the first stake layout
+impl StakeInfoV0 {
+    /// Convert the stake into a single unlocked position, settling the
+    /// rewards accrued at `apr` up to `current_time` into its pending rewards
+    fn migrate(self, current_time: u64, apr: u64) -> StakeInfo {
+        let (accrued, remainder) = round_rewards(
+            calculate_reward(self.amount, self.timestamp, current_time, apr) as i128,
+            RoundingMode::Floor
+        );
+        let mut stake = StakeInfo::new(current_time, 0);
+        stake.amount = self.amount;
+        stake.unbonding = self.unbonding;
+        stake.slashed = self.slashed;
+        stake.pending_rewards = self.pending_rewards.saturating_add(accrued);
+        stake.positions.push(Position {
+            amount: self.amount,
+            timestamp: self.timestamp,
+            pending_rewards: stake.pending_rewards,
+            lock_until: 0,
+            bonus_bps: 0,
+            reward_remainder: remainder,
+        });
+        stake.prune_positions();
+        stake
+    }
+}
+
+/// State of the contract in the layout of the first module, which `migrate`
+/// converts. Frozen: it must not change with `State`.
+#[derive(DeserialWithState)]
+#[concordium(state_parameter = "S")]
+struct StateV0<S = StateApi> {
+    paused: bool,
+    admin: AccountAddress,
+    total_staked: TokenAmountU64,
+    apr: u64,
+    stakes: StateMap<AccountAddress, StakeInfoV0, S>,
+    token_address: ContractAddress,
+    // Recounted from the stakes
+    _total_participants: u64,
+    nonces_registry: StateMap<AccountAddress, u64, S>,
+    unbonding_period: u64,
+    slashing_rate: u64,
+    rewards_pool: TokenAmountU64,
+    total_rewards_paid: TokenAmountU64,
+}
+
+/// Implementation of the first state layout
+impl StateV0 {
+    /// Convert the state into the current layout at `current_time`. The
+    /// stakes move to a new map, and the totals the first module did not
+    /// track, including the reward liability, are rebuilt from them.
+    fn migrate(mut self, state_builder: &mut StateBuilder, current_time: u64) -> State {
+        let mut state = State::new(
+            self.admin,
+            self.token_address,
+            self.unbonding_period,
+            self.slashing_rate,
+            current_time,
+            state_builder
+        );
+        state.paused = PauseFlags::all(self.paused);
+        state.total_staked = self.total_staked;
+        state.apr = self.apr;
+        state.apr_history = vec![(current_time, self.apr)];
+        state.nonces_registry = self.nonces_registry;
+        state.rewards_pool = self.rewards_pool;
+        state.total_rewards_paid = self.total_rewards_paid;
+
+        // Collect first, the old entries are deleted before the new ones
+        // are written
+        let stakes: Vec<(AccountAddress, StakeInfoV0)> = self.stakes
+            .iter()
+            .map(|(account, stake)| (*account, stake.clone()))
+            .collect();
+        self.stakes.clear();
+
+        for (account, stake) in stakes {
+            let stake = stake.migrate(current_time, state.apr);
+            state.reward_liability.add(&stake, state.reward_per_token_stored, current_time);
+            state.total_unbonding += TokenAmountU64(stake.unbonding_amount());
+            if stake.is_participant() {
+                state.total_participants += 1;
+            }
+            let _ = state.stakes.insert(account, stake);
+        }
+        state
+    }
+}
+
+/// Implementation of state
+impl State {
+    /// Create the state of a contract without stakes at `current_time`
+    pub fn new(
+        admin: AccountAddress,
+        token_address: ContractAddress,
+        unbonding_period: u64,
+        slashing_rate: u64,
+        current_time: u64,
+        state_builder: &mut StateBuilder
+    ) -> Self {
+        State {
+            paused: PauseFlags::default(),
+            admin,
+            total_staked: TokenAmountU64(0),
+            total_participants: 0,
+            apr: INITIAL_APR,
+            stakes: state_builder.new_map(),
+            token_address,
+            nonces_registry: state_builder.new_map(),
+            unbonding_period,
+            slashing_rate,
+            rewards_pool: TokenAmountU64(0),
+            total_rewards_paid: TokenAmountU64(0),
+            price_oracle: None,
+            apr_locking: false,
+            epoch_config: None,
+            total_unbonding: TokenAmountU64(0),
+            pending_admin: None,
+            max_reward_per_claim: None,
+            reward_per_token_stored: 0,
+            last_update_time: current_time,
+            minimum_stake: TokenAmountU64(0),
+            locked: false,
+            lock_tiers: Vec::new(),
+            version: CONTRACT_VERSION,
+            max_accrual_seconds: None,
+            lifetime_rewards_archive: state_builder.new_map(),
+            reward_fee_bps: 0,
+            fee_recipient: admin,
+            apr_history: vec![(current_time, INITIAL_APR)],
+            max_total_staked: TokenAmountU64(0),
+            max_apr_change_bps: None,
+            min_apr_update_interval: 0,
+            last_apr_update: current_time,
+            locked_rewards_pool: TokenAmountU64(0),
+            vesting_checkpoint: 0,
+            vest_until: 0,
+            claim_cooldown_seconds: 0,
+            frozen: state_builder.new_set(),
+            last_mutation_time: current_time,
+            reward_rounding: RoundingMode::Floor,
+            max_apr: None,
+            forbid_zero_apr: false,
+            event_seq: 0,
+            slash_history: Vec::new(),
+            slash_exempt: state_builder.new_set(),
+            auto_pause_on_depletion: false,
+            operators: state_builder.new_set(),
+            receipt_metadata_url: MetadataUrl {
+                url: String::new(),
+                hash: None,
+            },
+            reward_liability: RewardLiability::default(),
+        }
+    }
+
+    /// Record `now` as the time of the last state change. Called by every
+    /// mutable entrypoint once it succeeded.
+    pub fn touch(&mut self, now: u64) {
+        self.last_mutation_time = now;
+    }
+
+    /// Log `event`, with the next event sequence number if it is a custom
+    /// event
+    pub fn log_event(&mut self, logger: &mut Logger, event: Event) -> ContractResult<()> {
+        if event.is_standard() {
+            logger.log(&event)?;
+            return Ok(());
+        }
+        logger.log(&SequencedEvent {
+            event,
+            event_seq: self.event_seq,
+        })?;
+        self.event_seq += 1;
+        Ok(())
+    }
+
+    /// Get user stake info
+    pub fn get_user_stake(
+        &self,
+        user: &AccountAddress
+    ) -> (TokenAmountU64, u64) {
+        self.stakes.get(user).map_or_else(
+            || (TokenAmountU64(0), 0),
+            |s| (TokenAmountU64(s.amount), s.timestamp)
+        )
+    }
+
+    /// Get the rewards accrued by a user up to `current_time`
+    pub fn get_accrued_rewards(
+        &self,
+        user: &AccountAddress,
+        current_time: u64
+    ) -> u64 {
+        let reward_per_token = self.reward_per_token(current_time);
+        self.stakes.get(user).map_or(0, |s| {
+            s.pending_rewards.saturating_add(
+                s.new_rewards(
+                    current_time,
+                    reward_per_token,
+                    self.max_accrual_seconds,
+                    self.reward_rounding
+                )
+            )
+        })
+    }
+
+    /// Get an upper bound of the rewards accrued by all stakers up to
+    /// `current_time`, see `RewardLiability`
+    pub fn get_total_accrued_rewards(&self, current_time: u64) -> u64 {
+        self.reward_liability.total(
+            self.reward_per_token(current_time).saturating_sub(self.reward_per_token_stored),
+            current_time.saturating_sub(self.last_update_time)
+        )
+    }
+
+    /// Get the reward per token accumulator at `current_time`
+    pub fn reward_per_token(&self, current_time: u64) -> u128 {
+        let elapsed = current_time.saturating_sub(self.last_update_time);
+        self.reward_per_token_stored.saturating_add(
+            (self.apr as u128)
+                .saturating_mul(elapsed as u128)
+                .saturating_mul(REWARD_PER_TOKEN_PRECISION)
+                .saturating_div(SECONDS_PER_YEAR * APR_DENOMINATOR)
+        )
+    }
+
+    /// Get the reward per token accumulator at `timestamp`. Accumulation
+    /// since a `timestamp` before the last checkpoint is unwound with the APR
+    /// history, so `None` is returned if the history no longer reaches back
+    /// that far.
+    pub fn reward_per_token_at(&self, timestamp: u64) -> Option<u128> {
+        if timestamp >= self.last_update_time {
+            return Some(self.reward_per_token(timestamp));
+        }
+        let mut unwound: u128 = 0;
+        let mut end = self.last_update_time;
+        for (effective_from, apr) in self.apr_history.iter().rev() {
+            let start = (*effective_from).max(timestamp);
+            if start < end {
+                unwound = unwound.saturating_add(
+                    (*apr as u128)
+                        .saturating_mul((end - start) as u128)
+                        .saturating_mul(REWARD_PER_TOKEN_PRECISION)
+                        .saturating_div(SECONDS_PER_YEAR * APR_DENOMINATOR)
+                );
+                end = start;
+            }
+            if *effective_from <= timestamp {
+                return Some(self.reward_per_token_stored.saturating_sub(unwound));
+            }
+        }
+        None
+    }
+
+    /// Checkpoint the reward per token accumulator at `current_time`.
+    /// Must be called before the APR changes.
+    pub fn update_reward_per_token(&mut self, current_time: u64) -> u128 {
+        let reward_per_token = self.reward_per_token(current_time);
+        self.reward_liability.advance(
+            reward_per_token.saturating_sub(self.reward_per_token_stored),
+            current_time.saturating_sub(self.last_update_time)
+        );
+        self.reward_per_token_stored = reward_per_token;
+        self.last_update_time = self.last_update_time.max(current_time);
+        self.reward_per_token_stored
+    }
+
+    /// Get the unlock time of funds unstaked at `current_time`.
+    /// With an epoch configuration, funds unlock at the start of the epoch
+    /// following the current one plus `min_unbonding_epochs`. Otherwise they
+    /// unlock after `unbonding_period` seconds.
+    pub fn get_unlock_time(&self, current_time: u64) -> ContractResult<u64> {
+        match self.epoch_config {
+            Some(config) => {
+                let current_epoch = current_time
+                    .saturating_sub(config.genesis_time)
+                    .saturating_div(config.epoch_length);
+                let unlock_epoch = current_epoch
+                    .saturating_add(1)
+                    .saturating_add(config.min_unbonding_epochs);
+                Ok(
+                    config.genesis_time.saturating_add(
+                        unlock_epoch.saturating_mul(config.epoch_length)
+                    )
+                )
+            }
+            None =>
+                current_time
+                    .checked_add(self.unbonding_period)
+                    .ok_or(Error::ArithmeticOverflow),
+        }
+    }
+
+    /// Get the amount of EUROe the contract owes to stakers, including the
+    /// rewards pool
+    pub fn get_total_liabilities(&self) -> u64 {
+        self.total_staked.0
+            .saturating_add(self.total_unbonding.0)
+            .saturating_add(self.rewards_pool.0)
+            .saturating_add(self.locked_rewards_pool.0)
+    }
+
+    /// Update `total_participants` after a stake changed from
+    /// `was_participant` to `is_participant`
+    pub fn update_participants(
+        &mut self,
+        was_participant: bool,
+        is_participant: bool
+    ) {
+        if !was_participant && is_participant {
+            self.total_participants = self.total_participants.saturating_add(1);
+        } else if was_participant && !is_participant {
+            self.total_participants = self.total_participants.saturating_sub(1);
+        }
+    }
+
+    /// Remove the stake of `user` if it is empty. Empty stakes are not counted
+    /// as participants, so `total_participants` is unchanged.
+    pub fn remove_empty_stake(&mut self, user: &AccountAddress) {
+        if self.stakes.get(user).is_some_and(|s| s.is_empty()) {
+            self.remove_stake(user);
+        }
+    }
+
+    /// Remove the stake of `user`, keeping their lifetime claimed rewards
+    pub fn remove_stake(&mut self, user: &AccountAddress) {
+        if let Some(stake_info) = self.stakes.remove_and_get(user) {
+            if stake_info.lifetime_rewards_claimed > 0 {
+                let _ = self.lifetime_rewards_archive
+                    .insert(*user, stake_info.lifetime_rewards_claimed);
+            }
+        }
+    }
+
+    /// Move the locked rewards that vested by `now` into the rewards pool.
+    /// The locked rewards are released linearly until `vest_until`.
+    pub fn release_vested_rewards(&mut self, now: u64) {
+        if self.locked_rewards_pool.0 == 0 || now <= self.vesting_checkpoint {
+            return;
+        }
+
+        let released = if now >= self.vest_until {
+            self.locked_rewards_pool.0
+        } else {
+            ((self.locked_rewards_pool.0 as u128) * ((now - self.vesting_checkpoint) as u128) /
+                ((self.vest_until - self.vesting_checkpoint) as u128)) as u64
+        };
+
+        self.locked_rewards_pool.0 -= released;
+        self.rewards_pool.0 = self.rewards_pool.0.saturating_add(released);
+        self.vesting_checkpoint = now;
+    }
+
+    /// Lock `amount` of rewards vesting until `vest_until`. The rewards still
+    /// locked are merged and vest until the later of both timestamps.
+    pub fn add_vested_rewards(&mut self, amount: u64, now: u64, vest_until: u64) {
+        self.release_vested_rewards(now);
+        self.locked_rewards_pool.0 = self.locked_rewards_pool.0.saturating_add(amount);
+        self.vesting_checkpoint = now;
+        self.vest_until = self.vest_until.max(vest_until);
+    }
+
+    /// Append an APR change to the history, pruning the oldest entries
+    pub fn record_apr(&mut self, effective_from: u64, apr: u64) {
+        self.apr_history.push((effective_from, apr));
+        if self.apr_history.len() > MAX_APR_HISTORY {
+            let excess = self.apr_history.len() - MAX_APR_HISTORY;
+            self.apr_history.drain(..excess);
+        }
+    }
+
+    /// Append a slash to the history, pruning the oldest entries
+    pub fn record_slash(&mut self, record: SlashRecord) {
+        self.slash_history.push(record);
+        if self.slash_history.len() > MAX_SLASH_HISTORY {
+            let excess = self.slash_history.len() - MAX_SLASH_HISTORY;
+            self.slash_history.drain(..excess);
+        }
+    }
+
+    /// Get the APR effective at `timestamp`, or `None` if the history no
+    /// longer reaches back that far
+    pub fn get_apr_at(&self, timestamp: u64) -> Option<u64> {
+        self.apr_history
+            .iter()
+            .rev()
+            .find(|(effective_from, _)| *effective_from <= timestamp)
+            .map(|(_, apr)| *apr)
+    }
+
+    /// Pause reward payouts because the rewards pool cannot cover the
+    /// `requested` rewards of `user`
+    pub fn pause_on_depletion(
+        &mut self,
+        logger: &mut Logger,
+        user: AccountAddress,
+        requested: TokenAmountU64,
+        now: u64
+    ) -> ContractResult<()> {
+        self.paused.rewards_paused = true;
+        let rewards_pool = self.rewards_pool;
+        self.log_event(logger, Event::PoolDepleted(PoolDepletedEvent {
+            user,
+            requested,
+            rewards_pool,
+            depletion_timestamp: now,
+        }))
+    }
+
+    /// Whether `address` is an operator of `owner` for the receipt token
+    pub fn is_operator(&self, address: &Address, owner: &AccountAddress) -> bool {
+        self.operators.contains(&(*owner, *address))
+    }
+
+    /// Log the mint of `amount` receipt tokens to `owner`
+    pub fn log_receipt_mint(
+        &mut self,
+        logger: &mut Logger,
+        owner: AccountAddress,
+        amount: TokenAmountU64
+    ) -> ContractResult<()> {
+        self.log_event(logger, Event::ReceiptMint(MintEvent {
+            token_id: TOKEN_ID_RECEIPT,
+            amount,
+            owner: Address::Account(owner),
+        }))
+    }
+
+    /// Log the burn of `amount` receipt tokens of `owner`
+    pub fn log_receipt_burn(
+        &mut self,
+        logger: &mut Logger,
+        owner: AccountAddress,
+        amount: TokenAmountU64
+    ) -> ContractResult<()> {
+        self.log_event(logger, Event::ReceiptBurn(BurnEvent {
+            token_id: TOKEN_ID_RECEIPT,
+            amount,
+            owner: Address::Account(owner),
+        }))
+    }
+
+    /// Move `amount` of the active stake of `from` to `to` at `now`, which is
+    /// what a transfer of receipt tokens does. Rewards accrued so far stay
+    /// with `from`. The moved principal is an unlocked deposit of `to`, so it
+    /// earns at the rate of `to` from `now` on. Neither staker may be
+    /// slashed.
+    pub fn transfer_stake(
+        &mut self,
+        from: AccountAddress,
+        to: AccountAddress,
+        amount: TokenAmountU64,
+        now: u64
+    ) -> ContractResult<()> {
+        ensure!(!self.paused.stake_paused && !self.paused.unstake_paused, Error::ContractPaused);
+        self.ensure_unlocked()?;
+        self.ensure_not_frozen(&from)?;
+        self.ensure_not_frozen(&to)?;
+        if amount.0 == 0 {
+            return Ok(());
+        }
+        let reward_per_token = self.update_reward_per_token(now);
+
+        // Take the principal from the sender
+        let mut from_stake = self.stakes.entry(from).occupied_or(Error::InsufficientFunds)?;
+        ensure!(!from_stake.slashed, Error::AlreadySlashed);
+        ensure!(from_stake.amount >= amount.0, Error::InsufficientFunds);
+        ensure!(from_stake.unlocked_amount(now) >= amount.0, Error::StakeLocked);
+        if from == to {
+            return Ok(());
+        }
+        self.reward_liability.remove(&from_stake, reward_per_token, now);
+        from_stake.accrue_rewards(
+            now,
+            reward_per_token,
+            self.max_accrual_seconds,
+            self.reward_rounding
+        );
+        from_stake.remove_principal(amount.0, now);
+        self.reward_liability.add(&from_stake, reward_per_token, now);
+        let from_is_participant = from_stake.is_participant();
+        drop(from_stake);
+        self.update_participants(true, from_is_participant);
+        self.remove_empty_stake(&from);
+
+        // The resulting stake of the receiver must reach the minimum stake
+        let to_amount = self.stakes.get(&to).map_or(0, |s| s.amount);
+        ensure!(
+            to_amount.saturating_add(amount.0) >= self.minimum_stake.0,
+            Error::BelowMinimumStake
+        );
+
+        // Add the principal to the receiver like a deposit
+        let to_was_participant = self.stakes.get(&to).is_some_and(|s| s.is_participant());
+        let lifetime_rewards_claimed = self.lifetime_rewards_archive
+            .remove_and_get(&to)
+            .unwrap_or(0);
+        let mut to_stake = self.stakes
+            .entry(to)
+            .or_insert_with(|| StakeInfo::new(now, lifetime_rewards_claimed));
+        ensure!(!to_stake.slashed, Error::AlreadySlashed);
+        self.reward_liability.remove(&to_stake, reward_per_token, now);
+        to_stake.accrue_rewards(
+            now,
+            reward_per_token,
+            self.max_accrual_seconds,
+            self.reward_rounding
+        );
+        if self.apr_locking || to_stake.locked_apr.is_some() {
+            to_stake.locked_apr = Some(
+                weighted_apr(to_stake.amount, to_stake.effective_apr(self.apr), amount.0, self.apr)
+            );
+        }
+        to_stake.add_position(amount.0, now, now, 0)?;
+        self.reward_liability.add(&to_stake, reward_per_token, now);
+        drop(to_stake);
+        self.update_participants(to_was_participant, true);
+        Ok(())
+    }
+
+    /// Reject the call if a token transfer is in progress
+    pub fn ensure_unlocked(&self) -> ContractResult<()> {
+        ensure!(!self.locked, Error::Reentrancy);
+        Ok(())
+    }
+
+    /// Reject the call if adding `amount` to the total stake exceeds the cap,
+    /// where a cap of 0 means unlimited
+    pub fn ensure_within_stake_cap(&self, amount: u64) -> ContractResult<()> {
+        ensure!(
+            self.max_total_staked.0 == 0 ||
+                self.total_staked.0.saturating_add(amount) <= self.max_total_staked.0,
+            Error::StakeCapExceeded
+        );
+        Ok(())
+    }
+
+    /// Reject the call if `account` is frozen
+    pub fn ensure_not_frozen(&self, account: &AccountAddress) -> ContractResult<()> {
+        ensure!(!self.frozen.contains(account), Error::AccountFrozen);
+        Ok(())
+    }
+
+    /// Get the stake of a user with pending rewards computed up to
+    /// `current_time`, or an empty stake if the user has none.
+    pub fn get_stake_info(
+        &self,
+        user: &AccountAddress,
+        current_time: u64
+    ) -> StakeInfo {
+        // Return default StakeInfo if no stake exists
+        self.stakes.get(user).map(|s| {
+            // Move the rewards accrued since the last update into pending
+            // rewards, including each position
+            let mut stake_info = s.clone();
+            stake_info.accrue_rewards(
+                current_time,
+                self.reward_per_token(current_time),
+                self.max_accrual_seconds,
+                self.reward_rounding
+            );
+            stake_info
+        }).unwrap_or_else(|| StakeInfo {
+            amount: 0,
+            timestamp: current_time,
+            unbonding: Vec::new(),
+            slashed: false,
+            pending_rewards: 0,
+            locked_apr: None,
+            reward_per_token_paid: 0,
+            positions: Vec::new(),
+            lifetime_rewards_claimed: self.lifetime_rewards_archive
+                .get(user)
+                .map_or(0, |claimed| *claimed),
+            last_claim_time: 0,
+        })
+    }
+
+    /// Get currrent nonce of a user
+    pub fn get_user_nonce(&self, user: &AccountAddress) -> u64 {
+        self.nonces_registry.get(user).map_or_else(
+            || 0,
+            |n| *n
+        )
+    }
+}
+
+/// The concordium liquid staking smart contract errors.
+#[derive(Debug, PartialEq, Eq, Clone, Reject, Serialize, SchemaType)]
+pub enum Error {
+    /// Failed Parsing The Parameter.
+    #[from(ParseError)]
+    ParseParams, // -1
+
+    /// Prevent Unauthorized Access
+    UnAuthorized, // -2
+
+    /// Invalid Stake Amount
+    InvalidStakeAmount, // -3
+
+    /// No Stake Found
+    NoStakeFound, // -4
+
+    /// OnlyAccount
+    OnlyAccount, // -5
+
+    /// Only Admin Access
+    OnlyAdmin, // -6
+
+    /// Raised when the invocation of the cis2 token contract fails.
+    InvokeContractError, //-7
+
+    /// Raised when the parsing of the result from the cis2 token contract
+    /// fails.
+    ParseResult, //-8
+
+    /// Raised when the response of the cis2 token contract is invalid.
+    InvalidResponse, //-9
+
+    /// Failed logging: Log is full.
+    LogFull, // -10
+
+    /// Failed logging: Log is malformed.
+    LogMalformed, // -11
+
+    /// Upgrade failed because the new module does not exist.
+    FailedUpgradeMissingModule, // -12
+
+    /// Upgrade failed because the new module does not contain a contract with a
+    /// matching name.
+    FailedUpgradeMissingContract, // -13
+
+    /// Upgrade failed because the smart contract version of the module is not
+    /// supported.
+    FailedUpgradeUnsupportedModuleVersion, // -14
+
+    // Contract is paused.
+    ContractPaused, // -15
+
+    /// Insufficient funds
+    InsufficientFunds, // -16
+
+    /// Raised when someone else than the cis2 token contract invokes the `stake`
+    /// entry point.
+    NotTokenContract, //-17
+
+    /// Failed to verify signature because signer account does not exist on
+    /// chain.
+    MissingAccount, // -18
+
+    /// Failed to verify signature because data was malformed.
+    MalformedData, // -19
+
+    /// Failed signature verification: Invalid signature.
+    WrongSignature, // -20
+
+    /// Failed signature verification: A different nonce is expected.
+    NonceMismatch, // -21
+
+    /// Failed signature verification: Signature was intended for a different
+    /// contract.
+    WrongContract, // -22
+
+    /// Failed signature verification: Signature was intended for a different
+    /// entry_point.
+    WrongEntryPoint, // -23
+
+    /// Failed signature verification: Signature is expired.
+    Expired, // -24
+
+    /// Invalid unstake amount
+    InvalidUnstakeAmount,
+
+    /// Unbonding period not met
+    UnbondingPeriodNotMet,
+
+    /// Already slashed
+    AlreadySlashed,
+
+    /// Insufficient rewards pool
+    InsufficientRewardsPool,
+
+    /// No rewards available to claim
+    NoRewardsAvailable,
+
+    /// Batch exceeds the maximum batch size
+    BatchTooLarge,
+
+    /// Invalid admin account
+    InvalidAdmin,
+
+    /// Invalid epoch configuration
+    InvalidEpochConfig,
+
+    /// Withdrawal would leave the rewards pool unable to cover the rewards
+    /// owed to stakers.
+    WouldUndercollateralize,
+
+    /// Claim amount exceeds the available rewards
+    ClaimExceedsRewards,
+
+    /// Resulting stake is below the minimum stake
+    BelowMinimumStake,
+
+    /// Contract is not paused.
+    ContractNotPaused,
+
+    /// No unbonding entry at the given index
+    InvalidUnbondingIndex,
+
+    /// Unbonding entry has already matured
+    UnbondingAlreadyMatured,
+
+    /// Slashing rate exceeds 100%
+    InvalidSlashingRate,
+
+    /// Contract was re-entered during a token transfer
+    Reentrancy,
+
+    /// A time or amount calculation exceeded the u64 range
+    ArithmeticOverflow,
+
+    /// No lock tier for the lock duration, or an invalid lock tier
+    InvalidLockTier,
+
+    /// Stake is still locked
+    StakeLocked,
+
+    /// Received a token other than EUROe
+    UnexpectedTokenId,
+
+    /// Received tokens without a valid `DepositKind`
+    UnknownDeposit,
+
+    /// Timestamp is before the current block time
+    TimestampInPast,
+
+    /// Reward fee exceeds 100%
+    InvalidRewardFee,
+
+    /// APR history does not reach back to the timestamp
+    AprHistoryUnavailable,
+
+    /// Stake would exceed the maximum total stake
+    StakeCapExceeded,
+
+    /// APR change exceeds the maximum change per update
+    AprChangeTooLarge,
+
+    /// APR was updated too recently
+    AprUpdateTooSoon,
+
+    /// Rewards were claimed too recently
+    ClaimCooldownActive,
+
+    /// Slash amount is zero or exceeds the active stake
+    InvalidSlashAmount,
+
+    /// Only the contract itself can call this function
+    OnlySelf,
+
+    /// Staker has too many pending unbonding entries
+    TooManyUnbonding,
+
+    /// Account is frozen by the admin
+    AccountFrozen,
+
+    /// APR exceeds the maximum APR
+    AprTooHigh,
+
+    /// APR of zero is not allowed
+    ZeroApr,
+
+    /// Account cannot be slashed
+    SlashExempt,
+
+    /// Timestamp is before the last update of the stake
+    TimestampBeforeStake,
+
+    /// Receipt tokens can only be transferred to accounts
+    InvalidReceiver,
+
+    /// Contract still holds funds in the current token
+    TokenFundsOutstanding,
+
+    /// Staker has too many locked positions
+    TooManyPositions,
+
+    /// Token contract does not support CIS-2
+    InvalidTokenContract,
+}
+
+/// Mapping the logging errors to Error.
+impl From<LogError> for Error {
+    fn from(le: LogError) -> Self {
+        match le {
+            LogError::Full => Self::LogFull,
+            LogError::Malformed => Self::LogMalformed,
+        }
+    }
+}
+
+/// Mapping Cis2ClientError<Error> to Error.
+impl From<Cis2ClientError<Error>> for Error {
+    fn from(e: Cis2ClientError<Error>) -> Self {
+        match e {
+            Cis2ClientError::InvokeContractError(_) =>
+                Self::InvokeContractError,
+            Cis2ClientError::ParseResult => Self::ParseResult,
+            Cis2ClientError::InvalidResponse => Self::InvalidResponse,
+        }
+    }
+}
+
+/// Mapping UpgradeError to Error
+impl From<UpgradeError> for Error {
+    #[inline(always)]
+    fn from(ue: UpgradeError) -> Self {
+        match ue {
+            UpgradeError::MissingModule => Self::FailedUpgradeMissingModule,
+            UpgradeError::MissingContract => Self::FailedUpgradeMissingContract,
+            UpgradeError::UnsupportedModuleVersion =>
+                Self::FailedUpgradeUnsupportedModuleVersion,
+        }
+    }
+}
+
+/// Mapping of errors related to contract invocations to Error.
+impl<T> From<CallContractError<T>> for Error {
+    fn from(_cce: CallContractError<T>) -> Self {
+        Self::InvokeContractError
+    }
+}
+
+/// Mapping account signature error to CustomContractError
+impl From<CheckAccountSignatureError> for Error {
+    fn from(e: CheckAccountSignatureError) -> Self {
+        match e {
+            CheckAccountSignatureError::MissingAccount => Self::MissingAccount,
+            CheckAccountSignatureError::MalformedData => Self::MalformedData,
+        }
+    }
+}
+
+/// Enum for different event types in the contract.
+/// Custom events are logged wrapped in a `SequencedEvent`. The CIS-2 and
+/// CIS-3 events are logged as they are, so they keep their standard format.
+#[derive(Debug, Serial, Deserial, PartialEq, Eq, SchemaType)]
+#[concordium(repr(u8))]
+pub enum Event {
+    /// Event for when tokens are staked.
+    Staked(StakeEvent),
+
+    /// Event for when tokens are unstaked.
+    Unstaked(UnstakeEvent),
+
+    /// Event for when rewards are claimed.
+    Claimed(ClaimEvent),
+
+    /// Event for when APR is updated.
+    AprUpdated(UpdateAprEvent),
+
+    /// Event for when a staker is slashed.
+    Slashed(SlashEvent),
+
+    /// Event for when the admin role is transferred.
+    AdminTransferred(AdminTransferredEvent),
+
+    /// Event for when the unbonding period is updated.
+    UnbondingPeriodUpdated(UpdateUnbondingPeriodEvent),
+
+    /// Event for when the slashing rate is updated.
+    SlashingRateUpdated(UpdateSlashingRateEvent),
+
+    /// Event for when the contract is paused or unpaused.
+    PauseChanged(PauseChangedEvent),
+
+    /// Event for when matured unbonding funds are paid out.
+    UnstakeCompleted(UnstakeCompletedEvent),
+
+    /// Event for when a permit invoked an entrypoint.
+    PermitExecuted(PermitExecutedEvent),
+
+    /// Event for when the rewards pool is funded.
+    RewardsFunded(RewardsFundedEvent),
+
+    /// Event for when a protocol fee is taken from claimed rewards.
+    FeeCollected(FeeCollectedEvent),
+
+    /// Event for when rewards are funded with vesting.
+    VestedRewardsFunded(VestedRewardsFundedEvent),
+
+    /// Event for when an account is frozen.
+    Frozen(FreezeEvent),
+
+    /// Event for when an account is unfrozen.
+    Unfrozen(FreezeEvent),
+
+    /// Event for when reward payouts are paused because the rewards pool
+    /// ran dry.
+    PoolDepleted(PoolDepletedEvent),
+
+    /// Event for when the EUROe token address is changed.
+    TokenAddressUpdated(UpdateTokenAddressEvent),
+
+    /// Event for when the rounding of rewards is changed.
+    RewardRoundingUpdated(UpdateRewardRoundingEvent),
+
+    /// Cis3 event.
+    /// The event tracks the nonce used by the signer of the `PermitMessage`
+    /// whenever the `permit` function is invoked.
+    #[concordium(tag = 250)]
+    Nonce(NonceEvent),
+
+    /// Cis2 event for when the metadata URL of the receipt token is set.
+    #[concordium(tag = 251)]
+    ReceiptMetadata(TokenMetadataEvent<ContractTokenId>),
+
+    /// Cis2 event for when an operator of receipt tokens is updated.
+    #[concordium(tag = 252)]
+    UpdateOperator(UpdateOperatorEvent),
+
+    /// Cis2 event for when receipt tokens are burned on unstaking or
+    /// slashing.
+    #[concordium(tag = 253)]
+    ReceiptBurn(BurnEvent<ContractTokenId, TokenAmountU64>),
+
+    /// Cis2 event for when receipt tokens are minted on staking.
+    #[concordium(tag = 254)]
+    ReceiptMint(MintEvent<ContractTokenId, TokenAmountU64>),
+
+    /// Cis2 event for when receipt tokens are transferred.
+    #[concordium(tag = 255)]
+    ReceiptTransfer(TransferEvent<ContractTokenId, TokenAmountU64>),
+}
+
+/// An event with its position in the event stream of the contract. The event
+/// comes first, so the tag byte still leads the logged bytes.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct SequencedEvent {
+    /// The logged event.
+    pub event: Event,
+
+    /// Number of custom events logged by the contract before this one.
+    pub event_seq: u64,
+}
+
+/// Implementation of the events
+impl Event {
+    /// Whether the event is one of the CIS-2 or CIS-3 events, tagged from 250
+    pub fn is_standard(&self) -> bool {
+        matches!(
+            self,
+            Event::Nonce(_) |
+                Event::ReceiptMetadata(_) |
+                Event::UpdateOperator(_) |
+                Event::ReceiptBurn(_) |
+                Event::ReceiptMint(_) |
+                Event::ReceiptTransfer(_)
+        )
+    }
+}
+
+/// Event structure for staking.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct StakeEvent {
+    /// Address of the user who staked.
+    pub user: AccountAddress,
+
+    /// Amount of tokens staked.
+    pub stake_amount: TokenAmountU64,
+
+    /// Timestamp when the stake was made.
+    pub staked_timestamp: u64,
+
+    /// Total amount of staked tokens after the stake.
+    pub total_staked_after: TokenAmountU64,
+
+    /// Whether the tokens were added to an existing principal.
+    pub is_top_up: bool,
+}
+
+/// Event structure for unstaking.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct UnstakeEvent {
+    /// Address of the user who unstaked.
+    pub user: AccountAddress,
+
+    /// Amount of tokens unstaked.
+    pub unstaked_amount: TokenAmountU64,
+
+    /// Timestamp when the unstake was made.
+    pub unix_timestamp: u64,
+
+    /// Rewards accrued since the last checkpoint and moved to the pending
+    /// rewards by the unstake. They are paid by a later claim, logged as
+    /// `Claimed`. Zero for an emergency withdrawal, which forfeits rewards.
+    pub rewards_earned: TokenAmountU64,
+
+    /// Total amount of staked tokens after the unstake.
+    pub total_staked_after: TokenAmountU64,
+}
+
+/// Event structure for claiming rewards.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct ClaimEvent {
+    /// Address of the user who claimed rewards.
+    pub user: AccountAddress,
+
+    /// Amount of rewards claimed.
+    pub rewards_claimed: TokenAmountU64,
+
+    /// Timestamp when the claim was made.
+    pub claim_timestamp: u64,
+
+    /// Address of the account that received the rewards.
+    pub recipient: AccountAddress,
+}
+
+/// Event structure for updating APR.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct UpdateAprEvent {
+    /// New APR value.
+    new_apr: u64,
+
+    /// Timestamp when the APR was updated.
+    update_timestamp: u64,
+}
+
+/// Event structure for slashing.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct SlashEvent {
+    /// Address of the user who was slashed.
+    pub user: AccountAddress,
+
+    /// Amount of staked tokens moved to the rewards pool.
+    pub slashed_amount: TokenAmountU64,
+
+    /// Pending rewards voided by the slash.
+    pub forfeited_rewards: TokenAmountU64,
+
+    /// Timestamp when the slash was made.
+    pub slash_timestamp: u64,
+
+    /// Why the staker was slashed.
+    pub reason_code: u16,
+
+    /// Amount of unbonding tokens moved to the rewards pool.
+    pub slashed_unbonding: TokenAmountU64,
+}
+
+/// Event structure for transferring the admin role.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct AdminTransferredEvent {
+    /// The previous admin.
+    previous_admin: AccountAddress,
+
+    /// The new admin.
+    new_admin: AccountAddress,
+}
+
+/// Event structure for updating the unbonding period.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct UpdateUnbondingPeriodEvent {
+    /// Previous unbonding period in seconds.
+    pub old_unbonding_period: u64,
+
+    /// New unbonding period in seconds.
+    pub new_unbonding_period: u64,
+
+    /// Timestamp when the unbonding period was updated.
+    pub update_timestamp: u64,
+}
+
+/// Event structure for changing the EUROe token address.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct UpdateTokenAddressEvent {
+    /// Previous EUROe token contract.
+    old_token_address: ContractAddress,
+
+    /// New EUROe token contract.
+    new_token_address: ContractAddress,
+
+    /// Timestamp when the token address was changed.
+    update_timestamp: u64,
+}
+
+/// Event structure for updating the rounding of rewards.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct UpdateRewardRoundingEvent {
+    /// Previous rounding of rewards.
+    old_rounding: RoundingMode,
+
+    /// New rounding of rewards.
+    new_rounding: RoundingMode,
+
+    /// Timestamp when the rounding was changed.
+    update_timestamp: u64,
+}
+
+/// Event structure for updating the slashing rate.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct UpdateSlashingRateEvent {
+    /// Previous slashing rate in basis points.
+    pub old_slashing_rate: u64,
+
+    /// New slashing rate in basis points.
+    pub new_slashing_rate: u64,
+
+    /// Timestamp when the slashing rate was updated.
+    pub update_timestamp: u64,
+}
+
+/// Event structure for pausing or unpausing.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct PauseChangedEvent {
+    /// New pause flags.
+    pub paused: PauseFlags,
+
+    /// Account that changed the pause flags.
+    pub caller: AccountAddress,
+
+    /// Timestamp when the pause flags were changed.
+    pub update_timestamp: u64,
+}
+
+/// Event structure for completing an unstake.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct UnstakeCompletedEvent {
+    /// Address of the user who received the funds.
+    user: AccountAddress,
+
+    /// Amount of tokens paid out.
+    amount: TokenAmountU64,
+
+    /// Timestamp when the funds were paid out.
+    complete_timestamp: u64,
+}
+
+/// Event structure for executing a permit.
+/// Logged next to the CIS3 `Nonce` event, whose layout is fixed by the
+/// standard.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct PermitExecutedEvent {
+    /// Account that signed the permit.
+    pub signer: AccountAddress,
+
+    /// The nonce used by the permit.
+    pub nonce: u64,
+
+    /// The entrypoint the permit invoked.
+    pub entry_point: OwnedEntrypointName,
+}
+
+/// Event structure for funding the rewards pool.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct RewardsFundedEvent {
+    /// Address that provided the tokens.
+    pub funder: Address,
+
+    /// Amount of tokens added to the rewards pool.
+    pub amount: TokenAmountU64,
+
+    /// Timestamp when the pool was funded.
+    pub fund_timestamp: u64,
+}
+
+/// Event structure for funding rewards with vesting.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct VestedRewardsFundedEvent {
+    /// Address that provided the tokens.
+    funder: Address,
+
+    /// Amount of tokens locked.
+    amount: TokenAmountU64,
+
+    /// Timestamp at which all locked rewards are released.
+    vest_until: u64,
+
+    /// Timestamp when the rewards were funded.
+    fund_timestamp: u64,
+}
+
+/// Event structure for freezing or unfreezing an account.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct FreezeEvent {
+    /// The account frozen or unfrozen.
+    pub account: AccountAddress,
+
+    /// Timestamp when the account was frozen or unfrozen.
+    pub update_timestamp: u64,
+}
+
+/// Event structure for pausing reward payouts on a depleted rewards pool.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct PoolDepletedEvent {
+    /// The user whose claim or restake the pool could not cover.
+    user: AccountAddress,
+
+    /// The rewards requested by the user.
+    requested: TokenAmountU64,
+
+    /// The rewards pool at the time.
+    rewards_pool: TokenAmountU64,
+
+    /// Timestamp when reward payouts were paused.
+    depletion_timestamp: u64,
+}
+
+/// Event structure for collecting the protocol fee.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct FeeCollectedEvent {
+    /// Address of the user whose rewards were charged.
+    pub user: AccountAddress,
+
+    /// Account that received the fee.
+    pub fee_recipient: AccountAddress,
+
+    /// Amount of the fee.
+    pub fee: TokenAmountU64,
+
+    /// Timestamp when the fee was collected.
+    pub fee_timestamp: u64,
+}
+
+/// The NonceEvent is logged when the `permit` function is invoked. The event
+/// tracks the nonce used by the signer of the `PermitMessage`.
+#[derive(Debug, Serialize, SchemaType, PartialEq, Eq)]
+pub struct NonceEvent {
+    /// The nonce that was used in the `PermitMessage`.
+    pub nonce: u64,
+    /// Account that signed the `PermitMessage`.
+    pub account: AccountAddress,
+}
+
+/// Contract token ID type. It has to be the `ContractTokenId` from the cis2
+/// token contract.
+pub type ContractTokenId = TokenIdUnit;
+
+/// Token ID type of received tokens. The token contract may send any of its
+/// tokens, so any ID is parsed and checked with `is_euroe`.
+pub type ReceivedTokenId = TokenIdVec;
+
+/// ContractResult type.
+pub type ContractResult<A> = Result<A, Error>;
+
+/// The parameter type for the contract function `transfer` of the receipt
+/// token.
+pub type TransferParameter = TransferParams<ContractTokenId, TokenAmountU64>;
+
+/// Initialization function for the contract.
 #[init(contract = "concordium_staking", parameter = "InitContractParams")]
 fn contract_init(
     ctx: &InitContext,
     state_builder: &mut StateBuilder
 ) -> InitResult<State> {
     let params: InitContractParams = ctx.parameter_cursor().get()?;
-    let state = State {
-        paused: false,
-        admin: params.admin,
-        total_staked: TokenAmountU64(0),
-        total_participants: 0,
-        apr: INITIAL_APR,
-        stakes: state_builder.new_map(),
-        token_address: params.token_address,
-        nonces_registry: state_builder.new_map(),
-        unbonding_period: params.unbonding_period,
-        slashing_rate: params.slashing_rate,
-        rewards_pool: TokenAmountU64(0),
-        total_rewards_paid: TokenAmountU64(0),
+    ensure_valid_admin(&params.admin)?;
+    ensure_valid_slashing_rate(params.slashing_rate)?;
+
+    let state = State::new(
+        params.admin,
+        params.token_address,
+        params.unbonding_period,
+        params.slashing_rate,
+        ctx.metadata().block_time().millis / 1000,
+        state_builder
+    );
+
+    Ok(state)
+}
+
+/// Receive cis-2 token
+/// The additional data must be a `DepositKind` telling whether the tokens are
+/// staked, fund the rewards pool or fund it with vesting.
+#[receive(
+    contract = "concordium_staking",
+    name = "onReceivingCIS2",
+    parameter = "OnReceivingCis2Params<ReceivedTokenId, TokenAmountU64>",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_on_cis2_received(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let params: OnReceivingCis2Params<ReceivedTokenId, TokenAmountU64> = ctx
+        .parameter_cursor()
+        .get()?;
+
+    let deposit_kind = parse_deposit_kind(params.data.as_ref())?;
+
+    match deposit_kind {
+        DepositKind::Stake(stake_data) => {
+            stake_helper(ctx, host, logger, params, stake_data)?;
+            host.state_mut().touch(get_current_timestamp(ctx));
+            Ok(())
+        }
+        DepositKind::FundRewards => {
+            let state = host.state_mut();
+            // Check if sender is the token contract
+            ensure!(ctx.sender().matches_contract(&state.token_address), Error::NotTokenContract);
+            ensure!(is_euroe(&params.token_id), Error::UnexpectedTokenId);
+
+            // Tokens arrive while `fundRewards` holds the lock, so it is not
+            // checked here
+            state.rewards_pool += params.amount;
+
+            state.log_event(logger, Event::RewardsFunded(RewardsFundedEvent {
+                funder: params.from,
+                amount: params.amount,
+                fund_timestamp: get_current_timestamp(ctx),
+            }))?;
+
+            state.touch(get_current_timestamp(ctx));
+            Ok(())
+        }
+        DepositKind::FundRewardsVested(vest_until) => {
+            let current_time = get_current_timestamp(ctx);
+            let state = host.state_mut();
+            ensure!(ctx.sender().matches_contract(&state.token_address), Error::NotTokenContract);
+            ensure!(is_euroe(&params.token_id), Error::UnexpectedTokenId);
+            ensure!(vest_until > current_time, Error::TimestampInPast);
+
+            // Tokens arrive while `fundRewardsVested` holds the lock, so it
+            // is not checked here
+            state.add_vested_rewards(params.amount.0, current_time, vest_until);
+
+            state.log_event(logger, Event::VestedRewardsFunded(VestedRewardsFundedEvent {
+                funder: params.from,
+                amount: params.amount,
+                vest_until: state.vest_until,
+                fund_timestamp: current_time,
+            }))?;
+
+            state.touch(current_time);
+            Ok(())
+        }
+    }
+}
+
+/// Get the additional data to attach to a CIS-2 transfer to
+/// `onReceivingCIS2`, staking the tokens or funding the rewards pool as given
+/// by the `DepositKind`.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The lock days of a stake match no lock tier.
+/// - The vesting end of a reward fund is not in the future.
+#[receive(
+    contract = "concordium_staking",
+    name = "expectedStakeData",
+    parameter = "DepositKind",
+    return_value = "AdditionalData",
+    error = "Error"
+)]
+fn contract_expected_stake_data(
+    ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<AdditionalData> {
+    let deposit_kind: DepositKind = ctx.parameter_cursor().get()?;
+    let state = host.state();
+
+    match &deposit_kind {
+        DepositKind::Stake(stake_data) => {
+            ensure!(
+                stake_data.lock_days == 0 ||
+                    state.lock_tiers.iter().any(|tier| tier.lock_days == stake_data.lock_days),
+                Error::InvalidLockTier
+            );
+        }
+        DepositKind::FundRewards => {}
+        DepositKind::FundRewardsVested(vest_until) => {
+            ensure!(*vest_until > get_current_timestamp(ctx), Error::TimestampInPast);
+        }
+    }
+
+    Ok(AdditionalData::from(to_bytes(&deposit_kind)))
+}
+
+/// Verify an ed25519 signature and allow the unstake, claimRewards,
+/// completeUnstake, restake, fundRewards.
+/// The admin can also sign withdrawEuroe and slash, so multi-sig admin
+/// accounts can authorize them with several keys. A signed `upgrade` goes
+/// through `permitUpgrade`.
+/// Returns the rewards transferred for a `claimRewards` message and `None`
+/// for the other entrypoints.
+#[receive(
+    contract = "concordium_staking",
+    name = "permit",
+    parameter = "PermitParam",
+    return_value = "Option<TokenAmountU64>",
+    error = "Error",
+    crypto_primitives,
+    mutable,
+    enable_logger
+)]
+fn contract_permit(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    _logger: &mut Logger,
+    crypto_primitives: &impl HasCryptoPrimitives
+) -> ContractResult<Option<TokenAmountU64>> {
+    // The pause flags are checked by the dispatched entrypoint.
+
+    // Parse the parameter.
+    let param: PermitParam = ctx.parameter_cursor().get()?;
+    host.state().ensure_not_frozen(&param.signer)?;
+
+    let message = param.message;
+    let nonce = use_permit_nonce(ctx, host.state_mut(), param.signer, &message)?;
+
+    let message_hash = contract_view_message_hash(
+        ctx,
+        host,
+        crypto_primitives
+    )?;
+
+    let valid_signature = host.check_account_signature(
+        param.signer,
+        &param.signature,
+        &message_hash
+    )?; // Check signature.
+
+    ensure!(valid_signature, Error::WrongSignature);
+
+    let mut claimed = None;
+    if
+        message.entry_point.as_entrypoint_name() ==
+        EntrypointName::new_unchecked("unstake")
+    {
+        let payload: UnstakeParams = from_bytes(&message.payload)?;
+        unstake_helper(ctx, host, _logger, param.signer, payload.amount)?;
+    } else if
+        // claim
+        message.entry_point.as_entrypoint_name() ==
+        EntrypointName::new_unchecked("claimRewards")
+    {
+        let payload: ClaimRewardsParams = parse_optional_params(&message.payload)?;
+        claimed = Some(claim_rewards_helper(
+            ctx,
+            host,
+            _logger,
+            param.signer,
+            payload.amount,
+            payload.recipient
+        )?);
+    } else if
+        // complete unstake, the payload may be empty
+        message.entry_point.as_entrypoint_name() ==
+        EntrypointName::new_unchecked("completeUnstake")
+    {
+        let payload: CompleteUnstakeParams = parse_optional_params(&message.payload)?;
+        complete_unstake_helper(ctx, host, _logger, param.signer, payload.index)?;
+    } else if
+        // restake
+        message.entry_point.as_entrypoint_name() ==
+        EntrypointName::new_unchecked("restake")
+    {
+        restake_helper(ctx, host, _logger, param.signer)?;
+    } else if
+        // fund rewards
+        message.entry_point.as_entrypoint_name() ==
+        EntrypointName::new_unchecked("fundRewards")
+    {
+        let payload: TokenAmountU64 = from_bytes(&message.payload)?;
+        fund_rewards_helper(ctx, host, param.signer, payload, DepositKind::FundRewards)?;
+    } else if
+        // withdraw EUROe, admin only
+        message.entry_point.as_entrypoint_name() ==
+        EntrypointName::new_unchecked("withdrawEuroe")
+    {
+        let payload: WithdrawEuroEParams = from_bytes(&message.payload)?;
+        withdraw_euroe_helper(ctx, host, param.signer, payload)?;
+    } else if
+        // slash, admin only
+        message.entry_point.as_entrypoint_name() ==
+        EntrypointName::new_unchecked("slash")
+    {
+        let payload: SlashParams = from_bytes(&message.payload)?;
+        slash_helper(ctx, host, _logger, param.signer, payload)?;
+    } else {
+        // no entrypoint
+        bail!(Error::WrongEntryPoint);
+    }
+
+    // Log the nonce event.
+    host.state_mut().log_event(
+        _logger,
+        Event::Nonce(NonceEvent {
+            account: param.signer,
+            nonce,
+        })
+    )?;
+
+    // Log the invoked entrypoint.
+    host.state_mut().log_event(
+        _logger,
+        Event::PermitExecuted(PermitExecutedEvent {
+            signer: param.signer,
+            nonce,
+            entry_point: message.entry_point,
+        })
+    )?;
+
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(claimed)
+}
+
+/// Verify the ed25519 signature of the admin on an `upgrade` message, whose
+/// payload is the `UpgradeParams`, and upgrade like `upgrade`. Multi-sig
+/// admin accounts can so authorize upgrades with several keys. The nonce is
+/// shared with `permit`.
+///
+/// This function is marked as `low_level` for the same reason as `upgrade`.
+#[receive(
+    contract = "concordium_staking",
+    name = "permitUpgrade",
+    parameter = "PermitParam",
+    error = "Error",
+    crypto_primitives,
+    low_level,
+    enable_logger
+)]
+fn contract_permit_upgrade(
+    ctx: &ReceiveContext,
+    host: &mut LowLevelHost,
+    logger: &mut Logger,
+    crypto_primitives: &impl HasCryptoPrimitives
+) -> ContractResult<()> {
+    let param: PermitParam = ctx.parameter_cursor().get()?;
+    let mut state: State = host.state().read_root()?;
+    ensure!(param.signer == state.admin, Error::OnlyAdmin);
+
+    let message = param.message;
+    let nonce = use_permit_nonce(ctx, &mut state, param.signer, &message)?;
+    ensure!(
+        message.entry_point.as_entrypoint_name() == EntrypointName::new_unchecked("upgrade"),
+        Error::WrongEntryPoint
+    );
+
+    let message_hash = compute_message_hash(ctx, crypto_primitives)?.message_hash;
+    let valid_signature = host.check_account_signature(
+        param.signer,
+        &param.signature,
+        &message_hash
+    )?;
+    ensure!(valid_signature, Error::WrongSignature);
+    let params: UpgradeParams = from_bytes(&message.payload)?;
+
+    state.log_event(logger, Event::Nonce(NonceEvent {
+        account: param.signer,
+        nonce,
+    }))?;
+    state.log_event(logger, Event::PermitExecuted(PermitExecutedEvent {
+        signer: param.signer,
+        nonce,
+        entry_point: message.entry_point,
+    }))?;
+    state.touch(get_current_timestamp(ctx));
+
+    // Store the state before a migration reads it
+    host.state_mut().write_root(&state);
+    upgrade_helper(ctx, host, params)
+}
+
+/// Consume the nonce of `signer` for the permit `message`, checking that it
+/// matches, that the message is meant for this contract and that it has not
+/// expired. Returns the nonce.
+fn use_permit_nonce(
+    ctx: &ReceiveContext,
+    state: &mut State,
+    signer: AccountAddress,
+    message: &PermitMessage
+) -> ContractResult<u64> {
+    // Update the nonce.
+    let mut entry = state.nonces_registry.entry(signer).or_insert_with(|| 0);
+
+    // Get the current nonce.
+    let nonce = *entry;
+
+    // Bump nonce.
+    *entry += 1;
+    drop(entry);
+
+    ensure_eq!(message.nonce, nonce, Error::NonceMismatch); // Check the nonce to prevent replay attacks.
+
+    ensure_eq!(
+        message.contract_address,
+        ctx.self_address(),
+        Error::WrongContract
+    ); // Check that the signature was intended for this contract.
+
+    // Compare as `Timestamp`s, both in milliseconds, unlike
+    // `get_current_timestamp` which is in seconds.
+    ensure!(message.timestamp > ctx.metadata().block_time(), Error::Expired); // Check signature is not expired.
+
+    Ok(nonce)
+}
+
+/// Function to stake tokens.
+/// The additional data is an optional `StakeData`, whose beneficiary lets a
+/// third party stake on behalf of another account.
+/// Returns the total active stake of the staker afterwards.
+#[receive(
+    contract = "concordium_staking",
+    name = "stake",
+    parameter = "OnReceivingCis2Params<ReceivedTokenId, TokenAmountU64>",
+    return_value = "TokenAmountU64",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_stake(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<TokenAmountU64> {
+    let params: OnReceivingCis2Params<ReceivedTokenId, TokenAmountU64> = ctx
+        .parameter_cursor()
+        .get()?;
+    let stake_data: StakeData = parse_optional_params(params.data.as_ref())?;
+    let staker_total = stake_helper(ctx, host, logger, params, stake_data)?;
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(staker_total)
+}
+
+/// Stakes the tokens received from the token contract for the beneficiary in
+/// `stake_data`, or the account that sent them, and returns their total
+/// active stake.
+fn stake_helper(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger,
+    params: OnReceivingCis2Params<ReceivedTokenId, TokenAmountU64>,
+    stake_data: StakeData
+) -> ContractResult<TokenAmountU64> {
+    let state = host.state_mut();
+    // Check if sender is the token contract
+    if !ctx.sender().matches_contract(&state.token_address) {
+        bail!(Error::NotTokenContract);
+    }
+
+    ensure!(is_euroe(&params.token_id), Error::UnexpectedTokenId);
+
+    // The stake belongs to the beneficiary if one is given
+    let sender_address = match stake_data.beneficiary {
+        Some(beneficiary) => beneficiary,
+        None => only_account(&params.from)?,
+    };
+    let unix_timestamp = get_current_timestamp(ctx);
+    let amount = params.amount;
+
+    ensure!(!state.paused.stake_paused, Error::ContractPaused);
+    state.ensure_unlocked()?;
+    state.ensure_not_frozen(&sender_address)?;
+    if let Address::Account(funder) = params.from {
+        state.ensure_not_frozen(&funder)?;
+    }
+    ensure!(amount.gt(&TokenAmountU64(0)), Error::InvalidStakeAmount);
+
+    // A slashed staker cannot stake again
+    ensure!(
+        !state.stakes.get(&sender_address).is_some_and(|s| s.slashed),
+        Error::AlreadySlashed
+    );
+
+    // The resulting stake must reach the minimum stake
+    let current_amount = state.stakes.get(&sender_address).map_or(0, |s| s.amount);
+    ensure!(
+        current_amount.saturating_add(amount.0) >= state.minimum_stake.0,
+        Error::BelowMinimumStake
+    );
+
+    // The total stake must stay within the cap
+    state.ensure_within_stake_cap(amount.0)?;
+
+    // Look up the bonus for the lock duration
+    let (lock_until, bonus_bps) = if stake_data.lock_days == 0 {
+        (unix_timestamp, 0)
+    } else {
+        let tier = state.lock_tiers
+            .iter()
+            .find(|tier| tier.lock_days == stake_data.lock_days)
+            .ok_or(Error::InvalidLockTier)?;
+        let lock_until = stake_data.lock_days
+            .checked_mul(SECONDS_PER_DAY)
+            .and_then(|duration| unix_timestamp.checked_add(duration))
+            .ok_or(Error::ArithmeticOverflow)?;
+        (lock_until, tier.bonus_bps)
+    };
+
+    let reward_per_token = state.update_reward_per_token(unix_timestamp);
+
+    // A staker is new when they are not counted as a participant yet, i.e.
+    // they hold neither an active stake nor funds still unbonding.
+    let is_new_staker = state.stakes
+        .get(&sender_address)
+        .is_none_or(|s| !s.is_participant());
+
+    // Restore the lifetime claimed rewards of a returning staker
+    let lifetime_rewards_claimed = state.lifetime_rewards_archive
+        .remove_and_get(&sender_address)
+        .unwrap_or(0);
+
+    // Get or create stake info
+    let mut sender_stake = state.stakes
+        .entry(sender_address)
+        .or_insert_with(|| StakeInfo::new(unix_timestamp, lifetime_rewards_claimed));
+
+    // Calculate pending rewards before updating stake
+    state.reward_liability.remove(&sender_stake, reward_per_token, unix_timestamp);
+    sender_stake.accrue_rewards(
+        unix_timestamp,
+        reward_per_token,
+        state.max_accrual_seconds,
+        state.reward_rounding
+    );
+
+    // Lock the APR for new deposits. A top-up of a locked stake blends the
+    // locked rate with the current APR, weighted by amount.
+    if state.apr_locking || sender_stake.locked_apr.is_some() {
+        sender_stake.locked_apr = Some(
+            weighted_apr(
+                sender_stake.amount,
+                sender_stake.effective_apr(state.apr),
+                amount.0,
+                state.apr
+            )
+        );
+    }
+
+    // Add the deposit as a new position
+    sender_stake.add_position(amount.0, unix_timestamp, lock_until, bonus_bps)?;
+    state.reward_liability.add(&sender_stake, reward_per_token, unix_timestamp);
+    let staker_total = TokenAmountU64(sender_stake.amount);
+    drop(sender_stake);
+
+    // Update total staked and participants
+    state.total_staked = TokenAmountU64(state.total_staked.0.saturating_add(amount.0));
+    state.update_participants(!is_new_staker, true);
+    state.log_receipt_mint(logger, sender_address, amount)?;
+
+    state.log_event(logger, Event::Staked(StakeEvent {
+        user: sender_address,
+        stake_amount: amount,
+        staked_timestamp: unix_timestamp,
+        total_staked_after: state.total_staked,
+        is_top_up: !is_new_staker,
+    }))?;
+
+    Ok(staker_total)
+}
+
+/// Function to unstake tokens.
+/// Returns the created unbonding entry.
+#[receive(
+    contract = "concordium_staking",
+    name = "unstake",
+    parameter = "UnstakeParams",
+    return_value = "UnbondingInfo",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_unstake(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    _logger: &mut Logger
+) -> ContractResult<UnbondingInfo> {
+    let param: UnstakeParams = ctx.parameter_cursor().get()?;
+    let sender_address = only_account(&ctx.sender())?;
+    let unbonding = unstake_helper(ctx, host, _logger, sender_address, param.amount)?;
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(unbonding)
+}
+
+/// Function to unstake the whole active stake of the caller.
+/// Returns the created unbonding entry. Rejects with `StakeLocked` if part of
+/// the stake is still locked.
+#[receive(
+    contract = "concordium_staking",
+    name = "unstakeAll",
+    return_value = "UnbondingInfo",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_unstake_all(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<UnbondingInfo> {
+    let sender_address = only_account(&ctx.sender())?;
+    let amount = host.state().stakes.get(&sender_address).map_or(0, |s| s.amount);
+    ensure!(amount > 0, Error::NoStakeFound);
+    let unbonding = unstake_helper(ctx, host, logger, sender_address, TokenAmountU64(amount))?;
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(unbonding)
+}
+
+/// Function to cancel an unbonding entry and stake its funds again.
+/// Matured entries cannot be cancelled and must be completed instead.
+#[receive(
+    contract = "concordium_staking",
+    name = "cancelUnbonding",
+    parameter = "CancelUnbondingParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_cancel_unbonding(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let params: CancelUnbondingParams = ctx.parameter_cursor().get()?;
+    let sender_address = only_account(&ctx.sender())?;
+    let current_time = get_current_timestamp(ctx);
+
+    let state = host.state_mut();
+    ensure!(!state.paused.stake_paused, Error::ContractPaused);
+    state.ensure_unlocked()?;
+    state.ensure_not_frozen(&sender_address)?;
+    let reward_per_token = state.update_reward_per_token(current_time);
+
+    let mut sender_stake = state.stakes
+        .entry(sender_address)
+        .occupied_or(Error::NoStakeFound)?;
+
+    ensure!(!sender_stake.slashed, Error::AlreadySlashed);
+
+    let index = params.index as usize;
+    let unbonding = sender_stake.unbonding
+        .get(index)
+        .cloned()
+        .ok_or(Error::InvalidUnbondingIndex)?;
+    ensure!(current_time < unbonding.unlock_time, Error::UnbondingAlreadyMatured);
+
+    // The resulting stake must reach the minimum stake
+    ensure!(
+        sender_stake.amount.saturating_add(unbonding.amount.0) >= state.minimum_stake.0,
+        Error::BelowMinimumStake
+    );
+
+    // Restart accrual so the funds earn rewards from now on
+    state.reward_liability.remove(&sender_stake, reward_per_token, current_time);
+    sender_stake.accrue_rewards(
+        current_time,
+        reward_per_token,
+        state.max_accrual_seconds,
+        state.reward_rounding
+    );
+
+    // Lock the APR like a fresh deposit would
+    if state.apr_locking || sender_stake.locked_apr.is_some() {
+        sender_stake.locked_apr = Some(
+            weighted_apr(
+                sender_stake.amount,
+                sender_stake.effective_apr(state.apr),
+                unbonding.amount.0,
+                state.apr
+            )
+        );
+    }
+
+    sender_stake.unbonding.remove(index);
+    let is_top_up = sender_stake.amount > 0;
+    sender_stake.add_position(unbonding.amount.0, current_time, current_time, 0)?;
+    state.reward_liability.add(&sender_stake, reward_per_token, current_time);
+    drop(sender_stake);
+
+    state.ensure_within_stake_cap(unbonding.amount.0)?;
+    state.total_unbonding -= unbonding.amount;
+    state.total_staked = TokenAmountU64(state.total_staked.0.saturating_add(unbonding.amount.0));
+    state.log_receipt_mint(logger, sender_address, unbonding.amount)?;
+
+    state.log_event(logger, Event::Staked(StakeEvent {
+        user: sender_address,
+        stake_amount: unbonding.amount,
+        staked_timestamp: current_time,
+        total_staked_after: state.total_staked,
+        is_top_up,
+    }))?;
+
+    state.touch(get_current_timestamp(ctx));
+    Ok(())
+}
+
+/// Function to withdraw the active stake and all unbonding funds
+/// immediately while unstaking is paused. Pending rewards are forfeited.
+#[receive(
+    contract = "concordium_staking",
+    name = "emergencyWithdraw",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_emergency_withdraw(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let sender_address = only_account(&ctx.sender())?;
+    let current_time = get_current_timestamp(ctx);
+
+    let (withdraw_amount, total_staked_after) = {
+        let state = host.state_mut();
+        ensure!(state.paused.unstake_paused, Error::ContractNotPaused);
+        state.ensure_unlocked()?;
+        state.ensure_not_frozen(&sender_address)?;
+
+        let reward_per_token = state.update_reward_per_token(current_time);
+        let stake_info = state.stakes.get(&sender_address).ok_or(Error::NoStakeFound)?;
+        ensure!(!stake_info.slashed, Error::AlreadySlashed);
+
+        let staked_amount = TokenAmountU64(stake_info.amount);
+        let unbonding_amount = stake_info.unbonding
+            .iter()
+            .fold(TokenAmountU64(0), |acc, unbonding| acc + unbonding.amount);
+        let was_participant = stake_info.is_participant();
+        state.reward_liability.remove(&stake_info, reward_per_token, current_time);
+        drop(stake_info);
+
+        // Remove the stake, forfeiting pending rewards
+        state.remove_stake(&sender_address);
+        state.total_staked -= staked_amount;
+        state.total_unbonding -= unbonding_amount;
+        state.update_participants(was_participant, false);
+        if staked_amount.0 > 0 {
+            state.log_receipt_burn(logger, sender_address, staked_amount)?;
+        }
+
+        (staked_amount + unbonding_amount, state.total_staked)
+    }; // state borrow ends here
+
+    ensure!(withdraw_amount.0 > 0, Error::InvalidUnstakeAmount);
+
+    transfer_euroe_token(
+        host,
+        Address::Contract(ctx.self_address()),
+        Receiver::Account(sender_address),
+        withdraw_amount,
+        AdditionalData::empty(),
+        true
+    )?;
+
+    host.state_mut().log_event(logger, Event::Unstaked(UnstakeEvent {
+        user: sender_address,
+        unstaked_amount: withdraw_amount,
+        unix_timestamp: current_time,
+        rewards_earned: TokenAmountU64(0), // Rewards are forfeited
+        total_staked_after,
+    }))?;
+
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(())
+}
+
+/// Function to claim rewards.
+/// Claims all rewards if the parameter is empty or the amount is `None`.
+/// The rewards go to `recipient` if given, while the stake stays with the
+/// sender. Returns the amount of rewards transferred.
+#[receive(
+    contract = "concordium_staking",
+    name = "claimRewards",
+    parameter = "ClaimRewardsParams",
+    return_value = "TokenAmountU64",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_claim_rewards(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    _logger: &mut Logger
+) -> ContractResult<TokenAmountU64> {
+    let params: ClaimRewardsParams = parse_optional_params(&read_parameter(ctx)?)?;
+    let sender_address = only_account(&ctx.sender())?;
+    let claimed = claim_rewards_helper(
+        ctx,
+        host,
+        _logger,
+        sender_address,
+        params.amount,
+        params.recipient
+    )?;
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(claimed)
+}
+
+/// Function to compound pending rewards into the staked principal.
+/// Returns the amount of rewards restaked.
+#[receive(
+    contract = "concordium_staking",
+    name = "restake",
+    return_value = "TokenAmountU64",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_restake(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<TokenAmountU64> {
+    let sender_address = only_account(&ctx.sender())?;
+    let restaked = restake_helper(ctx, host, logger, sender_address)?;
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(restaked)
+}
+
+/// Moves the rewards of `sender_address` into their staked principal and
+/// returns the amount restaked. If the rewards pool cannot cover the rewards
+/// and `auto_pause_on_depletion` is set, reward payouts are paused and
+/// nothing is restaked.
+fn restake_helper(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger,
+    sender_address: AccountAddress
+) -> ContractResult<TokenAmountU64> {
+    let current_time = get_current_timestamp(ctx);
+
+    let state = host.state_mut();
+    ensure!(!state.paused.stake_paused, Error::ContractPaused);
+    ensure!(!state.paused.rewards_paused, Error::ContractPaused);
+    state.ensure_unlocked()?;
+    state.ensure_not_frozen(&sender_address)?;
+    let reward_per_token = state.update_reward_per_token(current_time);
+    state.release_vested_rewards(current_time);
+
+    let mut sender_stake = state.stakes
+        .entry(sender_address)
+        .occupied_or(Error::NoStakeFound)?;
+
+    ensure!(!sender_stake.slashed, Error::AlreadySlashed);
+
+    // Get total rewards (pending + new)
+    state.reward_liability.remove(&sender_stake, reward_per_token, current_time);
+    sender_stake.accrue_rewards(
+        current_time,
+        reward_per_token,
+        state.max_accrual_seconds,
+        state.reward_rounding
+    );
+    let total_rewards = TokenAmountU64(sender_stake.pending_rewards);
+    ensure!(total_rewards.0 > 0, Error::NoRewardsAvailable);
+    if state.rewards_pool.0 < total_rewards.0 {
+        ensure!(state.auto_pause_on_depletion, Error::InsufficientRewardsPool);
+        state.reward_liability.add(&sender_stake, reward_per_token, current_time);
+        drop(sender_stake);
+        state.pause_on_depletion(logger, sender_address, total_rewards, current_time)?;
+        return Ok(TokenAmountU64(0));
+    }
+
+    // The resulting stake must reach the minimum stake
+    ensure!(
+        sender_stake.amount.saturating_add(total_rewards.0) >= state.minimum_stake.0,
+        Error::BelowMinimumStake
+    );
+
+    // A locked stake blends its rate with the current APR for the rewards added
+    if sender_stake.locked_apr.is_some() {
+        sender_stake.locked_apr = Some(
+            weighted_apr(
+                sender_stake.amount,
+                sender_stake.effective_apr(state.apr),
+                total_rewards.0,
+                state.apr
+            )
+        );
+    }
+
+    // Move the rewards into the principal
+    let was_participant = sender_stake.is_participant();
+    let is_top_up = sender_stake.amount > 0;
+    sender_stake.take_rewards(total_rewards.0);
+    sender_stake.lifetime_rewards_claimed =
+        sender_stake.lifetime_rewards_claimed.saturating_add(total_rewards.0);
+    sender_stake.add_position(total_rewards.0, current_time, current_time, 0)?;
+    state.reward_liability.add(&sender_stake, reward_per_token, current_time);
+    drop(sender_stake);
+
+    // Update contract state
+    state.ensure_within_stake_cap(total_rewards.0)?;
+    state.rewards_pool.0 = state.rewards_pool.0.saturating_sub(total_rewards.0);
+    state.total_rewards_paid.0 = state.total_rewards_paid.0.saturating_add(total_rewards.0);
+    state.total_staked = TokenAmountU64(state.total_staked.0.saturating_add(total_rewards.0));
+    state.update_participants(was_participant, true);
+    state.log_receipt_mint(logger, sender_address, total_rewards)?;
+
+    state.log_event(logger, Event::Claimed(ClaimEvent {
+        user: sender_address,
+        rewards_claimed: total_rewards,
+        claim_timestamp: current_time,
+        recipient: sender_address,
+    }))?;
+
+    state.log_event(logger, Event::Staked(StakeEvent {
+        user: sender_address,
+        stake_amount: total_rewards,
+        staked_timestamp: current_time,
+        total_staked_after: state.total_staked,
+        is_top_up,
+    }))?;
+
+    Ok(total_rewards)
+}
+
+/// Function to withdraw EUROe stablecoin
+/// Only the surplus of the contract balance over staked, unbonding and reward
+/// funds can be withdrawn.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "withdrawEuroe",
+    parameter = "WithdrawEuroEParams",
+    error = "Error",
+    mutable
+)]
+fn contract_withdraw_euroe(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let params: WithdrawEuroEParams = ctx.parameter_cursor().get()?;
+    let sender_address = only_account(&ctx.sender())?;
+    withdraw_euroe_helper(ctx, host, sender_address, params)?;
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(())
+}
+
+/// Transfers `params.amount` of the surplus to `params.withdraw_address` on
+/// behalf of `sender_address`, who must be the admin.
+fn withdraw_euroe_helper(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    sender_address: AccountAddress,
+    params: WithdrawEuroEParams
+) -> ContractResult<()> {
+    let state = host.state();
+    ensure!(sender_address == state.admin, Error::OnlyAdmin); // Access by admin only.
+
+    let contract_balance = Cis2Client::new(state.token_address).balance_of::<
+        State,
+        ContractTokenId,
+        TokenAmountU64,
+        Error
+    >(host, TOKEN_ID_EUROE, Address::Contract(ctx.self_address()))?;
+    // Only the surplus over staked, unbonding and reward funds is withdrawable
+    let surplus = contract_balance.0.saturating_sub(state.get_total_liabilities());
+    ensure!(params.amount.0 <= surplus, Error::InsufficientFunds); // Keep user funds backed.
+
+    transfer_euroe_token(
+        host,
+        Address::Contract(ctx.self_address()),
+        Receiver::Account(params.withdraw_address),
+        params.amount,
+        AdditionalData::empty(),
+        true
+    )?; // transfer EUROe token
+
+    Ok(()) // Return success
+}
+
+/// Function to withdraw excess funds from the rewards pool to the admin.
+/// The pool must still cover the rewards accrued by all stakers afterwards.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "withdrawExcessRewards",
+    parameter = "TokenAmountU64",
+    error = "Error",
+    mutable
+)]
+fn contract_withdraw_excess_rewards(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let amount: TokenAmountU64 = ctx.parameter_cursor().get()?;
+    let current_time = get_current_timestamp(ctx);
+
+    let admin = {
+        let state = host.state_mut();
+        ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+        // Keep the rewards owed to stakers in the pool
+        let remaining_pool = state.rewards_pool.0
+            .checked_sub(amount.0)
+            .ok_or(Error::InsufficientRewardsPool)?;
+        ensure!(
+            remaining_pool >= state.get_total_accrued_rewards(current_time),
+            Error::WouldUndercollateralize
+        );
+
+        state.rewards_pool = TokenAmountU64(remaining_pool);
+        state.admin
+    }; // state borrow ends here
+
+    transfer_euroe_token(
+        host,
+        Address::Contract(ctx.self_address()),
+        Receiver::Account(admin),
+        amount,
+        AdditionalData::empty(),
+        true
+    )?;
+
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to pause or unpause the concordium liquid staking contract
+/// Sets all pause flags at once.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setPaused",
+    parameter = "SetPausedParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_set_paused(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let params: SetPausedParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.paused = PauseFlags::all(params.paused);
+
+    state.log_event(logger, Event::PauseChanged(PauseChangedEvent {
+        paused: state.paused,
+        caller: state.admin,
+        update_timestamp: get_current_timestamp(ctx),
+    }))?;
+
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to enable or disable pausing reward payouts when a claim or
+/// restake finds the rewards pool too small. When enabled, such a call
+/// succeeds without paying, sets the `rewards_paused` flag and logs
+/// `PoolDepleted`, rather than failing. Unstaking never draws from the pool.
+/// The flag is cleared with `setPauseFlags` or `setPaused`.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setAutoPauseOnDepletion",
+    parameter = "bool",
+    error = "Error",
+    mutable
+)]
+fn contract_set_auto_pause_on_depletion(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let auto_pause_on_depletion: bool = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.auto_pause_on_depletion = auto_pause_on_depletion;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to transfer receipt tokens. Each transfer moves that much unlocked
+/// principal from the stake of `from` to the stake of the receiver, as in
+/// `State::transfer_stake`. The sender must be `from` or an operator of it.
+/// Receipt tokens can only be transferred to accounts.
+#[receive(
+    contract = "concordium_staking",
+    name = "transfer",
+    parameter = "TransferParameter",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_transfer(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let TransferParams(transfers): TransferParameter = ctx.parameter_cursor().get()?;
+    ensure_batch_size(transfers.len())?;
+    let sender = ctx.sender();
+    let current_time = get_current_timestamp(ctx);
+
+    let state = host.state_mut();
+    for Transfer { token_id, amount, from, to, data: _ } in transfers {
+        ensure!(token_id == TOKEN_ID_RECEIPT, Error::UnexpectedTokenId);
+        let owner = only_account(&from)?;
+        let receiver = match to {
+            Receiver::Account(account) => account,
+            Receiver::Contract(..) => bail!(Error::InvalidReceiver),
+        };
+        ensure!(
+            sender.matches_account(&owner) || state.is_operator(&sender, &owner),
+            Error::UnAuthorized
+        );
+
+        state.transfer_stake(owner, receiver, amount, current_time)?;
+        state.log_event(logger, Event::ReceiptTransfer(TransferEvent {
+            token_id,
+            amount,
+            from,
+            to: Address::Account(receiver),
+        }))?;
+    }
+
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to add or remove operators of the sender's receipt tokens.
+#[receive(
+    contract = "concordium_staking",
+    name = "updateOperator",
+    parameter = "UpdateOperatorParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_update_operator(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let UpdateOperatorParams(updates) = ctx.parameter_cursor().get()?;
+    ensure_batch_size(updates.len())?;
+    let owner = only_account(&ctx.sender())?;
+
+    let state = host.state_mut();
+    for UpdateOperator { update, operator } in updates {
+        match update {
+            OperatorUpdate::Add => state.operators.insert((owner, operator)),
+            OperatorUpdate::Remove => state.operators.remove(&(owner, operator)),
+        };
+        state.log_event(logger, Event::UpdateOperator(UpdateOperatorEvent {
+            update,
+            owner: Address::Account(owner),
+            operator,
+        }))?;
+    }
+
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to set the metadata URL of the receipt token.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setReceiptMetadataUrl",
+    parameter = "MetadataUrl",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_set_receipt_metadata_url(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let metadata_url: MetadataUrl = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.receipt_metadata_url = metadata_url.clone();
+    state.log_event(logger, Event::ReceiptMetadata(TokenMetadataEvent {
+        token_id: TOKEN_ID_RECEIPT,
+        metadata_url,
+    }))?;
+
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to pause or unpause individual groups of operations
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setPauseFlags",
+    parameter = "PauseFlags",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_set_pause_flags(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let params: PauseFlags = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.paused = params;
+
+    state.log_event(logger, Event::PauseChanged(PauseChangedEvent {
+        paused: state.paused,
+        caller: state.admin,
+        update_timestamp: get_current_timestamp(ctx),
+    }))?;
+
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to freeze or unfreeze an account. A frozen account cannot
+/// stake, unstake, claim, restake or complete unbonding, directly or through
+/// `permit`.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setFrozen",
+    parameter = "SetFrozenParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_set_frozen(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let params: SetFrozenParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    let event = FreezeEvent {
+        account: params.account,
+        update_timestamp: get_current_timestamp(ctx),
     };
+    if params.frozen {
+        state.frozen.insert(params.account);
+        state.log_event(logger, Event::Frozen(event))?;
+    } else {
+        state.frozen.remove(&params.account);
+        state.log_event(logger, Event::Unfrozen(event))?;
+    }
+
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to exempt an account from slashing or stop exempting it. The
+/// admin is always exempt.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setSlashExempt",
+    parameter = "SetSlashExemptParams",
+    error = "Error",
+    mutable
+)]
+fn contract_set_slash_exempt(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let params: SetSlashExemptParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    if params.exempt {
+        state.slash_exempt.insert(params.account);
+    } else {
+        state.slash_exempt.remove(&params.account);
+    }
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to update the APR.
+/// Rewards accrued so far are settled at the old APR.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "updateApr",
+    parameter = "UpdateAprParams",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn update_apr(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    _logger: &mut Logger
+) -> ContractResult<()> {
+    let params: UpdateAprParams = ctx.parameter_cursor().get()?; // Get request parameters.
+    let sender = ctx.sender(); // Get the sender's address.
+
+    let update_timestamp = get_current_timestamp(ctx); // Get the current timestamp.
+    let state = host.state_mut(); // Get the contract state.
+    ensure!(sender.matches_account(&state.admin), Error::OnlyAdmin); // Ensure only the admin can update the APR
+
+    // Reject out of bounds APRs
+    if let Some(max_apr) = state.max_apr {
+        ensure!(params.new_apr <= max_apr, Error::AprTooHigh);
+    }
+    ensure!(!(state.forbid_zero_apr && params.new_apr == 0), Error::ZeroApr);
+
+    // Limit how fast the APR can change
+    ensure!(
+        update_timestamp >= state.last_apr_update.saturating_add(state.min_apr_update_interval),
+        Error::AprUpdateTooSoon
+    );
+    if let Some(max_apr_change_bps) = state.max_apr_change_bps {
+        ensure!(
+            params.new_apr.abs_diff(state.apr) <= max_apr_change_bps,
+            Error::AprChangeTooLarge
+        );
+    }
+
+    // Checkpoint rewards accrued at the old APR before switching.
+    state.update_reward_per_token(update_timestamp);
+
+    state.apr = params.new_apr; // Update the APR.
+    state.record_apr(update_timestamp, params.new_apr);
+    state.last_apr_update = update_timestamp;
+    state.log_event(
+        _logger,
+        Event::AprUpdated(UpdateAprEvent {
+            new_apr: params.new_apr,
+            update_timestamp,
+        })
+    )?; // Log APR update event.
+
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to propose a new admin. The proposed account becomes admin once
+/// it calls `acceptAdmin`.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "proposeAdmin",
+    parameter = "AccountAddress",
+    error = "Error",
+    mutable
+)]
+fn contract_propose_admin(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let new_admin: AccountAddress = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+    ensure_valid_admin(&new_admin)?;
+
+    state.pending_admin = Some(new_admin);
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to accept the admin role.
+/// Access by the proposed admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "acceptAdmin",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_accept_admin(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let sender_address = only_account(&ctx.sender())?;
+    let state = host.state_mut();
+    ensure!(state.pending_admin == Some(sender_address), Error::UnAuthorized);
+
+    let previous_admin = state.admin;
+    state.admin = sender_address;
+    state.pending_admin = None;
+
+    state.log_event(logger, Event::AdminTransferred(AdminTransferredEvent {
+        previous_admin,
+        new_admin: sender_address,
+    }))?;
+
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to switch between epoch-based and seconds-based unbonding.
+/// Already queued unbonding entries keep their unlock time.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setEpochConfig",
+    parameter = "SetEpochConfigParams",
+    error = "Error",
+    mutable
+)]
+fn contract_set_epoch_config(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let params: SetEpochConfigParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    if let Some(config) = params.epoch_config {
+        ensure!(config.epoch_length > 0, Error::InvalidEpochConfig);
+    }
+
+    state.epoch_config = params.epoch_config;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to set the minimum active stake required after staking.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setMinimumStake",
+    parameter = "TokenAmountU64",
+    error = "Error",
+    mutable
+)]
+fn contract_set_minimum_stake(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let minimum_stake: TokenAmountU64 = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.minimum_stake = minimum_stake;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to set the maximum total stake, 0 for unlimited.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setMaxTotalStaked",
+    parameter = "TokenAmountU64",
+    error = "Error",
+    mutable
+)]
+fn contract_set_max_total_staked(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let max_total_staked: TokenAmountU64 = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.max_total_staked = max_total_staked;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to set the minimum number of seconds between two claims of a
+/// staker, 0 to disable the cooldown.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setClaimCooldown",
+    parameter = "u64",
+    error = "Error",
+    mutable
+)]
+fn contract_set_claim_cooldown(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let claim_cooldown_seconds: u64 = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.claim_cooldown_seconds = claim_cooldown_seconds;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to set or clear the maximum amount paid by a single claim.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setMaxRewardPerClaim",
+    parameter = "SetMaxRewardPerClaimParams",
+    error = "Error",
+    mutable
+)]
+fn contract_set_max_reward_per_claim(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let params: SetMaxRewardPerClaimParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.max_reward_per_claim = params.max_reward_per_claim;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to set or clear the reward accrual cap.
+/// Rewards stop growing `max_accrual_seconds` after a staker last interacted,
+/// which bounds the rewards the contract can owe to inactive stakers.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setMaxAccrualSeconds",
+    parameter = "SetMaxAccrualSecondsParams",
+    error = "Error",
+    mutable
+)]
+fn contract_set_max_accrual_seconds(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let params: SetMaxAccrualSecondsParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.max_accrual_seconds = params.max_accrual_seconds;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to set the rounding of rewards to token units. Each position
+/// carries the remainder to its next accrual, so the mode only decides
+/// whether a fraction of a unit is paid now or later.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setRewardRounding",
+    parameter = "RoundingMode",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_set_reward_rounding(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let new_rounding: RoundingMode = ctx.parameter_cursor().get()?;
+    let current_time = get_current_timestamp(ctx);
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    // Checkpoint the rewards accrued under the previous mode
+    state.update_reward_per_token(current_time);
+    let old_rounding = state.reward_rounding;
+    state.reward_rounding = new_rounding;
+
+    state.log_event(logger, Event::RewardRoundingUpdated(UpdateRewardRoundingEvent {
+        old_rounding,
+        new_rounding,
+        update_timestamp: current_time,
+    }))?;
+
+    state.touch(current_time);
+    Ok(()) // Return success
+}
+
+/// Function to set the protocol fee on claimed rewards and its recipient.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setRewardFee",
+    parameter = "SetRewardFeeParams",
+    error = "Error",
+    mutable
+)]
+fn contract_set_reward_fee(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let params: SetRewardFeeParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+    ensure!(params.reward_fee_bps <= BASIS_POINTS_DENOMINATOR, Error::InvalidRewardFee);
+
+    state.reward_fee_bps = params.reward_fee_bps;
+    state.fee_recipient = params.fee_recipient;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to limit how much and how often `updateApr` can change the APR.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setAprChangeLimits",
+    parameter = "SetAprChangeLimitsParams",
+    error = "Error",
+    mutable
+)]
+fn contract_set_apr_change_limits(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let params: SetAprChangeLimitsParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.max_apr_change_bps = params.max_apr_change_bps;
+    state.min_apr_update_interval = params.min_apr_update_interval;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to bound the APR accepted by `updateApr`. The current APR is not
+/// checked against the new bounds.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setAprBounds",
+    parameter = "SetAprBoundsParams",
+    error = "Error",
+    mutable
+)]
+fn contract_set_apr_bounds(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let params: SetAprBoundsParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.max_apr = params.max_apr;
+    state.forbid_zero_apr = params.forbid_zero_apr;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to enable or disable APR locking for new stakes.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setAprLocking",
+    parameter = "SetAprLockingParams",
+    error = "Error",
+    mutable
+)]
+fn contract_set_apr_locking(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let params: SetAprLockingParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.apr_locking = params.apr_locking;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to set the lock tiers stakers can commit to.
+/// Existing positions keep their lock and bonus.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setLockTiers",
+    parameter = "SetLockTiersParams",
+    error = "Error",
+    mutable
+)]
+fn contract_set_lock_tiers(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let params: SetLockTiersParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+    ensure_batch_size(params.lock_tiers.len())?;
+    ensure!(
+        params.lock_tiers.iter().all(|tier| tier.lock_days > 0),
+        Error::InvalidLockTier
+    );
+
+    state.lock_tiers = params.lock_tiers;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to set or clear the price oracle.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setPriceOracle",
+    parameter = "SetPriceOracleParams",
+    error = "Error",
+    mutable
+)]
+fn contract_set_price_oracle(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let params: SetPriceOracleParams = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    state.price_oracle = params.price_oracle;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
+
+/// Function to update the unbonding period.
+/// Already queued unbonding entries keep their unlock time.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "setUnbondingPeriod",
+    parameter = "u64",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_set_unbonding_period(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let new_unbonding_period: u64 = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+
+    let old_unbonding_period = state.unbonding_period;
+    state.unbonding_period = new_unbonding_period;
 
-    Ok(state)
+    state.log_event(logger, Event::UnbondingPeriodUpdated(UpdateUnbondingPeriodEvent {
+        old_unbonding_period,
+        new_unbonding_period,
+        update_timestamp: get_current_timestamp(ctx),
+    }))?;
+
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
 }
 
-/// Receive cis-2 token
+/// Function to change the EUROe token contract, e.g. after the token
+/// migrated. Only allowed while nothing is staked, unbonding or held for
+/// rewards, so no funds are left in the old token, and only to a contract
+/// passing the checks of `verifyTokenIntegration`.
+/// Access by admin only.
 #[receive(
     contract = "concordium_staking",
-    name = "onReceivingCIS2",
-    error = "Error"
+    name = "setTokenAddress",
+    parameter = "ContractAddress",
+    error = "Error",
+    mutable,
+    enable_logger
 )]
-fn contract_on_cis2_received<S: HasStateApi>(
-    _ctx: &impl HasReceiveContext,
-    _host: &impl HasHost<State, StateApiType = S>
+fn contract_set_token_address(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
 ) -> ContractResult<()> {
-    Ok(())
+    let new_token_address: ContractAddress = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+    ensure!(
+        state.total_staked.0 == 0 &&
+            state.total_unbonding.0 == 0 &&
+            state.rewards_pool.0 == 0 &&
+            state.locked_rewards_pool.0 == 0,
+        Error::TokenFundsOutstanding
+    );
+    ensure!(
+        is_token_integrated(ctx, host, new_token_address),
+        Error::InvalidTokenContract
+    );
+
+    let state = host.state_mut();
+
+    let old_token_address = state.token_address;
+    state.token_address = new_token_address;
+
+    state.log_event(logger, Event::TokenAddressUpdated(UpdateTokenAddressEvent {
+        old_token_address,
+        new_token_address,
+        update_timestamp: get_current_timestamp(ctx),
+    }))?;
+
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
 }
 
-/// Verify an ed25519 signature and allow the unstake, claimRewards.
+/// Function to update the slashing rate.
+/// Access by admin only.
 #[receive(
     contract = "concordium_staking",
-    name = "permit",
-    parameter = "PermitParam",
+    name = "setSlashingRate",
+    parameter = "u64",
     error = "Error",
-    crypto_primitives,
     mutable,
     enable_logger
 )]
-fn contract_permit(
+fn contract_set_slashing_rate(
     ctx: &ReceiveContext,
     host: &mut Host<State>,
-    _logger: &mut Logger,
-    crypto_primitives: &impl HasCryptoPrimitives
+    logger: &mut Logger
 ) -> ContractResult<()> {
-    // Check if the contract is paused.
-    ensure!(!host.state().paused, Error::ContractPaused);
+    let new_slashing_rate: u64 = ctx.parameter_cursor().get()?;
+    let state = host.state_mut();
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+    ensure_valid_slashing_rate(new_slashing_rate)?;
 
-    // Parse the parameter.
-    let param: PermitParam = ctx.parameter_cursor().get()?;
+    let old_slashing_rate = state.slashing_rate;
+    state.slashing_rate = new_slashing_rate;
 
-    // Update the nonce.
-    let mut entry = host
-        .state_mut()
-        .nonces_registry.entry(param.signer)
-        .or_insert_with(|| 0);
+    state.log_event(logger, Event::SlashingRateUpdated(UpdateSlashingRateEvent {
+        old_slashing_rate,
+        new_slashing_rate,
+        update_timestamp: get_current_timestamp(ctx),
+    }))?;
 
-    // Get the current nonce.
-    let nonce = *entry;
+    state.touch(get_current_timestamp(ctx));
+    Ok(()) // Return success
+}
 
-    // Bump nonce.
-    *entry += 1;
-    drop(entry);
+/// Upgrade this smart contract instance to a new module and call optionally a
+/// migration function after the upgrade.
+///
+/// It rejects if:
+/// - Sender is not the admin of the contract instance.
+/// - It fails to parse the parameter.
+/// - If the ugrade fails.
+/// - If the migration invoke fails.
+///
+/// This function is marked as `low_level`. This is **necessary** since the
+/// high-level mutable functions store the state of the contract at the end of
+/// execution. This conflicts with migration since the shape of the state
+/// **might** be changed by the migration function. If the state is then written
+/// by this function it would overwrite the state stored by the migration
+/// function.
+#[receive(
+    contract = "concordium_staking",
+    name = "upgrade",
+    parameter = "UpgradeParams",
+    error = "Error",
+    low_level
+)]
+fn contract_upgrade(
+    ctx: &ReceiveContext,
+    host: &mut LowLevelHost
+) -> ContractResult<()> {
+    let state: State = host.state().read_root()?; // Read the top-level contract state.
+    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin); // Check that only the admin is authorized to upgrade the smart contract.
+    let params: UpgradeParams = ctx.parameter_cursor().get()?; // Parse the parameter.
 
-    let message = param.message;
+    upgrade_helper(ctx, host, params)
+}
 
-    ensure_eq!(message.nonce, nonce, Error::NonceMismatch); // Check the nonce to prevent replay attacks.
+/// Upgrades to `params.module` and calls the migration function of
+/// `params`, if any.
+fn upgrade_helper(
+    ctx: &ReceiveContext,
+    host: &mut LowLevelHost,
+    params: UpgradeParams
+) -> ContractResult<()> {
+    host.upgrade(params.module)?; // Trigger the upgrade.
+    if let Some((func, parameters)) = params.migrate {
+        host.invoke_contract_raw(
+            &ctx.self_address(),
+            parameters.as_parameter(),
+            func.as_entrypoint_name(),
+            Amount::zero()
+        )?;
+    } // Call the migration function if provided.
 
-    ensure_eq!(
-        message.contract_address,
-        ctx.self_address(),
-        Error::WrongContract
-    ); // Check that the signature was intended for this contract.
+    Ok(()) // Return success
+}
 
-    ensure!(message.timestamp > ctx.metadata().slot_time(), Error::Expired); // Check signature is not expired.
+/// Migrate the state after an upgrade, by passing `migrate` in the
+/// `UpgradeParams`. Reads the state stored by the previous module, fills in
+/// defaults it lacks and records the `CONTRACT_VERSION` of this module. A
+/// state in the layout of the first module, `StateV0`, is converted to the
+/// current layout. A module changing the layout of `State` adds its previous
+/// layout here.
+///
+/// It rejects if:
+/// - It is not invoked by the contract itself.
+/// - It fails to read the state in either layout.
+///
+/// This function is marked as `low_level` for the same reason as `upgrade`:
+/// it writes the state itself.
+#[receive(
+    contract = "concordium_staking",
+    name = "migrate",
+    error = "Error",
+    low_level
+)]
+fn contract_migrate(
+    ctx: &ReceiveContext,
+    host: &mut LowLevelHost
+) -> ContractResult<()> {
+    // Only `upgrade` invokes the migration
+    ensure!(ctx.sender().matches_contract(&ctx.self_address()), Error::OnlySelf);
+
+    // Read the previous state. The root of the first module is too short
+    // for the current layout.
+    let mut state: State = match host.state().read_root() {
+        Ok(state) => state,
+        Err(_) => {
+            let state_v0: StateV0 = host.state().read_root()?;
+            let mut state_builder = StateBuilder::open(host.state().clone());
+            state_v0.migrate(&mut state_builder, get_current_timestamp(ctx))
+        }
+    };
 
-    let message_hash = contract_view_message_hash(
-        ctx,
-        host,
-        crypto_primitives
-    )?;
+    // The APR history starts with the APR in effect at the migration
+    if state.apr_history.is_empty() {
+        state.record_apr(get_current_timestamp(ctx), state.apr);
+    }
+    state.version = CONTRACT_VERSION;
+    state.last_mutation_time = get_current_timestamp(ctx);
 
-    let valid_signature = host.check_account_signature(
-        param.signer,
-        &param.signature,
-        &message_hash
-    )?; // Check signature.
+    host.state_mut().write_root(&state); // Store the migrated state.
+    Ok(()) // Return success
+}
 
-    ensure!(valid_signature, Error::WrongSignature);
+/// Get current nonce of a user
+#[receive(
+    contract = "concordium_staking",
+    name = "getUserNonce",
+    parameter = "AccountAddress",
+    error = "Error",
+    return_value = "u64"
+)]
+fn contract_get_user_nonce(
+    ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<u64> {
+    let user: AccountAddress = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    Ok(state.get_user_nonce(&user))
+}
 
-    if
-        message.entry_point.as_entrypoint_name() ==
-        EntrypointName::new_unchecked("unstake")
-    {
-        let payload: UnstakeParams = from_bytes(&message.payload)?;
-        unstake_helper(ctx, host, _logger, param.signer, payload.amount)?;
-    } else if
-        // claim
-        message.entry_point.as_entrypoint_name() ==
-        EntrypointName::new_unchecked("claimRewards")
-    {
-        claim_rewards_helper(ctx, host, _logger, param.signer)?;
-    } else {
-        // no entrypoint
-        bail!(Error::WrongEntryPoint);
+/// Check whether a permit with the given nonce was consumed.
+/// Nonces are strictly sequential: a permit must use the current nonce of its
+/// signer, so every nonce below it has been used and none above it.
+#[receive(
+    contract = "concordium_staking",
+    name = "isNonceUsed",
+    parameter = "IsNonceUsedParams",
+    error = "Error",
+    return_value = "bool"
+)]
+fn contract_is_nonce_used(
+    ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<bool> {
+    let params: IsNonceUsedParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    Ok(params.nonce < state.get_user_nonce(&params.account))
+}
+
+/// Helper function that can be invoked at the front-end to serialize the
+/// `PermitMessage` before signing it in the wallet.
+#[receive(
+    contract = "concordium_staking",
+    name = "serializationHelper",
+    parameter = "PermitMessage"
+)]
+fn contract_serialization_helper(
+    _ctx: &ReceiveContext,
+    _host: &Host<State>
+) -> ContractResult<()> {
+    Ok(())
+}
+
+/// Calculates the message hash
+/// The contract can only be called by any account
+/// Returns message hash
+///
+/// It rejects if:
+/// - It fails to parse the parameter
+#[receive(
+    contract = "concordium_staking",
+    name = "viewMessageHash",
+    parameter = "PermitParam",
+    return_value = "[u8;32]",
+    crypto_primitives
+)]
+fn contract_view_message_hash<S: HasStateApi>(
+    ctx: &ReceiveContext,
+    _host: &impl HasHost<State<S>, StateApiType = S>,
+    crypto_primitives: &impl HasCryptoPrimitives
+) -> ContractResult<[u8; 32]> {
+    Ok(compute_message_hash(ctx, crypto_primitives)?.message_hash)
+}
+
+/// Calculates the message hash like `viewMessageHash`, from the signer and
+/// the message only, so it can be computed before signing.
+///
+/// It rejects if:
+/// - It fails to parse the parameter
+#[receive(
+    contract = "concordium_staking",
+    name = "hashPermitMessage",
+    parameter = "HashPermitMessageParams",
+    return_value = "[u8;32]",
+    error = "Error",
+    crypto_primitives
+)]
+fn contract_hash_permit_message(
+    ctx: &ReceiveContext,
+    _host: &Host<State>,
+    crypto_primitives: &impl HasCryptoPrimitives
+) -> ContractResult<[u8; 32]> {
+    let mut cursor = ctx.parameter_cursor();
+    let signer: AccountAddress = cursor.get()?;
+
+    // Read the serialized `PermitMessage` as it is, like `viewMessageHash`.
+    let mut message_bytes =
+        vec![0; (cursor.size() - cursor.cursor_position()) as usize];
+    cursor.read_exact(&mut message_bytes)?;
+
+    Ok(hash_permit_message(&signer, &message_bytes, crypto_primitives).message_hash)
+}
+
+/// Calculates the message hash like `viewMessageHash` and also returns the
+/// prepended bytes and the message length, to debug failing signatures.
+#[receive(
+    contract = "concordium_staking",
+    name = "viewMessageHashDetailed",
+    parameter = "PermitParam",
+    return_value = "MessageHashResult",
+    error = "Error",
+    crypto_primitives
+)]
+fn contract_view_message_hash_detailed(
+    ctx: &ReceiveContext,
+    _host: &Host<State>,
+    crypto_primitives: &impl HasCryptoPrimitives
+) -> ContractResult<MessageHashResult> {
+    compute_message_hash(ctx, crypto_primitives)
+}
+
+/// Get the standards supported by this contract given a list of standard
+/// identifiers (CIS0).
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The number of queries exceeds `MAX_BATCH_SIZE`.
+#[receive(
+    contract = "concordium_staking",
+    name = "supports",
+    parameter = "SupportsQueryParams",
+    return_value = "SupportsQueryResponse",
+    error = "Error"
+)]
+fn contract_supports(
+    ctx: &ReceiveContext,
+    _host: &Host<State>
+) -> ContractResult<SupportsQueryResponse> {
+    // Parse the parameter.
+    let params: SupportsQueryParams = ctx.parameter_cursor().get()?;
+    ensure_batch_size(params.queries.len())?;
+
+    // Build the response.
+    let mut response = Vec::with_capacity(params.queries.len());
+    for std_id in params.queries {
+        if SUPPORTS_STANDARDS.contains(&std_id.as_standard_identifier()) {
+            response.push(SupportResult::Support);
+        } else {
+            response.push(SupportResult::NoSupport);
+        }
     }
+    Ok(SupportsQueryResponse::from(response))
+}
 
-    // Log the nonce event.
-    _logger.log(
-        &Event::Nonce(NonceEvent {
-            account: param.signer,
-            nonce,
-        })
-    )?;
+/// Get the entrypoints supported by the `permit` function given a
+/// list of entrypoints.
+///
+/// It rejects if:
+/// - It fails to parse the parameter.
+/// - The number of queries exceeds `MAX_BATCH_SIZE`.
+#[receive(
+    contract = "concordium_staking",
+    name = "supportsPermit",
+    parameter = "SupportsPermitQueryParams",
+    return_value = "SupportsQueryResponse",
+    error = "Error"
+)]
+fn contract_supports_permit<S: HasStateApi>(
+    ctx: &ReceiveContext,
+    _host: &impl HasHost<State<S>, StateApiType = S>
+) -> ContractResult<SupportsQueryResponse> {
+    // Parse the parameter.
+    let params: SupportsPermitQueryParams = ctx.parameter_cursor().get()?;
+    ensure_batch_size(params.queries.len())?;
 
-    Ok(())
+    // Build the response.
+    let mut response = Vec::with_capacity(params.queries.len());
+    for entrypoint in params.queries {
+        if
+            SUPPORTS_PERMIT_ENTRYPOINTS.contains(
+                &entrypoint.as_entrypoint_name()
+            )
+        {
+            response.push(SupportResult::Support);
+        } else {
+            response.push(SupportResult::NoSupport);
+        }
+    }
+    let result = SupportsQueryResponse::from(response);
+    Ok(result)
 }
 
-/// Function to stake tokens.
+/// Function to verify that the configured token is a CIS-2 contract
+/// supporting the operations the staking contract relies on.
+/// Returns true if the token reports CIS-2 support and answers a `balanceOf`
+/// query for this contract.
+///
+/// Init functions cannot invoke other contracts, so this check is meant to be
+/// called right after deployment to catch a misconfigured `token_address`.
 #[receive(
     contract = "concordium_staking",
-    name = "stake",
-    parameter = "OnReceivingCis2DataParams<ContractTokenId, TokenAmountU64,AdditionalData>",
-    error = "Error",
-    mutable,
-    enable_logger
+    name = "verifyTokenIntegration",
+    return_value = "bool",
+    error = "Error"
 )]
-fn contract_stake(
+fn contract_verify_token_integration(
     ctx: &ReceiveContext,
-    host: &mut Host<State>,
-    logger: &mut Logger
-) -> ContractResult<()> {
-    let state = host.state_mut();
-    // Check if sender is the token contract
-    if !ctx.sender().matches_contract(&state.token_address) {
-        bail!(Error::NotTokenContract);
-    }
+    host: &Host<State>
+) -> ContractResult<bool> {
+    Ok(is_token_integrated(ctx, host, host.state().token_address))
+}
 
-    let params: OnReceivingCis2DataParams<
+/// Whether `token_address` reports CIS-2 support and answers a `balanceOf`
+/// query for this contract, see `verifyTokenIntegration`.
+fn is_token_integrated(
+    ctx: &ReceiveContext,
+    host: &Host<State>,
+    token_address: ContractAddress
+) -> bool {
+    let client = Cis2Client::new(token_address);
+
+    let supports_cis2 = matches!(
+        client.supports_cis2::<State, Error>(host),
+        Ok(SupportResult::Support)
+    );
+
+    let balance_query = client.balance_of::<
+        State,
         ContractTokenId,
         TokenAmountU64,
-        AdditionalData
-    > = ctx.parameter_cursor().get()?;
-
-    ensure!(params.token_id == TOKEN_ID_EUROE, Error::InvalidResponse);
-
-    let sender_address = only_account(&params.from)?;
-    let unix_timestamp = get_current_timestamp(ctx);
-    let amount = params.amount;
-
-    ensure!(!state.paused, Error::ContractPaused);
-    ensure!(amount.gt(&TokenAmountU64(0)), Error::InvalidStakeAmount);
-
-    // Get or create stake info
-    let is_new_staker = state.stakes.get(&sender_address).is_none();
-    let mut sender_stake = state.stakes
-        .entry(sender_address)
-        .or_insert_with(|| StakeInfo {
-            amount: 0,
-            timestamp: unix_timestamp,
-            unbonding: Vec::new(),
-            slashed: false,
-            pending_rewards: 0,
-        });
-
-    // Calculate pending rewards before updating stake
-    if sender_stake.amount > 0 {
-        let new_rewards = calculate_reward(
-            sender_stake.amount,
-            sender_stake.timestamp,
-            unix_timestamp,
-            state.apr
-        );
-        sender_stake.pending_rewards = sender_stake.pending_rewards.saturating_add(new_rewards);
-    }
+        Error
+    >(host, TOKEN_ID_EUROE, Address::Contract(ctx.self_address()));
 
-    // Update stake amount and timestamp
-    sender_stake.amount = sender_stake.amount.saturating_add(amount.0);
-    sender_stake.timestamp = unix_timestamp;
-
-    // Update total staked and participants
-    state.total_staked = TokenAmountU64(state.total_staked.0.saturating_add(amount.0));
-    if is_new_staker {
-        state.total_participants = state.total_participants.saturating_add(1);
-    }
+    supports_cis2 && balance_query.is_ok()
+}
 
-    logger.log(&Event::Staked(StakeEvent {
-        user: sender_address,
-        stake_amount: amount,
-        staked_timestamp: unix_timestamp,
-    }))?;
+/// View function to get the static configuration of the contract
+#[receive(
+    contract = "concordium_staking",
+    name = "metadata",
+    return_value = "MetadataResult",
+    error = "Error"
+)]
+fn contract_metadata(
+    _ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<MetadataResult> {
+    let state = host.state();
 
-    Ok(())
+    Ok(MetadataResult {
+        token_address: state.token_address,
+        apr_denominator: APR_DENOMINATOR,
+        supported_permit_entrypoints: SUPPORTS_PERMIT_ENTRYPOINTS
+            .iter()
+            .map(|entrypoint| entrypoint.to_owned())
+            .collect(),
+        version: state.version,
+    })
 }
 
-/// Function to unstake tokens.
+/// Function to compare the EUROe balance of the contract with its
+/// obligations: the staked amount, queued unbonding and the rewards pool.
 #[receive(
     contract = "concordium_staking",
-    name = "unstake",
-    parameter = "UnstakeParams",
-    error = "Error",
-    mutable,
-    enable_logger
+    name = "solvency",
+    return_value = "SolvencyResult",
+    error = "Error"
 )]
-fn contract_unstake(
+fn contract_solvency(
     ctx: &ReceiveContext,
-    host: &mut Host<State>,
-    _logger: &mut Logger
-) -> ContractResult<()> {
-    let param: UnstakeParams = ctx.parameter_cursor().get()?;
-    let sender_address = only_account(&ctx.sender())?;
-    
-    let state = host.state_mut();
-    ensure!(!state.paused, Error::ContractPaused);
-
-    let mut sender_stake = state.stakes
-        .entry(sender_address)
-        .occupied_or(Error::NoStakeFound)?;
-
-    ensure!(!sender_stake.slashed, Error::AlreadySlashed);
-    ensure!(sender_stake.amount >= param.amount.0, Error::InvalidUnstakeAmount);
+    host: &Host<State>
+) -> ContractResult<SolvencyResult> {
+    let state = host.state();
+    let balance = Cis2Client::new(state.token_address).balance_of::<
+        State,
+        ContractTokenId,
+        TokenAmountU64,
+        Error
+    >(host, TOKEN_ID_EUROE, Address::Contract(ctx.self_address()))?;
+    let obligations = TokenAmountU64(state.get_total_liabilities());
+
+    Ok(SolvencyResult {
+        balance,
+        obligations,
+        solvent: balance >= obligations,
+    })
+}
 
-    let current_time = get_current_timestamp(ctx);
-    let unlock_time = current_time + state.unbonding_period;
+/// Function to get metrics derived from the global state: the APY, the
+/// utilization of the rewards and the average stake. Ratios are expressed in
+/// basis points and are zero when nothing has been funded or staked.
+#[receive(
+    contract = "concordium_staking",
+    name = "globalStats",
+    return_value = "GlobalStatsResult",
+    error = "Error"
+)]
+fn contract_global_stats(
+    _ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<GlobalStatsResult> {
+    let state = host.state();
 
-    // Add to unbonding list
-    sender_stake.unbonding.push(UnbondingInfo {
-        amount: param.amount,
-        unlock_time,
-    });
+    let total_funded = (state.total_rewards_paid.0 as u128) + (state.rewards_pool.0 as u128);
+    let utilization = ((state.total_rewards_paid.0 as u128) * (BASIS_POINTS_DENOMINATOR as u128))
+        .checked_div(total_funded)
+        .unwrap_or(0) as u64;
 
-    // Update stake amount
-    sender_stake.amount -= param.amount.0;
-    state.total_staked -= param.amount;
+    let average_stake = state.total_staked.0
+        .checked_div(state.total_participants)
+        .unwrap_or(0);
 
-    _logger.log(&Event::Unstaked(UnstakeEvent {
-        user: sender_address,
-        unstaked_amount: param.amount,
-        unix_timestamp: current_time,
-        rewards_earned: TokenAmountU64(0), // Rewards claimed separately
-    }))?;
+    Ok(GlobalStatsResult {
+        apr: state.apr,
+        apy: compounded_apr(state.apr),
+        utilization,
+        average_stake: TokenAmountU64(average_stake),
+    })
+}
 
-    Ok(())
+/// View function to get contract state
+#[receive(
+    contract = "concordium_staking",
+    name = "view",
+    return_value = "ViewResult"
+)]
+fn contract_view(
+    _ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<ViewResult> {
+    let state = host.state();
+    
+    Ok(ViewResult {
+        paused: state.paused.any(),
+        admin: state.admin,
+        total_staked: state.total_staked.0,
+        apr: state.apr,
+        token_address: state.token_address,
+        total_participants: state.total_participants,
+        total_rewards_paid: state.total_rewards_paid.0,
+        rewards_pool: state.rewards_pool.0,
+        unbonding_period: state.unbonding_period,
+        slashing_rate: state.slashing_rate,
+        pause_flags: state.paused,
+        total_unbonding: state.total_unbonding.0,
+        locked_rewards_pool: state.locked_rewards_pool.0,
+        last_mutation_time: state.last_mutation_time,
+    })
 }
 
-/// Function to claim rewards.
+/// Function to retrieve specific user stake, with the principal split into
+/// the earning amount and the non-earning unbonding amount
 #[receive(
     contract = "concordium_staking",
-    name = "claimRewards",
-    error = "Error",
-    mutable,
-    enable_logger
+    name = "getStakeInfo",
+    parameter = "AccountAddress",
+    return_value = "StakeInfoResult",
+    error = "Error"
 )]
-fn contract_claim_rewards(
+fn contract_get_stake_info(
     ctx: &ReceiveContext,
-    host: &mut Host<State>,
-    _logger: &mut Logger
-) -> ContractResult<()> {
-    let sender_address = only_account(&ctx.sender())?;
-    claim_rewards_helper(ctx, host, _logger, sender_address)
+    host: &Host<State>
+) -> ContractResult<StakeInfoResult> {
+    let user: AccountAddress = ctx.parameter_cursor().get()?;
+    let stake = host.state().get_stake_info(&user, get_current_timestamp(ctx));
+    Ok(StakeInfoResult {
+        earning_amount: stake.amount,
+        unbonding_amount: stake.unbonding_amount(),
+        stake,
+    })
 }
 
-/// Function to withdraw EUROe stablecoin
-/// Access by contract owner only.
+/// Function to retrieve a user's stake with the rewards accrued up to
+/// `at_timestamp` instead of the current block time, for example to show
+/// what the stake looked like at a past moment. Fails if `at_timestamp` is
+/// before the last update of the stake.
 #[receive(
     contract = "concordium_staking",
-    name = "withdrawEuroe",
-    parameter = "WithdrawEuroEParams",
-    error = "Error",
-    mutable
+    name = "getStakeInfoAt",
+    parameter = "GetStakeInfoAtParams",
+    return_value = "StakeInfo",
+    error = "Error"
 )]
-fn contract_withdraw_euroe(
+fn contract_get_stake_info_at(
     ctx: &ReceiveContext,
-    host: &mut Host<State>
-) -> ContractResult<()> {
-    let params: WithdrawEuroEParams = ctx.parameter_cursor().get()?;
-    let sender = ctx.sender();
-    ensure!(sender.matches_account(&ctx.owner()), Error::UnAuthorized); // Access by contract owner only.
-
-    transfer_euroe_token(
-        host,
-        Address::Contract(ctx.self_address()),
-        Receiver::Account(params.withdraw_address),
-        params.amount,
-        true
-    )?; // transfer EUROe token
-
-    Ok(()) // Return success
+    host: &Host<State>
+) -> ContractResult<StakeInfo> {
+    let params: GetStakeInfoAtParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    let stake = state.stakes.get(&params.user).ok_or(Error::NoStakeFound)?;
+    ensure!(params.at_timestamp >= stake.timestamp, Error::TimestampBeforeStake);
+    let reward_per_token = state
+        .reward_per_token_at(params.at_timestamp)
+        .ok_or(Error::AprHistoryUnavailable)?;
+
+    let mut stake_info = stake.clone();
+    stake_info.accrue_rewards(
+        params.at_timestamp,
+        reward_per_token,
+        state.max_accrual_seconds,
+        state.reward_rounding
+    );
+    Ok(stake_info)
 }
 
-/// Function to pause or unpause the concordium liquid staking contract
-/// Access by contract owner only.
+/// Function to get the active staked amount of each queried address, shaped
+/// like the CIS-2 `balanceOf` query so stakes can be read like token
+/// balances. Contract addresses have no stake and get 0.
 #[receive(
     contract = "concordium_staking",
-    name = "setPaused",
-    parameter = "SetPausedParams",
-    error = "Error",
-    mutable
+    name = "stakedBalanceOf",
+    parameter = "BalanceOfQueryParams<ContractTokenId>",
+    return_value = "BalanceOfQueryResponse<TokenAmountU64>",
+    error = "Error"
 )]
-fn contract_set_paused(
+fn contract_staked_balance_of(
     ctx: &ReceiveContext,
-    host: &mut Host<State>
-) -> ContractResult<()> {
-    let params: SetPausedParams = ctx.parameter_cursor().get()?;
-    let sender = ctx.sender();
-    ensure!(sender.matches_account(&ctx.owner()), Error::UnAuthorized);
+    host: &Host<State>
+) -> ContractResult<BalanceOfQueryResponse<TokenAmountU64>> {
+    let params: BalanceOfQueryParams<ContractTokenId> = ctx.parameter_cursor().get()?;
+    ensure_batch_size(params.queries.len())?;
 
-    let state = host.state_mut();
-    state.paused = params.paused;
-    Ok(()) // Return success
+    let state = host.state();
+    let response: Vec<TokenAmountU64> = params.queries
+        .iter()
+        .map(|query| match query.address {
+            Address::Account(account) => state.get_user_stake(&account).0,
+            Address::Contract(_) => TokenAmountU64(0),
+        })
+        .collect();
+    Ok(BalanceOfQueryResponse::from(response))
 }
 
-/// Function to update the APR.
-/// Access by contract owner only.
+/// Function to get the receipt token balances, which are the active stakes
+/// as returned by `stakedBalanceOf`.
 #[receive(
     contract = "concordium_staking",
-    name = "updateApr",
-    parameter = "UpdateAprParams",
-    error = "Error",
-    mutable,
-    enable_logger
+    name = "balanceOf",
+    parameter = "BalanceOfQueryParams<ContractTokenId>",
+    return_value = "BalanceOfQueryResponse<TokenAmountU64>",
+    error = "Error"
 )]
-fn update_apr(
+fn contract_balance_of(
     ctx: &ReceiveContext,
-    host: &mut Host<State>,
-    _logger: &mut Logger
-) -> ContractResult<()> {
-    let params: UpdateAprParams = ctx.parameter_cursor().get()?; // Get request parameters.
-    let sender = ctx.sender(); // Get the sender's address.
+    host: &Host<State>
+) -> ContractResult<BalanceOfQueryResponse<TokenAmountU64>> {
+    contract_staked_balance_of(ctx, host)
+}
 
-    let update_timestamp = get_current_timestamp(ctx); // Get the current timestamp.
-    ensure!(sender.matches_account(&ctx.owner()), Error::UnAuthorized); // Ensure only the contract owner can update the APR
-    let state = host.state_mut(); // Get the contract state.
+/// Function to check whether addresses are operators of owners for the
+/// receipt token.
+#[receive(
+    contract = "concordium_staking",
+    name = "operatorOf",
+    parameter = "OperatorOfQueryParams",
+    return_value = "OperatorOfQueryResponse",
+    error = "Error"
+)]
+fn contract_operator_of(
+    ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<OperatorOfQueryResponse> {
+    let params: OperatorOfQueryParams = ctx.parameter_cursor().get()?;
+    ensure_batch_size(params.queries.len())?;
 
-    state.apr = params.new_apr; // Update the APR.
-    _logger.log(
-        &Event::AprUpdated(UpdateAprEvent {
-            new_apr: params.new_apr,
-            update_timestamp,
+    let state = host.state();
+    let response: Vec<bool> = params.queries
+        .iter()
+        .map(|query| match query.owner {
+            Address::Account(owner) => state.is_operator(&query.address, &owner),
+            Address::Contract(_) => false,
         })
-    )?; // Log APR update event.
-
-    Ok(()) // Return success
+        .collect();
+    Ok(OperatorOfQueryResponse::from(response))
 }
 
-/// Upgrade this smart contract instance to a new module and call optionally a
-/// migration function after the upgrade.
-///
-/// It rejects if:
-/// - Sender is not the admin of the contract instance.
-/// - It fails to parse the parameter.
-/// - If the ugrade fails.
-/// - If the migration invoke fails.
-///
-/// This function is marked as `low_level`. This is **necessary** since the
-/// high-level mutable functions store the state of the contract at the end of
-/// execution. This conflicts with migration since the shape of the state
-/// **might** be changed by the migration function. If the state is then written
-/// by this function it would overwrite the state stored by the migration
-/// function.
+/// Function to get the metadata URL of the receipt token.
+#[receive(
+    contract = "concordium_staking",
+    name = "tokenMetadata",
+    parameter = "TokenMetadataQueryParams<ContractTokenId>",
+    return_value = "TokenMetadataQueryResponse",
+    error = "Error"
+)]
+fn contract_token_metadata(
+    ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<TokenMetadataQueryResponse> {
+    let params: TokenMetadataQueryParams<ContractTokenId> = ctx.parameter_cursor().get()?;
+    ensure_batch_size(params.queries.len())?;
+
+    let url = &host.state().receipt_metadata_url;
+    Ok(TokenMetadataQueryResponse::from(vec![url.clone(); params.queries.len()]))
+}
+
+/// Function to retrieve the stakes of several users, in the order given.
 #[receive(
     contract = "concordium_staking",
-    name = "upgrade",
-    parameter = "UpgradeParams",
-    error = "Error",
-    low_level
+    name = "batchGetStakeInfo",
+    parameter = "BatchGetStakeInfoParams",
+    return_value = "Vec<StakeInfo>",
+    error = "Error"
 )]
-fn contract_upgrade(
+fn contract_batch_get_stake_info(
     ctx: &ReceiveContext,
-    host: &mut LowLevelHost
-) -> ContractResult<()> {
-    let state: State = host.state().read_root()?; // Read the top-level contract state.
-    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin); // Check that only the admin is authorized to upgrade the smart contract.
-    let params: UpgradeParams = ctx.parameter_cursor().get()?; // Parse the parameter.
-
-    host.upgrade(params.module)?; // Trigger the upgrade.
-    if let Some((func, parameters)) = params.migrate {
-        host.invoke_contract_raw(
-            &ctx.self_address(),
-            parameters.as_parameter(),
-            func.as_entrypoint_name(),
-            Amount::zero()
-        )?;
-    } // Call the migration function if provided.
+    host: &Host<State>
+) -> ContractResult<Vec<StakeInfo>> {
+    let params: BatchGetStakeInfoParams = ctx.parameter_cursor().get()?;
+    ensure_batch_size(params.accounts.len())?;
 
-    Ok(()) // Return success
+    let state = host.state();
+    let current_time = get_current_timestamp(ctx);
+    Ok(
+        params.accounts
+            .iter()
+            .map(|user| state.get_stake_info(user, current_time))
+            .collect()
+    )
 }
 
-/// Get current nonce of a user
+/// Function to list stakers page by page.
+/// Stakers are ordered by account address, so the order is stable across
+/// calls.
 #[receive(
     contract = "concordium_staking",
-    name = "getUserNonce",
-    parameter = "AccountAddress",
-    error = "Error",
-    return_value = "u64"
+    name = "getStakers",
+    parameter = "PageParams",
+    return_value = "Vec<(AccountAddress, StakeInfo)>",
+    error = "Error"
 )]
-fn contract_get_user_nonce(
+fn contract_get_stakers(
     ctx: &ReceiveContext,
     host: &Host<State>
-) -> ContractResult<u64> {
-    let user: AccountAddress = ctx.parameter_cursor().get()?;
-    let state = host.state();
-    Ok(state.get_user_nonce(&user))
+) -> ContractResult<Vec<(AccountAddress, StakeInfo)>> {
+    let params: PageParams = ctx.parameter_cursor().get()?;
+    let take = params.take.min(MAX_PAGE_SIZE);
+
+    let stakers = host
+        .state()
+        .stakes.iter()
+        .skip(params.skip as usize)
+        .take(take as usize)
+        .map(|(account, stake)| (*account, stake.clone()))
+        .collect();
+
+    Ok(stakers)
 }
 
-/// Helper function that can be invoked at the front-end to serialize the
-/// `PermitMessage` before signing it in the wallet.
+/// Function to get the `n` largest stakers by active stake, largest first.
+/// Ties are ordered by account address. `n` is capped at `MAX_TOP_STAKERS`.
 #[receive(
     contract = "concordium_staking",
-    name = "serializationHelper",
-    parameter = "PermitMessage"
+    name = "topStakers",
+    parameter = "u16",
+    return_value = "Vec<(AccountAddress, TokenAmountU64)>",
+    error = "Error"
 )]
-fn contract_serialization_helper(
-    _ctx: &ReceiveContext,
-    _host: &Host<State>
-) -> ContractResult<()> {
-    Ok(())
+fn contract_top_stakers(
+    ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<Vec<(AccountAddress, TokenAmountU64)>> {
+    let n: u16 = ctx.parameter_cursor().get()?;
+    let n = n.min(MAX_TOP_STAKERS) as usize;
+
+    // Keep the largest stakes seen so far, sorted descending. Stakes are
+    // visited by account address, so equal amounts stay in that order.
+    let mut top: Vec<(AccountAddress, TokenAmountU64)> = Vec::with_capacity(n + 1);
+    for (account, stake) in host.state().stakes.iter() {
+        if stake.amount == 0 {
+            continue;
+        }
+        let index = top.partition_point(|(_, amount)| amount.0 >= stake.amount);
+        if index < n {
+            top.insert(index, (*account, TokenAmountU64(stake.amount)));
+            top.truncate(n);
+        }
+    }
+
+    Ok(top)
 }
 
-/// Calculates the message hash
-/// The contract can only be called by any account
-/// Returns message hash
-///
-/// It rejects if:
-/// - It fails to parse the parameter
+/// Function to list the stakers with matured unbonding entries page by page,
+/// with the total matured amount of each before any slashing. Pages are
+/// taken over these stakers only, ordered by account address.
 #[receive(
     contract = "concordium_staking",
-    name = "viewMessageHash",
-    parameter = "PermitParam",
-    return_value = "[u8;32]",
-    crypto_primitives
+    name = "maturedUnbonding",
+    parameter = "PageParams",
+    return_value = "Vec<(AccountAddress, TokenAmountU64)>",
+    error = "Error"
 )]
-fn contract_view_message_hash<S: HasStateApi>(
+fn contract_matured_unbonding(
     ctx: &ReceiveContext,
-    _host: &impl HasHost<State<S>, StateApiType = S>,
-    crypto_primitives: &impl HasCryptoPrimitives
-) -> ContractResult<[u8; 32]> {
-    // Parse the parameter.
-    let mut cursor = ctx.parameter_cursor();
-    // The input parameter is `PermitParam` but we only read the initial part of it
-    // with `PermitParamPartial`. I.e. we read the `signature` and the
-    // `signer`, but not the `message` here.
-    let param: PermitParamPartial = cursor.get()?;
+    host: &Host<State>
+) -> ContractResult<Vec<(AccountAddress, TokenAmountU64)>> {
+    let params: PageParams = ctx.parameter_cursor().get()?;
+    let take = params.take.min(MAX_PAGE_SIZE);
+    let current_time = get_current_timestamp(ctx);
 
-    // The input parameter is `PermitParam` but we have only read the initial part
-    // of it with `PermitParamPartial` so far. We read in the `message` now.
-    // `(cursor.size() - cursor.cursor_position()` is the length of the message in
-    // bytes.
-    let mut message_bytes =
-        vec![0; (cursor.size() - cursor.cursor_position()) as usize];
+    let matured = host
+        .state()
+        .stakes.iter()
+        .filter_map(|(account, stake)| {
+            let amount = stake.unbonding
+                .iter()
+                .filter(|unbonding| unbonding.unlock_time <= current_time)
+                .fold(TokenAmountU64(0), |acc, unbonding| acc + unbonding.amount);
+            (amount.0 > 0).then_some((*account, amount))
+        })
+        .skip(params.skip as usize)
+        .take(take as usize)
+        .collect();
 
-    cursor.read_exact(&mut message_bytes)?;
+    Ok(matured)
+}
 
-    // The message signed in the Concordium browser wallet is prepended with the
-    // `account` address and 8 zero bytes. Accounts in the Concordium browser wallet
-    // can either sign a regular transaction (in that case the prepend is
-    // `account` address and the nonce of the account which is by design >= 1)
-    // or sign a message (in that case the prepend is `account` address and 8 zero
-    // bytes). Hence, the 8 zero bytes ensure that the user does not accidentally
-    // sign a transaction. The account nonce is of type u64 (8 bytes).
-    let mut msg_prepend = vec![0; 32 + 8];
+/// Function to get the pending unbonding entries of a user.
+/// Returns no entries for unknown accounts.
+#[receive(
+    contract = "concordium_staking",
+    name = "getUnbonding",
+    parameter = "AccountAddress",
+    return_value = "UnbondingResult",
+    error = "Error"
+)]
+fn contract_get_unbonding(
+    ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<UnbondingResult> {
+    let user: AccountAddress = ctx.parameter_cursor().get()?;
+    let state = host.state();
 
-    // Prepend the `account` address of the signer.
-    msg_prepend[0..32].copy_from_slice(param.signer.as_ref());
+    Ok(UnbondingResult {
+        unbonding: state.stakes.get(&user).map_or_else(Vec::new, |s| s.unbonding.clone()),
+        unbonding_period: state.unbonding_period,
+    })
+}
 
-    // Prepend 8 zero bytes.
-    msg_prepend[32..40].copy_from_slice(&[0u8; 8]);
+/// Function to get earned rewards: the pending rewards plus those accrued
+/// since the last checkpoint, as in `getStakeInfo`.
+#[receive(
+    contract = "concordium_staking",
+    name = "getEarnedRewards",
+    parameter = "AccountAddress",
+    return_value = "u64",
+    error = "Error"
+)]
+fn get_earned_rewards(
+    ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<u64> {
+    let user: AccountAddress = ctx.parameter_cursor().get()?;
+    let unix_timestamp = get_current_timestamp(ctx);
+    let state = host.state();
 
-    // Calculate the message hash.
-    let message_hash = crypto_primitives.hash_sha2_256(
-        &[&msg_prepend[0..40], &message_bytes].concat()
-    ).0;
+    // Return 0 if no stake exists or if stake is slashed
+    let earned_rewards = match state.stakes.get(&user) {
+        Some(stake_info) if !stake_info.slashed => state.get_accrued_rewards(&user, unix_timestamp),
+        _ => 0,
+    };
 
-    Ok(message_hash)
+    Ok(earned_rewards)
 }
 
-/// Get the entrypoints supported by the `permit` function given a
-/// list of entrypoints.
-///
-/// It rejects if:
-/// - It fails to parse the parameter.
+/// Function to simulate `unstake` of `amount` by `user` at the current block
+/// time without changing the state. Fails whenever `unstake` would.
 #[receive(
     contract = "concordium_staking",
-    name = "supportsPermit",
-    parameter = "SupportsPermitQueryParams",
-    return_value = "SupportsQueryResponse",
+    name = "simulateUnstake",
+    parameter = "SimulateUnstakeParams",
+    return_value = "SimulateUnstakeResult",
     error = "Error"
 )]
-fn contract_supports_permit<S: HasStateApi>(
+fn contract_simulate_unstake(
     ctx: &ReceiveContext,
-    _host: &impl HasHost<State<S>, StateApiType = S>
-) -> ContractResult<SupportsQueryResponse> {
-    // Parse the parameter.
-    let params: SupportsPermitQueryParams = ctx.parameter_cursor().get()?;
+    host: &Host<State>
+) -> ContractResult<SimulateUnstakeResult> {
+    let params: SimulateUnstakeParams = ctx.parameter_cursor().get()?;
+    let state = host.state();
+    ensure!(!state.paused.unstake_paused, Error::ContractPaused);
+    state.ensure_unlocked()?;
+    state.ensure_not_frozen(&params.user)?;
 
-    // Build the response.
-    let mut response = Vec::with_capacity(params.queries.len());
-    for entrypoint in params.queries {
-        if
-            SUPPORTS_PERMIT_ENTRYPOINTS.contains(
-                &entrypoint.as_entrypoint_name()
-            )
-        {
-            response.push(SupportResult::Support);
-        } else {
-            response.push(SupportResult::NoSupport);
-        }
-    }
-    let result = SupportsQueryResponse::from(response);
-    Ok(result)
+    let current_time = get_current_timestamp(ctx);
+    let unlock_time = state.get_unlock_time(current_time)?;
+
+    let sender_stake = state.stakes.get(&params.user).ok_or(Error::NoStakeFound)?;
+    ensure!(!sender_stake.slashed, Error::AlreadySlashed);
+    ensure!(sender_stake.amount >= params.amount.0, Error::InvalidUnstakeAmount);
+    ensure!(sender_stake.unbonding.len() < MAX_UNBONDING_ENTRIES, Error::TooManyUnbonding);
+    ensure!(
+        sender_stake.unlocked_amount(current_time) >= params.amount.0,
+        Error::StakeLocked
+    );
+
+    let rewards_crystallized = sender_stake.new_rewards(
+        current_time,
+        state.reward_per_token(current_time),
+        state.max_accrual_seconds,
+        state.reward_rounding
+    );
+
+    Ok(SimulateUnstakeResult {
+        unbonding: UnbondingInfo {
+            amount: params.amount,
+            unlock_time,
+        },
+        rewards_crystallized: TokenAmountU64(rewards_crystallized),
+        remaining_stake: sender_stake.amount - params.amount.0,
+    })
 }
 
-/// View function to get contract state
+/// Function to get the latest slashes, oldest first.
 #[receive(
     contract = "concordium_staking",
-    name = "view",
-    return_value = "ViewResult"
+    name = "getSlashHistory",
+    return_value = "Vec<SlashRecord>",
+    error = "Error"
 )]
-fn contract_view(
+fn contract_get_slash_history(
     _ctx: &ReceiveContext,
     host: &Host<State>
-) -> ContractResult<ViewResult> {
-    let state = host.state();
-    
-    Ok(ViewResult {
-        paused: state.paused,
-        admin: state.admin,
-        total_staked: state.total_staked.0,
-        apr: state.apr,
-        token_address: state.token_address,
-        total_participants: state.total_participants,
-        total_rewards_paid: state.total_rewards_paid.0,
-        rewards_pool: state.rewards_pool.0,
-    })
+) -> ContractResult<Vec<SlashRecord>> {
+    Ok(host.state().slash_history.clone())
 }
 
-/// Function to retrieve specific user stake
+/// Function to get a user's share of the total stake in basis points.
+/// Returns 0 if nothing is staked.
 #[receive(
     contract = "concordium_staking",
-    name = "getStakeInfo",
+    name = "getUserShare",
     parameter = "AccountAddress",
-    return_value = "StakeInfo",
+    return_value = "u64",
     error = "Error"
 )]
-fn contract_get_stake_info(
+fn contract_get_user_share(
     ctx: &ReceiveContext,
     host: &Host<State>
-) -> ContractResult<StakeInfo> {
+) -> ContractResult<u64> {
     let user: AccountAddress = ctx.parameter_cursor().get()?;
     let state = host.state();
-    
-    // Return default StakeInfo if no stake exists
-    let stake_info = state.stakes.get(&user).map(|s| {
-        let current_time = get_current_timestamp(ctx);
-        
-        // Calculate new rewards since last update
-        let additional_rewards = calculate_reward(
-            s.amount,
-            s.timestamp,
-            current_time,
-            state.apr
-        );
 
-        // Add new rewards to existing pending rewards
-        let total_pending_rewards = s.pending_rewards.saturating_add(additional_rewards);
+    if state.total_staked.0 == 0 {
+        return Ok(0);
+    }
 
-        StakeInfo {
-            amount: s.amount,
-            timestamp: s.timestamp,
-            unbonding: s.unbonding.clone(),
-            slashed: s.slashed,
-            pending_rewards: total_pending_rewards,  // Use total rewards including new calculations
-        }
-    }).unwrap_or(StakeInfo {
-        amount: 0,
-        timestamp: get_current_timestamp(ctx),
-        unbonding: Vec::new(),
-        slashed: false,
-        pending_rewards: 0,
-    });
-    
-    Ok(stake_info)
+    let amount = state.stakes.get(&user).map_or(0, |s| s.amount);
+    let share = (amount as u128)
+        .saturating_mul(BASIS_POINTS_DENOMINATOR as u128)
+        .saturating_div(state.total_staked.0 as u128);
+
+    Ok(share.try_into().unwrap_or(BASIS_POINTS_DENOMINATOR))
 }
 
-/// Function to get earned rewards.
+/// Function to get the accrued rewards of a user valued in the display
+/// currency of the price oracle.
+///
+/// Falls back to the raw EUROe amount with `price_available` set to false if
+/// no oracle is configured or the oracle invocation fails.
 #[receive(
     contract = "concordium_staking",
-    name = "getEarnedRewards",
+    name = "getRewardsValue",
     parameter = "AccountAddress",
-    return_value = "u64",
+    return_value = "RewardsValueResult",
     error = "Error"
 )]
-fn get_earned_rewards(
+fn contract_get_rewards_value(
     ctx: &ReceiveContext,
     host: &Host<State>
-) -> ContractResult<u64> {
+) -> ContractResult<RewardsValueResult> {
     let user: AccountAddress = ctx.parameter_cursor().get()?;
-    let unix_timestamp = get_current_timestamp(ctx);
     let state = host.state();
-
-    // Return 0 if no stake exists or if stake is slashed
-    let earned_rewards = state.stakes.get(&user).map_or(0, |stake_info| {
-        if stake_info.slashed {
-            0
-        } else {
-            calculate_reward(
-                stake_info.amount,
-                stake_info.timestamp,
-                unix_timestamp,
-                state.apr
-            )
-        }
+    let rewards = state.get_accrued_rewards(&user, get_current_timestamp(ctx));
+
+    // Query the oracle for the EUROe price, ignoring any failure.
+    let price: Option<u64> = state.price_oracle.and_then(|oracle| {
+        host.invoke_contract_read_only(
+            &oracle,
+            &state.token_address,
+            PRICE_ORACLE_ENTRYPOINT,
+            Amount::zero()
+        )
+            .ok()
+            .flatten()
+            .and_then(|mut rv| rv.get().ok())
     });
 
-    Ok(earned_rewards)
+    let result = match price {
+        Some(price) => RewardsValueResult {
+            rewards: TokenAmountU64(rewards),
+            value: ((rewards as u128).saturating_mul(price as u128) /
+                PRICE_DENOMINATOR)
+                .try_into()
+                .unwrap_or(u64::MAX),
+            price_available: true,
+        },
+        None => RewardsValueResult {
+            rewards: TokenAmountU64(rewards),
+            value: 0,
+            price_available: false,
+        },
+    };
+
+    Ok(result)
+}
+
+/// Function to project the total rewards of a user at a future timestamp at
+/// the current APR. Uses the same accrual as claiming, so the forecast
+/// matches a claim at `at_timestamp` if nothing changes until then.
+#[receive(
+    contract = "concordium_staking",
+    name = "rewardsForecast",
+    parameter = "RewardsForecastParams",
+    return_value = "u64",
+    error = "Error"
+)]
+fn contract_rewards_forecast(
+    ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<u64> {
+    let params: RewardsForecastParams = ctx.parameter_cursor().get()?;
+    ensure!(
+        params.at_timestamp >= get_current_timestamp(ctx),
+        Error::TimestampInPast
+    );
+
+    Ok(host.state().get_accrued_rewards(&params.user, params.at_timestamp))
+}
+
+/// Function to get the APR effective at a timestamp in seconds.
+#[receive(
+    contract = "concordium_staking",
+    name = "getAprAt",
+    parameter = "u64",
+    return_value = "u64",
+    error = "Error"
+)]
+fn contract_get_apr_at(
+    ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<u64> {
+    let timestamp: u64 = ctx.parameter_cursor().get()?;
+    host.state().get_apr_at(timestamp).ok_or(Error::AprHistoryUnavailable)
 }
 
 //  ## HELPER FUNCTIONS ##
 
+/// Moves `amount` of the active stake of `sender_address` into the unbonding
+/// queue. The tokens are paid out by `completeUnstake` after the unbonding
+/// period. No rewards are paid here: they stay pending until `claimRewards`
+/// or `restake`, which check and debit the rewards pool. Returns the created
+/// unbonding entry.
 fn unstake_helper(
     ctx: &ReceiveContext,
     host: &mut Host<State>,
     _logger: &mut Logger,
-    sender_address: AccountAddress,
-    amount: TokenAmountU64
-) -> ContractResult<()> {
-    let unix_timestamp = get_current_timestamp(ctx);
-
-    let earned_rewards = {
-        let state = host.state_mut();  // Get mutable state
-        ensure!(!state.paused, Error::ContractPaused);
-    
-        let sender_stake = state.stakes.get(&sender_address).ok_or(Error::NoStakeFound)?;
-        let staked_amount = sender_stake.amount;
-        ensure!(staked_amount >= amount.0, Error::InvalidUnstakeAmount);
-    
-        let earned_rewards = TokenAmountU64(
-            calculate_reward(
-                amount.0,
-                sender_stake.timestamp,
-                unix_timestamp,
-                state.apr
-            ).into()
-        );
-    
-        // Remove entry if fully unstaking
-        if amount.eq(&TokenAmountU64(staked_amount)) {
-            state.stakes.remove(&sender_address);
-            state.total_participants -= 1;
-        } else {
-            // Otherwise just update the amount
-            let _ = state.stakes.insert(sender_address, StakeInfo {
-                amount: staked_amount - amount.0,
-                timestamp: sender_stake.timestamp,
-                unbonding: sender_stake.unbonding.clone(),
-                slashed: sender_stake.slashed,
-                pending_rewards: sender_stake.pending_rewards,
-            });
-        }
-    
-        state.total_staked -= amount;
-        earned_rewards
-    }; // state borrow ends here
+    sender_address: AccountAddress,
+    amount: TokenAmountU64
+) -> ContractResult<UnbondingInfo> {
+    let state = host.state_mut();
+    ensure!(!state.paused.unstake_paused, Error::ContractPaused);
+    state.ensure_unlocked()?;
+    state.ensure_not_frozen(&sender_address)?;
 
-    transfer_euroe_token(
-        host,
-        Address::Contract(ctx.self_address()),
-        Receiver::Account(sender_address),
-        amount + earned_rewards,
-        true
-    )?;
+    let current_time = get_current_timestamp(ctx);
+    let unlock_time = state.get_unlock_time(current_time)?;
+    let reward_per_token = state.update_reward_per_token(current_time);
 
-    _logger.log(
-        &Event::Unstaked(UnstakeEvent {
-            user: sender_address,
-            unstaked_amount: amount,
-            unix_timestamp,
-            rewards_earned: earned_rewards.into(),
-        })
-    )?;
+    let mut sender_stake = state.stakes
+        .entry(sender_address)
+        .occupied_or(Error::NoStakeFound)?;
 
-    Ok(())
+    ensure!(!sender_stake.slashed, Error::AlreadySlashed);
+    ensure!(amount.0 > 0, Error::InvalidUnstakeAmount);
+    ensure!(sender_stake.amount >= amount.0, Error::InvalidUnstakeAmount);
+    ensure!(sender_stake.unbonding.len() < MAX_UNBONDING_ENTRIES, Error::TooManyUnbonding);
+    ensure!(
+        sender_stake.unlocked_amount(current_time) >= amount.0,
+        Error::StakeLocked
+    );
+
+    // Keep the rewards earned on the full principal up to now
+    let was_participant = sender_stake.is_participant();
+    let pending_before = sender_stake.pending_rewards;
+    state.reward_liability.remove(&sender_stake, reward_per_token, current_time);
+    sender_stake.accrue_rewards(
+        current_time,
+        reward_per_token,
+        state.max_accrual_seconds,
+        state.reward_rounding
+    );
+    let rewards_earned = TokenAmountU64(
+        sender_stake.pending_rewards.saturating_sub(pending_before)
+    );
+
+    // Add to unbonding list
+    let unbonding = UnbondingInfo {
+        amount,
+        unlock_time,
+    };
+    sender_stake.unbonding.push(unbonding.clone());
+
+    // Update stake amount. The staker remains a participant while unbonding.
+    sender_stake.remove_principal(amount.0, current_time);
+    state.reward_liability.add(&sender_stake, reward_per_token, current_time);
+    let is_participant = sender_stake.is_participant();
+    drop(sender_stake);
+    state.update_participants(was_participant, is_participant);
+    state.total_staked -= amount;
+    state.total_unbonding += amount;
+    state.remove_empty_stake(&sender_address);
+    state.log_receipt_burn(_logger, sender_address, amount)?;
+
+    state.log_event(_logger, Event::Unstaked(UnstakeEvent {
+        user: sender_address,
+        unstaked_amount: amount,
+        unix_timestamp: current_time,
+        rewards_earned, // Rewards claimed separately
+        total_staked_after: state.total_staked,
+    }))?;
+
+    Ok(unbonding)
 }
 
+/// Claims `amount` of the rewards of `sender_address`, or all of them if
+/// `amount` is `None`, and returns the amount transferred after the protocol
+/// fee. The rewards go to `recipient`, or to `sender_address` if `None`.
+/// Unclaimed rewards stay in `pending_rewards`. If the rewards pool cannot
+/// cover the claim and `auto_pause_on_depletion` is set, reward payouts are
+/// paused and nothing is transferred.
 fn claim_rewards_helper(
     ctx: &ReceiveContext,
     host: &mut Host<State>,
     logger: &mut Logger,
-    sender_address: AccountAddress
-) -> ContractResult<()> {
+    sender_address: AccountAddress,
+    amount: Option<TokenAmountU64>,
+    recipient: Option<AccountAddress>
+) -> ContractResult<TokenAmountU64> {
+    let recipient = recipient.unwrap_or(sender_address);
+
     // Calculate rewards and update state
-    let earned_rewards = {
+    let (earned_rewards, fee, fee_recipient) = {
         let state = host.state_mut();
-        ensure!(!state.paused, Error::ContractPaused);
+        ensure!(!state.paused.claim_paused, Error::ContractPaused);
+        ensure!(!state.paused.rewards_paused, Error::ContractPaused);
+        state.ensure_unlocked()?;
+        state.ensure_not_frozen(&sender_address)?;
+        state.ensure_not_frozen(&recipient)?;
+
+        let current_time = get_current_timestamp(ctx);
+        let reward_per_token = state.update_reward_per_token(current_time);
+        state.release_vested_rewards(current_time);
 
         let mut sender_stake = state.stakes
             .entry(sender_address)
             .occupied_or(Error::NoStakeFound)?;
 
         ensure!(!sender_stake.slashed, Error::AlreadySlashed);
-
-        // Calculate new rewards
-        let current_time = get_current_timestamp(ctx);
-        let new_rewards = calculate_reward(
-            sender_stake.amount,
-            sender_stake.timestamp,
-            current_time,
-            state.apr
+        ensure!(
+            current_time.saturating_sub(sender_stake.last_claim_time) >=
+                state.claim_cooldown_seconds,
+            Error::ClaimCooldownActive
         );
 
         // Get total rewards (pending + new)
-        let total_rewards = TokenAmountU64(sender_stake.pending_rewards.saturating_add(new_rewards));
-        ensure!(total_rewards.0 > 0, Error::NoRewardsAvailable);
-        ensure!(state.rewards_pool.0 >= total_rewards.0, Error::InsufficientRewardsPool);
+        state.reward_liability.remove(&sender_stake, reward_per_token, current_time);
+        sender_stake.accrue_rewards(
+            current_time,
+            reward_per_token,
+            state.max_accrual_seconds,
+            state.reward_rounding
+        );
+        let total_rewards = TokenAmountU64(sender_stake.pending_rewards);
+        let mut claim_amount = amount.unwrap_or(total_rewards);
+        ensure!(claim_amount.0 > 0, Error::NoRewardsAvailable);
+        ensure!(claim_amount.0 <= total_rewards.0, Error::ClaimExceedsRewards);
+
+        // Cap the claim, the remainder can be claimed later
+        if let Some(max_reward_per_claim) = state.max_reward_per_claim {
+            claim_amount = claim_amount.min(max_reward_per_claim);
+        }
+        if state.rewards_pool.0 < claim_amount.0 {
+            ensure!(state.auto_pause_on_depletion, Error::InsufficientRewardsPool);
+            state.reward_liability.add(&sender_stake, reward_per_token, current_time);
+            drop(sender_stake);
+            state.pause_on_depletion(logger, sender_address, claim_amount, current_time)?;
+            return Ok(TokenAmountU64(0));
+        }
 
-        // Reset pending rewards and update timestamp
-        sender_stake.pending_rewards = 0;
-        sender_stake.timestamp = current_time;
+        // Keep the unclaimed rewards pending
+        sender_stake.take_rewards(claim_amount.0);
+        sender_stake.lifetime_rewards_claimed =
+            sender_stake.lifetime_rewards_claimed.saturating_add(claim_amount.0);
+        sender_stake.last_claim_time = current_time;
+        state.reward_liability.add(&sender_stake, reward_per_token, current_time);
+        drop(sender_stake);
         
         // Update contract state
-        state.rewards_pool.0 = state.rewards_pool.0.saturating_sub(total_rewards.0);
-        state.total_rewards_paid.0 = state.total_rewards_paid.0.saturating_add(total_rewards.0);
+        state.rewards_pool.0 = state.rewards_pool.0.saturating_sub(claim_amount.0);
+        state.total_rewards_paid.0 = state.total_rewards_paid.0.saturating_add(claim_amount.0);
+        state.remove_empty_stake(&sender_address);
+
+        // Take the protocol fee out of the claimed rewards
+        let fee = TokenAmountU64(
+            ((claim_amount.0 as u128) * (state.reward_fee_bps as u128) /
+                (BASIS_POINTS_DENOMINATOR as u128))
+                .try_into()
+                .unwrap_or(claim_amount.0)
+        );
         
-        total_rewards
+        (claim_amount - fee, fee, state.fee_recipient)
     };
 
-    // Transfer rewards to user
+    // Transfer the fee to the fee recipient
+    if fee.0 > 0 {
+        transfer_euroe_token(
+            host,
+            Address::Contract(ctx.self_address()),
+            Receiver::Account(fee_recipient),
+            fee,
+            AdditionalData::empty(),
+            true
+        )?;
+
+        host.state_mut().log_event(logger, Event::FeeCollected(FeeCollectedEvent {
+            user: sender_address,
+            fee_recipient,
+            fee,
+            fee_timestamp: get_current_timestamp(ctx),
+        }))?;
+    }
+
+    // Transfer rewards to the recipient
     if earned_rewards.0 > 0 {
         transfer_euroe_token(
             host,
             Address::Contract(ctx.self_address()),
-            Receiver::Account(sender_address),
+            Receiver::Account(recipient),
             earned_rewards,
+            AdditionalData::empty(),
             true
         )?;
     }
 
-    logger.log(&Event::Claimed(ClaimEvent {
+    host.state_mut().log_event(logger, Event::Claimed(ClaimEvent {
         user: sender_address,
         rewards_claimed: earned_rewards,
         claim_timestamp: get_current_timestamp(ctx),
+        recipient,
     }))?;
 
-    Ok(())
+    Ok(earned_rewards)
+}
+
+/// Calculates the hash of the `PermitMessage` in the `PermitParam` parameter
+/// that the signer is expected to sign.
+fn compute_message_hash(
+    ctx: &ReceiveContext,
+    crypto_primitives: &impl HasCryptoPrimitives
+) -> ContractResult<MessageHashResult> {
+    // Parse the parameter.
+    let mut cursor = ctx.parameter_cursor();
+    // The input parameter is `PermitParam` but we only read the initial part of it
+    // with `PermitParamPartial`. I.e. we read the `signature` and the
+    // `signer`, but not the `message` here.
+    let param: PermitParamPartial = cursor.get()?;
+
+    // The input parameter is `PermitParam` but we have only read the initial part
+    // of it with `PermitParamPartial` so far. We read in the `message` now.
+    // `(cursor.size() - cursor.cursor_position()` is the length of the message in
+    // bytes.
+    let mut message_bytes =
+        vec![0; (cursor.size() - cursor.cursor_position()) as usize];
+
+    cursor.read_exact(&mut message_bytes)?;
+
+    Ok(hash_permit_message(&param.signer, &message_bytes, crypto_primitives))
+}
+
+/// Calculates the hash of the serialized `PermitMessage` in `message_bytes`
+/// that `signer` is expected to sign.
+fn hash_permit_message(
+    signer: &AccountAddress,
+    message_bytes: &[u8],
+    crypto_primitives: &impl HasCryptoPrimitives
+) -> MessageHashResult {
+    // The message signed in the Concordium browser wallet is prepended with the
+    // `account` address and 8 zero bytes. Accounts in the Concordium browser wallet
+    // can either sign a regular transaction (in that case the prepend is
+    // `account` address and the nonce of the account which is by design >= 1)
+    // or sign a message (in that case the prepend is `account` address and 8 zero
+    // bytes). Hence, the 8 zero bytes ensure that the user does not accidentally
+    // sign a transaction. The account nonce is of type u64 (8 bytes).
+    let mut msg_prepend = [0; 32 + 8];
+
+    // Prepend the `account` address of the signer.
+    msg_prepend[0..32].copy_from_slice(signer.as_ref());
+
+    // Prepend 8 zero bytes.
+    msg_prepend[32..40].copy_from_slice(&[0u8; 8]);
+
+    // Calculate the message hash.
+    let message_hash = crypto_primitives.hash_sha2_256(
+        &[&msg_prepend[0..40], message_bytes].concat()
+    ).0;
+
+    MessageHashResult {
+        prepend: msg_prepend,
+        message_length: message_bytes.len() as u32,
+        message_hash,
+    }
+}
+
+/// Validation function to check that a received token is EUROe
+fn is_euroe(token_id: &ReceivedTokenId) -> bool {
+    to_bytes(token_id) == to_bytes(&TOKEN_ID_EUROE)
 }
 
 /// Validation function to check only account
@@ -1254,18 +5555,66 @@ fn only_account(sender: &Address) -> ContractResult<AccountAddress> {
     }
 }
 
+/// Reads the raw bytes of the parameter.
+fn read_parameter(ctx: &ReceiveContext) -> ContractResult<Vec<u8>> {
+    let mut cursor = ctx.parameter_cursor();
+    let mut parameter = vec![0; cursor.size() as usize];
+    cursor.read_exact(&mut parameter)?;
+    Ok(parameter)
+}
+
+/// Parses a parameter of optional fields, treating an empty parameter as the
+/// default. Keeps entrypoints that gained a parameter callable without one.
+fn parse_optional_params<T: Deserial + Default>(bytes: &[u8]) -> ContractResult<T> {
+    if bytes.is_empty() {
+        return Ok(T::default());
+    }
+    Ok(from_bytes(bytes)?)
+}
+
+/// Parses the `DepositKind` of a transfer to `onReceivingCIS2`, rejecting
+/// data that is malformed or has trailing bytes.
+fn parse_deposit_kind(bytes: &[u8]) -> ContractResult<DepositKind> {
+    let mut cursor = Cursor::new(bytes);
+    let deposit_kind = DepositKind::deserial(&mut cursor).map_err(|_| Error::UnknownDeposit)?;
+    ensure!(cursor.offset == bytes.len(), Error::UnknownDeposit);
+    Ok(deposit_kind)
+}
+
+/// Validation function to check an admin account.
+/// Rejects the zero address, which is a common placeholder and cannot sign.
+/// Whether the account exists on chain can only be established once it acts,
+/// so admin transfers must be accepted by the new admin itself.
+fn ensure_valid_admin(admin: &AccountAddress) -> ContractResult<()> {
+    ensure!(*admin != AccountAddress([0u8; 32]), Error::InvalidAdmin);
+    Ok(())
+}
+
+/// Validation function to check a slashing rate does not exceed 100%
+fn ensure_valid_slashing_rate(slashing_rate: u64) -> ContractResult<()> {
+    ensure!(slashing_rate <= BASIS_POINTS_DENOMINATOR, Error::InvalidSlashingRate);
+    Ok(())
+}
+
+/// Validation function to check the length of a batch
+fn ensure_batch_size(len: usize) -> ContractResult<()> {
+    ensure!(len <= MAX_BATCH_SIZE, Error::BatchTooLarge);
+    Ok(())
+}
+
 /// Function to derive current block timestamp
 fn get_current_timestamp(ctx: &ReceiveContext) -> u64 {
     ctx.metadata().block_time().millis / 1000
 }
 
-/// Function to calculate rewards.
+/// Function to calculate rewards scaled by `REWARD_PER_TOKEN_PRECISION`,
+/// keeping fractions of a token unit for `round_rewards`.
 fn calculate_reward(
     staked_amount: u64,
     last_timestamp: u64,
     current_timestamp: u64,
     apr: u64
-) -> u64 {
+) -> u128 {
     if staked_amount == 0 {
         return 0;
     }
@@ -1275,25 +5624,114 @@ fn calculate_reward(
     // Use u128 for intermediate calculations to prevent overflow
     let staked_amount_u128 = staked_amount as u128;
     
-    // Calculate reward: (staked_amount * apr * time_staked) / (365 * 24 * 60 * 60 * 10000)
-    // The 10000 divisor is because APR is in basis points (1% = 100)
-    staked_amount_u128
+    // Calculate reward: (staked_amount * apr * time_staked) / (SECONDS_PER_YEAR * APR_DENOMINATOR)
+    // Staking for exactly one year yields staked_amount * apr / APR_DENOMINATOR
+    let denominator = SECONDS_PER_YEAR * APR_DENOMINATOR;
+    let numerator = staked_amount_u128
         .saturating_mul(apr as u128)
-        .saturating_mul(time_staked as u128)
-        .saturating_div(365 * 24 * 60 * 60 * 10000)
-        .try_into()
-        .unwrap_or(0)
+        .saturating_mul(time_staked as u128);
+    (numerator / denominator)
+        .saturating_mul(REWARD_PER_TOKEN_PRECISION)
+        .saturating_add((numerator % denominator) * REWARD_PER_TOKEN_PRECISION / denominator)
+}
+
+/// Function to calculate rewards from the reward per token accumulator,
+/// scaled by `REWARD_PER_TOKEN_PRECISION`.
+/// Reward = staked_amount * (reward_per_token - reward_per_token_paid)
+fn calculate_accumulated_reward(
+    staked_amount: u64,
+    reward_per_token_paid: u128,
+    reward_per_token: u128
+) -> u128 {
+    (staked_amount as u128)
+        .saturating_mul(reward_per_token.saturating_sub(reward_per_token_paid))
+}
+
+/// Function to round `rewards`, scaled by `REWARD_PER_TOKEN_PRECISION`, to
+/// token units as given by `rounding`. Returns the token units and the
+/// remainder to carry forward, so rounding never adds up over accruals.
+fn round_rewards(rewards: i128, rounding: RoundingMode) -> (u64, i128) {
+    let precision = REWARD_PER_TOKEN_PRECISION as i128;
+    let rounded = match rounding {
+        RoundingMode::Floor => rewards,
+        RoundingMode::Nearest => rewards.saturating_add(precision / 2),
+    };
+    let units = u64::try_from(rounded.div_euclid(precision).max(0)).unwrap_or(u64::MAX);
+    (units, rewards.saturating_sub((units as i128).saturating_mul(precision)))
+}
+
+/// Function to blend two APRs weighted by the amounts earning them, rounding
+/// down.
+fn weighted_apr(
+    amount_a: u64,
+    apr_a: u64,
+    amount_b: u64,
+    apr_b: u64
+) -> u64 {
+    let total_amount = (amount_a as u128) + (amount_b as u128);
+    if total_amount == 0 {
+        return apr_b;
+    }
+
+    // Offset the lower APR by the weighted difference, so the products fit
+    // in u128 and the result never exceeds the higher APR
+    let (low_apr, high_amount, high_apr) = if apr_a >= apr_b {
+        (apr_b, amount_a, apr_a)
+    } else {
+        (apr_a, amount_b, apr_b)
+    };
+    let offset = (high_amount as u128) * ((high_apr - low_apr) as u128) / total_amount;
+    low_apr + (offset as u64)
+}
+
+/// Function to compound an APR daily over one year, giving the APY.
+/// Both are in basis points. Saturates at `u64::MAX`.
+fn compounded_apr(apr: u64) -> u64 {
+    let days_per_year = SECONDS_PER_YEAR / (SECONDS_PER_DAY as u128);
+    let daily_factor = APY_PRECISION + APY_PRECISION * (apr as u128) / (APR_DENOMINATOR * days_per_year);
+
+    // Raise the daily factor to the number of days by squaring
+    let mut growth = Some(APY_PRECISION);
+    let mut base = Some(daily_factor);
+    let mut exponent = days_per_year;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            growth = growth
+                .zip(base)
+                .and_then(|(growth, base)| growth.checked_mul(base))
+                .map(|growth| growth / APY_PRECISION);
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            base = base
+                .and_then(|base| base.checked_mul(base))
+                .map(|base| base / APY_PRECISION);
+        }
+    }
+
+    growth
+        .map(|growth| (growth - APY_PRECISION) * APR_DENOMINATOR / APY_PRECISION)
+        .and_then(|apy| apy.try_into().ok())
+        .unwrap_or(u64::MAX)
 }
 
 /// Function to transfer EUROe stablecoin.
+/// The contract is locked during the transfer, so the token contract cannot
+/// call back into entrypoints that check the lock.
+/// Callers commit their state changes before calling it. A failed transfer
+/// returns an error, which rejects the whole update and rolls back those
+/// changes together with the lock.
 fn transfer_euroe_token(
     host: &mut Host<State>,
     from: Address,
     to: Receiver,
     amount: TokenAmountU64,
+    data: AdditionalData,
     before_transfer_check: bool
 ) -> ContractResult<()> {
-    let state = host.state();
+    let state = host.state_mut();
+    state.ensure_unlocked()?;
+    state.locked = true;
     let client = Cis2Client::new(state.token_address);
 
     if before_transfer_check {
@@ -1303,7 +5741,7 @@ fn transfer_euroe_token(
             TokenAmountU64,
             Error
         >(host, TOKEN_ID_EUROE, from)?;
-        ensure!(contract_balance.gt(&amount), Error::InsufficientFunds);
+        ensure!(contract_balance.ge(&amount), Error::InsufficientFunds);
     }
 
     client.transfer::<State, ContractTokenId, TokenAmountU64, Error>(
@@ -1313,14 +5751,17 @@ fn transfer_euroe_token(
             from,
             to,
             token_id: TOKEN_ID_EUROE,
-            data: AdditionalData::empty(),
+            data,
         }
     )?;
 
+    host.state_mut().locked = false;
+
     Ok(())
 }
 
 /// New function to fund rewards pool
+/// Anyone can fund the pool from their own account.
 #[receive(
     contract = "concordium_staking",
     name = "fundRewards",
@@ -1332,34 +5773,74 @@ fn contract_fund_rewards(
     ctx: &ReceiveContext,
     host: &mut Host<State>
 ) -> ContractResult<()> {
-    // Get admin address first
-    let admin = host.state().admin;
-    ensure!(ctx.sender().matches_account(&admin), Error::OnlyAdmin);
-    
+    let sender_address = only_account(&ctx.sender())?;
     let amount: TokenAmountU64 = ctx.parameter_cursor().get()?;
-    
-    // Transfer EUROe from admin to contract
+    fund_rewards_helper(ctx, host, sender_address, amount, DepositKind::FundRewards)?;
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(())
+}
+
+/// New function to fund the rewards pool with vesting
+/// The amount becomes claimable linearly until `vest_until`. Anyone can fund
+/// from their own account.
+#[receive(
+    contract = "concordium_staking",
+    name = "fundRewardsVested",
+    parameter = "FundRewardsVestedParams",
+    error = "Error",
+    mutable
+)]
+fn contract_fund_rewards_vested(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    let sender_address = only_account(&ctx.sender())?;
+    let params: FundRewardsVestedParams = ctx.parameter_cursor().get()?;
+    ensure!(params.vest_until > get_current_timestamp(ctx), Error::TimestampInPast);
+    fund_rewards_helper(
+        ctx,
+        host,
+        sender_address,
+        params.amount,
+        DepositKind::FundRewardsVested(params.vest_until)
+    )?;
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(())
+}
+
+/// Transfers `amount` from `sender_address` into the rewards pool, directly
+/// or vested as given by `deposit_kind`.
+fn fund_rewards_helper(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    sender_address: AccountAddress,
+    amount: TokenAmountU64,
+    deposit_kind: DepositKind
+) -> ContractResult<()> {
+    // Transfer EUROe from the funder to contract
     transfer_euroe_token(
         host,
-        Address::Account(admin),
+        Address::Account(sender_address),
         Receiver::Contract(
             ctx.self_address(),
             OwnedEntrypointName::new_unchecked("onReceivingCIS2".to_string())
         ),
         amount,
+        AdditionalData::from(to_bytes(&deposit_kind)),
         true
-    )?;
-    
-    // Update rewards pool after transfer
-    host.state_mut().rewards_pool += amount;
+    )?; // `onReceivingCIS2` adds the amount to the rewards pool once it arrived
     
     Ok(())
 }
 
 /// New function to complete unstaking after unbonding period
+/// Withdraws all matured entries if the parameter is empty or the index is
+/// `None`. Entries that were unbonding during a slash were already reduced
+/// by `slashing_rate` then, so they are paid out in full.
 #[receive(
     contract = "concordium_staking",
     name = "completeUnstake",
+    parameter = "CompleteUnstakeParams",
     error = "Error",
     mutable,
     enable_logger
@@ -1369,23 +5850,43 @@ fn contract_complete_unstake(
     host: &mut Host<State>,
     _logger: &mut Logger
 ) -> ContractResult<()> {
+    let params: CompleteUnstakeParams = parse_optional_params(&read_parameter(ctx)?)?;
     let sender_address = only_account(&ctx.sender())?;
+    complete_unstake_helper(ctx, host, _logger, sender_address, params.index)?;
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(())
+}
+
+/// Transfers the matured unbonding entry at `index` of `sender_address` to
+/// them, or all matured entries if `index` is `None`.
+fn complete_unstake_helper(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger,
+    sender_address: AccountAddress,
+    index: Option<u32>
+) -> ContractResult<()> {
     let current_time = get_current_timestamp(ctx);
     
     let state = host.state_mut();
+    state.ensure_unlocked()?;
+    state.ensure_not_frozen(&sender_address)?;
     let mut stake_info = state.stakes
         .entry(sender_address)
         .occupied_or(Error::NoStakeFound)?;
 
-    ensure!(!stake_info.slashed, Error::AlreadySlashed);
-
     let mut total_amount = TokenAmountU64(0);
     let mut remaining_unbonding = Vec::new();
 
     // Process unbonding entries
-    for unbonding in stake_info.unbonding.iter() {
-        if current_time >= unbonding.unlock_time {
-            total_amount += unbonding.amount;
+    for (i, unbonding) in stake_info.unbonding.iter().enumerate() {
+        let selected = index.is_none_or(|index| index as usize == i);
+        if selected && current_time >= unbonding.unlock_time {
+            total_amount = TokenAmountU64(
+                total_amount.0
+                    .checked_add(unbonding.amount.0)
+                    .ok_or(Error::ArithmeticOverflow)?
+            );
         } else {
             remaining_unbonding.push(unbonding.clone());
         }
@@ -1395,51 +5896,364 @@ fn contract_complete_unstake(
 
     // Update unbonding list
     stake_info.unbonding = remaining_unbonding;
+    state.total_unbonding -= total_amount;
 
-    // If slashed, apply slashing
-    if stake_info.slashed {
-        let slash_amount = (total_amount.0 * state.slashing_rate) / 10000;
-        total_amount = TokenAmountU64(total_amount.0 - slash_amount);
-    }
+    // Stop counting the staker once nothing is staked or unbonding
+    let is_participant = stake_info.is_participant();
 
     // Drop the state borrow before calling transfer_euroe_token
     drop(stake_info);  // Drop any state borrows first
+    state.update_participants(true, is_participant);
+    state.remove_empty_stake(&sender_address);
 
     transfer_euroe_token(
         host,
         Address::Contract(ctx.self_address()),
         Receiver::Account(sender_address),
         total_amount,
+        AdditionalData::empty(),
         true
     )?;
 
+    host.state_mut().log_event(logger, Event::UnstakeCompleted(UnstakeCompletedEvent {
+        user: sender_address,
+        amount: total_amount,
+        complete_timestamp: current_time,
+    }))?;
+
+    Ok(())
+}
+
+/// Function to pay out all matured unbonding entries of a user on their
+/// behalf, exactly as `completeUnstake` would.
+/// Access by admin only.
+#[receive(
+    contract = "concordium_staking",
+    name = "sweepExpiredUnbonding",
+    parameter = "AccountAddress",
+    error = "Error",
+    mutable,
+    enable_logger
+)]
+fn contract_sweep_expired_unbonding(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let user: AccountAddress = ctx.parameter_cursor().get()?;
+    ensure!(ctx.sender().matches_account(&host.state().admin), Error::OnlyAdmin);
+
+    complete_unstake_helper(ctx, host, logger, user, None)?;
+    host.state_mut().touch(get_current_timestamp(ctx));
     Ok(())
 }
 
 /// New function to slash a staker
+/// Removes `amount` of the active stake and `slashing_rate` of the funds
+/// still unbonding, and moves both to the rewards pool. Pending rewards are
+/// forfeited. Funds unstaked after the slash are not reduced. The staker is
+/// marked as slashed once the whole active stake is removed, and then cannot
+/// stake or unstake again, but can still complete their unbonding entries.
+/// The admin and exempt accounts cannot be slashed.
+/// The slash is kept in the slash history with its `reason_code`.
 #[receive(
     contract = "concordium_staking",
     name = "slash",
-    parameter = "AccountAddress",
+    parameter = "SlashParams",
     error = "Error",
-    mutable
+    mutable,
+    enable_logger
 )]
 fn contract_slash(
     ctx: &ReceiveContext,
-    host: &mut Host<State>
+    host: &mut Host<State>,
+    logger: &mut Logger
+) -> ContractResult<()> {
+    let params: SlashParams = ctx.parameter_cursor().get()?;
+    let sender_address = only_account(&ctx.sender())?;
+    slash_helper(ctx, host, logger, sender_address, params)?;
+    host.state_mut().touch(get_current_timestamp(ctx));
+    Ok(())
+}
+
+/// Slashes `params.amount` of `params.staker` on behalf of
+/// `sender_address`, who must be the admin.
+fn slash_helper(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>,
+    logger: &mut Logger,
+    sender_address: AccountAddress,
+    params: SlashParams
 ) -> ContractResult<()> {
+    let SlashParams { staker, amount: slash_amount, reason_code } = params;
+    let current_time = get_current_timestamp(ctx);
     let state = host.state_mut();
-    ensure!(ctx.sender().matches_account(&state.admin), Error::OnlyAdmin);
+    ensure!(sender_address == state.admin, Error::OnlyAdmin);
+    ensure!(
+        staker != state.admin && !state.slash_exempt.contains(&staker),
+        Error::SlashExempt
+    );
+    let reward_per_token = state.update_reward_per_token(current_time);
     
-    let staker: AccountAddress = ctx.parameter_cursor().get()?;
     let mut stake_info = state.stakes
         .entry(staker)
         .occupied_or(Error::NoStakeFound)?;
 
     ensure!(!stake_info.slashed, Error::AlreadySlashed);
-
-    // Mark as slashed
-    stake_info.slashed = true;
+    ensure!(
+        slash_amount.0 > 0 && slash_amount.0 <= stake_info.amount,
+        Error::InvalidSlashAmount
+    );
+
+    // Forfeit all rewards accrued so far. They were never taken out of the
+    // rewards pool, so they stay available to other stakers.
+    state.reward_liability.remove(&stake_info, reward_per_token, current_time);
+    stake_info.accrue_rewards(
+        current_time,
+        reward_per_token,
+        state.max_accrual_seconds,
+        state.reward_rounding
+    );
+    let forfeited_rewards = TokenAmountU64(stake_info.pending_rewards);
+    stake_info.take_rewards(forfeited_rewards.0);
+
+    // Move the slashed principal to the rewards pool and mark the stake as
+    // slashed once nothing is left
+    let was_participant = stake_info.is_participant();
+    stake_info.remove_principal(slash_amount.0, current_time);
+    stake_info.slashed = stake_info.amount == 0;
+    state.reward_liability.add(&stake_info, reward_per_token, current_time);
+    state.total_staked -= slash_amount;
+    state.rewards_pool += slash_amount;
+
+    // Funds already unbonding lose `slashing_rate` as well
+    let mut slashed_unbonding = TokenAmountU64(0);
+    for unbonding in stake_info.unbonding.iter_mut() {
+        let cut = unbonding.amount.0
+            .checked_mul(state.slashing_rate)
+            .ok_or(Error::ArithmeticOverflow)? / BASIS_POINTS_DENOMINATOR;
+        unbonding.amount.0 -= cut;
+        slashed_unbonding.0 += cut;
+    }
+    stake_info.unbonding.retain(|unbonding| unbonding.amount.0 > 0);
+    state.total_unbonding -= slashed_unbonding;
+    state.rewards_pool += slashed_unbonding;
+
+    let is_participant = stake_info.is_participant();
+    drop(stake_info);
+    state.update_participants(was_participant, is_participant);
+    state.record_slash(SlashRecord {
+        staker,
+        amount: slash_amount,
+        reason_code,
+        timestamp: current_time,
+    });
+    state.log_receipt_burn(logger, staker, slash_amount)?;
+
+    state.log_event(logger, Event::Slashed(SlashEvent {
+        user: staker,
+        slashed_amount: slash_amount,
+        forfeited_rewards,
+        slash_timestamp: current_time,
+        reason_code,
+        slashed_unbonding,
+    }))?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[concordium_cfg_test]
+mod tests {
+    use super::*;
+
+    /// One year in seconds.
+    const YEAR: u64 = SECONDS_PER_YEAR as u64;
+
+    /// One token unit as scaled by `calculate_reward`.
+    const UNIT: u128 = REWARD_PER_TOKEN_PRECISION;
+
+    /// Staking for one year pays the APR of the staked amount.
+    #[concordium_test]
+    fn test_calculate_reward_one_year() {
+        claim_eq!(calculate_reward(1_000_000, 0, YEAR, 1_000), 100_000 * UNIT);
+        claim_eq!(calculate_reward(1_000_000, YEAR, 2 * YEAR, INITIAL_APR), 13_900 * UNIT);
+    }
+
+    /// The reward for one year is `amount * apr / APR_DENOMINATOR` for any
+    /// APR, the divisor having no other scaling.
+    #[concordium_test]
+    fn test_one_year_reward_uses_apr_denominator() {
+        for (amount, apr) in [(1_000_000u64, 1u64), (123_456_789, INITIAL_APR), (10, 10_000)] {
+            let expected = (amount as u128) * (apr as u128) / APR_DENOMINATOR;
+            claim_eq!(calculate_reward(amount, 0, YEAR, apr) / UNIT, expected);
+        }
+    }
+
+    /// No rewards accrue without a stake, time or APR, or when the clock
+    /// goes backwards.
+    #[concordium_test]
+    fn test_calculate_reward_zero() {
+        claim_eq!(calculate_reward(0, 0, YEAR, 1_000), 0);
+        claim_eq!(calculate_reward(1_000_000, YEAR, YEAR, 1_000), 0);
+        claim_eq!(calculate_reward(1_000_000, 0, YEAR, 0), 0);
+        claim_eq!(calculate_reward(1_000_000, YEAR, 0, 1_000), 0);
+    }
+
+    /// Fractions of a token unit are kept.
+    #[concordium_test]
+    fn test_calculate_reward_fraction() {
+        // 1.5 units: 3 units per year for half a year
+        claim_eq!(calculate_reward(30, 0, YEAR / 2, 1_000), 3 * UNIT / 2);
+        claim_eq!(calculate_reward(3, 0, YEAR, 1_000), 3 * UNIT / 10);
+    }
+
+    /// Amounts near `u64::MAX` do not overflow.
+    #[concordium_test]
+    fn test_calculate_reward_large_amount() {
+        let reward = calculate_reward(u64::MAX, 0, YEAR, 1_000);
+        claim_eq!(reward / UNIT, (u64::MAX / 10) as u128);
+    }
+
+    /// Floor rounds fractions of a token unit down, Nearest rounds halves up,
+    /// and the remainder is returned.
+    #[concordium_test]
+    fn test_round_rewards() {
+        let half = (UNIT / 2) as i128;
+        claim_eq!(round_rewards(3 * half, RoundingMode::Floor), (1, half));
+        claim_eq!(round_rewards(3 * half, RoundingMode::Nearest), (2, -half));
+        claim_eq!(round_rewards(half / 2, RoundingMode::Nearest), (0, half / 2));
+        claim_eq!(round_rewards(-half, RoundingMode::Floor), (0, -half));
+        claim_eq!(round_rewards(-half, RoundingMode::Nearest), (0, -half));
+    }
+
+    /// Both modes pay the exact rewards over many accruals, within one
+    /// token unit, on the locked APR and the accumulator path alike.
+    #[concordium_test]
+    fn test_rounding_modes_carry_remainder() {
+        for rounding in [RoundingMode::Floor, RoundingMode::Nearest] {
+            // 1.5 units per half year at a locked APR of 10%
+            let mut locked = StakeInfo::new(0, 0);
+            locked.locked_apr = Some(1_000);
+            claim_eq!(locked.add_position(30, 0, 0, 0), Ok(()));
+            // 1.5 units per accrual from the accumulator
+            let mut unlocked = StakeInfo::new(0, 0);
+            claim_eq!(unlocked.add_position(30, 0, 0, 0), Ok(()));
+            let reward_per_token_step = UNIT / 20;
+
+            for step in 1..=10u64 {
+                let reward_per_token = reward_per_token_step * step as u128;
+                locked.accrue_rewards(step * YEAR / 2, reward_per_token, None, rounding);
+                unlocked.accrue_rewards(step * YEAR / 2, reward_per_token, None, rounding);
+            }
+            claim_eq!(locked.pending_rewards, 15);
+            claim_eq!(unlocked.pending_rewards, 15);
+        }
+    }
+
+    /// Compounding daily yields slightly more than the APR.
+    #[concordium_test]
+    fn test_compounded_apr() {
+        claim_eq!(compounded_apr(0), 0);
+        claim_eq!(compounded_apr(INITIAL_APR), 139);
+        claim_eq!(compounded_apr(1_000), 1_051);
+        claim_eq!(compounded_apr(10_000), 17_145);
+    }
+
+    /// Compounding a huge APR saturates instead of overflowing.
+    #[concordium_test]
+    fn test_compounded_apr_saturates() {
+        claim_eq!(compounded_apr(u64::MAX), u64::MAX);
+    }
+
+    /// The blended APR is weighted by the amounts.
+    #[concordium_test]
+    fn test_weighted_apr() {
+        claim_eq!(weighted_apr(100, 1_000, 100, 2_000), 1_500);
+        claim_eq!(weighted_apr(300, 1_000, 100, 2_000), 1_250);
+        claim_eq!(weighted_apr(0, 1_000, 100, 2_000), 2_000);
+        claim_eq!(weighted_apr(0, 1_000, 0, 2_000), 2_000);
+        claim_eq!(weighted_apr(u64::MAX, u64::MAX, u64::MAX, u64::MAX), u64::MAX);
+        claim_eq!(weighted_apr(u64::MAX, u64::MAX, 1, 0), u64::MAX - 1);
+    }
+
+    /// The tracked liability covers the rewards of stakes at the global APR,
+    /// a locked APR and a lock bonus, and is cleared when they are removed.
+    #[concordium_test]
+    fn test_reward_liability() {
+        let mut liability = RewardLiability::default();
+        let mut stake = StakeInfo::new(0, 0);
+        claim_eq!(stake.add_position(1_000_000, 0, 0, 0), Ok(()));
+        liability.add(&stake, 0, 0);
+        let mut locked = StakeInfo::new(0, 0);
+        locked.locked_apr = Some(2_000);
+        claim_eq!(locked.add_position(1_000_000, 0, YEAR, 500), Ok(()));
+        liability.add(&locked, 0, 0);
+
+        // One year at a global APR of 10%, and one unit per position for
+        // rounding
+        let reward_per_token = 1_000 * REWARD_PER_TOKEN_PRECISION / APR_DENOMINATOR;
+        claim_eq!(liability.total(reward_per_token, YEAR), 100_000 + 200_000 + 50_000 + 2);
+
+        liability.advance(reward_per_token, YEAR);
+        liability.remove(&stake, reward_per_token, YEAR);
+        liability.remove(&locked, reward_per_token, YEAR);
+        claim_eq!(liability, RewardLiability::default());
+    }
+
+    /// Unlocked deposits are merged into one position, while locked deposits
+    /// each take a position up to `MAX_POSITIONS`.
+    #[concordium_test]
+    fn test_add_position() {
+        let mut stake = StakeInfo::new(0, 0);
+        for time in 0..(MAX_POSITIONS as u64) * 2 {
+            claim_eq!(stake.add_position(1, time, time, 0), Ok(()));
+        }
+        claim_eq!(stake.positions.len(), 1);
+        claim_eq!(stake.amount, (MAX_POSITIONS as u64) * 2);
+
+        // A lock that ended is merged with the next deposit
+        claim_eq!(stake.add_position(1, 100, 200, 500), Ok(()));
+        claim_eq!(stake.positions.len(), 2);
+        claim_eq!(stake.add_position(1, 200, 200, 0), Ok(()));
+        claim_eq!(stake.positions.len(), 1);
+
+        for _ in 1..MAX_POSITIONS {
+            claim_eq!(stake.add_position(1, 300, YEAR, 500), Ok(()));
+        }
+        claim_eq!(stake.add_position(1, 300, YEAR, 500), Err(Error::TooManyPositions));
+        claim_eq!(stake.add_position(1, 300, 300, 0), Ok(()));
+        claim_eq!(stake.positions.len(), MAX_POSITIONS);
+        claim_eq!(stake.unlocked_amount(300), (MAX_POSITIONS as u64) * 2 + 3);
+    }
+
+    /// Each deposit kind parses from its serialization.
+    #[concordium_test]
+    fn test_parse_deposit_kind() {
+        let stake = DepositKind::Stake(StakeData {
+            lock_days: 30,
+            beneficiary: Some(AccountAddress([2u8; 32])),
+        });
+        claim!(matches!(
+            parse_deposit_kind(&to_bytes(&stake)),
+            Ok(DepositKind::Stake(StakeData { lock_days: 30, beneficiary }))
+                if beneficiary == Some(AccountAddress([2u8; 32]))
+        ));
+        claim!(matches!(
+            parse_deposit_kind(&to_bytes(&DepositKind::FundRewards)),
+            Ok(DepositKind::FundRewards)
+        ));
+        claim!(matches!(
+            parse_deposit_kind(&to_bytes(&DepositKind::FundRewardsVested(42))),
+            Ok(DepositKind::FundRewardsVested(42))
+        ));
+    }
+
+    /// Empty, unknown or trailing data is rejected.
+    #[concordium_test]
+    fn test_parse_deposit_kind_invalid() {
+        claim!(matches!(parse_deposit_kind(&[]), Err(Error::UnknownDeposit)));
+        claim!(matches!(parse_deposit_kind(&[3]), Err(Error::UnknownDeposit)));
+        claim!(matches!(parse_deposit_kind(&[1, 0]), Err(Error::UnknownDeposit)));
+        claim!(matches!(parse_deposit_kind(&[2, 1]), Err(Error::UnknownDeposit)));
+    }
+}
@@ -0,0 +1,273 @@
+//! SPDX-License-Identifier: MIT
+//!
+//! A minimal stand-in for the EUROe token used by the staking contract tests.
+//! It holds a single fungible token that anyone can mint and burn. Transfers
+//! are not authorized, so the staking contract can move tokens of any account
+//! without operator setup. Transfers can be made to fail, to test how the
+//! staking contract handles a failing token contract, or to call back into a
+//! contract, to test its reentrancy protection. It reports support for CIS-0
+//! and CIS-2, as the staking contract checks before accepting a token.
+
+use concordium_std::*; // Import Concordium standard library.
+use concordium_cis2::*; // Import Concordium CIS-2 library.
+
+/// The token ID of the EUROe token
+type ContractTokenId = TokenIdUnit;
+
+/// The token amount type of the EUROe token
+type ContractTokenAmount = TokenAmountU64;
+
+/// The standards the mock token reports support for
+const SUPPORTS_STANDARDS: [StandardIdentifier<'static>; 2] = [
+    CIS0_STANDARD_IDENTIFIER,
+    CIS2_STANDARD_IDENTIFIER,
+];
+
+/// Custom errors of the mock token
+#[derive(Serialize, Debug, PartialEq, Eq, Reject, SchemaType)]
+enum CustomContractError {
+    /// Failed parsing the parameter.
+    #[from(ParseError)]
+    ParseParams,
+
+    /// Failed invoking the receive hook of a contract.
+    InvokeContractError,
+
+    /// Transfers are set to fail.
+    TransfersFailing,
+}
+
+/// Errors of the mock token
+type ContractError = Cis2Error<CustomContractError>;
+
+/// Result type of the mock token
+type ContractResult<A> = Result<A, ContractError>;
+
+/// Mint parameters
+#[derive(Serialize, SchemaType)]
+pub struct MintParams {
+    /// The owner of the minted tokens.
+    pub owner: Address,
+
+    /// The amount of tokens to mint.
+    pub amount: ContractTokenAmount,
+}
+
+/// A call the mock token makes after each transfer
+#[derive(Serialize, SchemaType, Clone)]
+pub struct ReentryParams {
+    /// The contract to call.
+    pub contract: ContractAddress,
+
+    /// The entrypoint to call.
+    pub entrypoint: OwnedEntrypointName,
+
+    /// The serialized parameter of the call.
+    pub parameter: Vec<u8>,
+}
+
+/// State of the mock token.
+#[derive(Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+struct State<S = StateApi> {
+    /// The balance of each address.
+    balances: StateMap<Address, ContractTokenAmount, S>,
+
+    /// Whether all transfers fail.
+    transfers_failing: bool,
+
+    /// The call made after each transfer, if any.
+    reentry: Option<ReentryParams>,
+}
+
+/// Initialize the mock token without any balances.
+#[init(contract = "euroe_mock")]
+fn contract_init(_ctx: &InitContext, state_builder: &mut StateBuilder) -> InitResult<State> {
+    Ok(State {
+        balances: state_builder.new_map(),
+        transfers_failing: false,
+        reentry: None,
+    })
+}
+
+/// Mint tokens to any address.
+#[receive(
+    contract = "euroe_mock",
+    name = "mint",
+    parameter = "MintParams",
+    error = "ContractError",
+    mutable
+)]
+fn contract_mint(ctx: &ReceiveContext, host: &mut Host<State>) -> ContractResult<()> {
+    let params: MintParams = ctx.parameter_cursor().get()?;
+    let mut balance = host
+        .state_mut()
+        .balances.entry(params.owner)
+        .or_insert(TokenAmountU64(0));
+    *balance += params.amount;
+    Ok(())
+}
+
+/// Burn tokens of any address, taking the same parameters as `mint`.
+#[receive(
+    contract = "euroe_mock",
+    name = "burn",
+    parameter = "MintParams",
+    error = "ContractError",
+    mutable
+)]
+fn contract_burn(ctx: &ReceiveContext, host: &mut Host<State>) -> ContractResult<()> {
+    let params: MintParams = ctx.parameter_cursor().get()?;
+    let mut balance = host
+        .state_mut()
+        .balances.entry(params.owner)
+        .occupied_or(ContractError::InsufficientFunds)?;
+    ensure!(*balance >= params.amount, ContractError::InsufficientFunds);
+    *balance -= params.amount;
+    Ok(())
+}
+
+/// Set whether all transfers fail.
+#[receive(
+    contract = "euroe_mock",
+    name = "setTransfersFailing",
+    parameter = "bool",
+    error = "ContractError",
+    mutable
+)]
+fn contract_set_transfers_failing(
+    ctx: &ReceiveContext,
+    host: &mut Host<State>
+) -> ContractResult<()> {
+    host.state_mut().transfers_failing = ctx.parameter_cursor().get()?;
+    Ok(())
+}
+
+/// Set the call made after each transfer, or `None` to make no call.
+#[receive(
+    contract = "euroe_mock",
+    name = "setReentry",
+    parameter = "Option<ReentryParams>",
+    error = "ContractError",
+    mutable
+)]
+fn contract_set_reentry(ctx: &ReceiveContext, host: &mut Host<State>) -> ContractResult<()> {
+    host.state_mut().reentry = ctx.parameter_cursor().get()?;
+    Ok(())
+}
+
+/// Transfer tokens, invoking the receive hook of contract receivers. Then
+/// makes the reentry call if one is set, ignoring whether it fails.
+#[receive(
+    contract = "euroe_mock",
+    name = "transfer",
+    parameter = "TransferParameter",
+    error = "ContractError",
+    mutable
+)]
+fn contract_transfer(ctx: &ReceiveContext, host: &mut Host<State>) -> ContractResult<()> {
+    let TransferParams(transfers): TransferParams<ContractTokenId, ContractTokenAmount> = ctx
+        .parameter_cursor()
+        .get()?;
+    ensure!(
+        !host.state().transfers_failing,
+        ContractError::Custom(CustomContractError::TransfersFailing)
+    );
+
+    for Transfer { token_id, amount, from, to, data } in transfers {
+        ensure_eq!(token_id, TokenIdUnit(), ContractError::InvalidTokenId);
+
+        {
+            let state = host.state_mut();
+            let mut from_balance = state.balances
+                .entry(from)
+                .occupied_or(ContractError::InsufficientFunds)?;
+            ensure!(*from_balance >= amount, ContractError::InsufficientFunds);
+            *from_balance -= amount;
+            drop(from_balance);
+
+            let mut to_balance = state.balances
+                .entry(to.address())
+                .or_insert(TokenAmountU64(0));
+            *to_balance += amount;
+        }
+
+        if let Receiver::Contract(address, entrypoint) = to {
+            let parameter = OnReceivingCis2Params {
+                token_id,
+                amount,
+                from,
+                data,
+            };
+            host.invoke_contract(
+                &address,
+                &parameter,
+                entrypoint.as_entrypoint_name(),
+                Amount::zero()
+            ).map_err(|_| ContractError::Custom(CustomContractError::InvokeContractError))?;
+        }
+    }
+
+    if let Some(reentry) = host.state().reentry.clone() {
+        let _ = host.invoke_contract_raw(
+            &reentry.contract,
+            Parameter::new_unchecked(&reentry.parameter),
+            reentry.entrypoint.as_entrypoint_name(),
+            Amount::zero()
+        );
+    }
+
+    Ok(())
+}
+
+/// Get the balances of the queried addresses.
+#[receive(
+    contract = "euroe_mock",
+    name = "balanceOf",
+    parameter = "BalanceOfQueryParams<ContractTokenId>",
+    return_value = "BalanceOfQueryResponse<ContractTokenAmount>",
+    error = "ContractError"
+)]
+fn contract_balance_of(
+    ctx: &ReceiveContext,
+    host: &Host<State>
+) -> ContractResult<BalanceOfQueryResponse<ContractTokenAmount>> {
+    let params: BalanceOfQueryParams<ContractTokenId> = ctx.parameter_cursor().get()?;
+
+    let mut response = Vec::with_capacity(params.queries.len());
+    for query in params.queries {
+        ensure_eq!(query.token_id, TokenIdUnit(), ContractError::InvalidTokenId);
+        let balance = host.state().balances.get(&query.address).map_or(TokenAmountU64(0), |b| *b);
+        response.push(balance);
+    }
+
+    Ok(BalanceOfQueryResponse::from(response))
+}
+
+/// Get whether the mock token supports the queried standards.
+#[receive(
+    contract = "euroe_mock",
+    name = "supports",
+    parameter = "SupportsQueryParams",
+    return_value = "SupportsQueryResponse",
+    error = "ContractError"
+)]
+fn contract_supports(
+    ctx: &ReceiveContext,
+    _host: &Host<State>
+) -> ContractResult<SupportsQueryResponse> {
+    let params: SupportsQueryParams = ctx.parameter_cursor().get()?;
+
+    let response: Vec<SupportResult> = params.queries
+        .iter()
+        .map(|standard| {
+            if SUPPORTS_STANDARDS.contains(&standard.as_standard_identifier()) {
+                SupportResult::Support
+            } else {
+                SupportResult::NoSupport
+            }
+        })
+        .collect();
+
+    Ok(SupportsQueryResponse::from(response))
+}
@@ -0,0 +1,40 @@
+//! SPDX-License-Identifier: MIT
+//!
+//! A minimal price oracle used by the staking contract tests. It returns the
+//! price set with `setPrice` for any token, and fails while no price is set,
+//! to test how the staking contract handles an unavailable price.
+
+use concordium_std::*; // Import Concordium standard library.
+
+/// State of the mock oracle.
+#[derive(Serial, Deserial)]
+struct State {
+    /// The price returned for any token, `None` to fail.
+    price: Option<u64>,
+}
+
+/// Initialize the mock oracle without a price.
+#[init(contract = "oracle_mock")]
+fn contract_init(_ctx: &InitContext, _state_builder: &mut StateBuilder) -> InitResult<State> {
+    Ok(State {
+        price: None,
+    })
+}
+
+/// Set the price returned for any token, or `None` to fail.
+#[receive(contract = "oracle_mock", name = "setPrice", parameter = "Option<u64>", mutable)]
+fn contract_set_price(ctx: &ReceiveContext, host: &mut Host<State>) -> ReceiveResult<()> {
+    host.state_mut().price = ctx.parameter_cursor().get()?;
+    Ok(())
+}
+
+/// Get the price of the token at the given address.
+#[receive(
+    contract = "oracle_mock",
+    name = "getPrice",
+    parameter = "ContractAddress",
+    return_value = "u64"
+)]
+fn contract_get_price(_ctx: &ReceiveContext, host: &Host<State>) -> ReceiveResult<u64> {
+    host.state().price.ok_or_else(Reject::default)
+}
@@ -0,0 +1,149 @@
+//! SPDX-License-Identifier: MIT
+//!
+//! A stand-in for the first module of the staking contract, used by the
+//! migration tests. It stores the state in the layout of the first module,
+//! seeded with the stakes given at initialization, and upgrades like it.
+
+use concordium_std::*; // Import Concordium standard library.
+use concordium_cis2::*; // Import Concordium CIS-2 library.
+
+/// Unbonding information
+#[derive(Serialize, SchemaType, Clone)]
+pub struct UnbondingInfo {
+    /// Amount to unbond
+    pub amount: TokenAmountU64,
+
+    /// Unlock time in seconds
+    pub unlock_time: u64,
+}
+
+/// Information about a stake.
+#[derive(Serialize, SchemaType, Clone)]
+pub struct StakeInfo {
+    /// The staked amount of user.
+    pub amount: u64,
+
+    /// Timestamp when the stake was made.
+    pub timestamp: u64,
+
+    /// Unbonding information
+    pub unbonding: Vec<UnbondingInfo>,
+
+    /// Whether the stake is slashed
+    pub slashed: bool,
+
+    /// Pending rewards
+    pub pending_rewards: u64,
+}
+
+/// Initialization parameters
+#[derive(Serialize, SchemaType)]
+pub struct InitParams {
+    /// The admin account.
+    pub admin: AccountAddress,
+
+    /// Address of the EUROe token contract.
+    pub token_address: ContractAddress,
+
+    /// Unbonding period in seconds
+    pub unbonding_period: u64,
+
+    /// Slashing rate in basis points
+    pub slashing_rate: u64,
+
+    /// The annual percentage rate in basis points
+    pub apr: u64,
+
+    /// The available rewards
+    pub rewards_pool: TokenAmountU64,
+
+    /// The stakes to seed
+    pub stakes: Vec<(AccountAddress, StakeInfo)>,
+
+    /// The nonces to seed
+    pub nonces: Vec<(AccountAddress, u64)>,
+}
+
+/// Upgrade parameters
+#[derive(Serialize, SchemaType)]
+pub struct UpgradeParams {
+    /// The new module reference.
+    pub module: ModuleReference,
+
+    /// Optional entrypoint to call in the new module after upgrade.
+    pub migrate: Option<(OwnedEntrypointName, OwnedParameter)>,
+}
+
+/// State of the contract in the layout of the first module.
+#[derive(Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+struct State<S = StateApi> {
+    paused: bool,
+    admin: AccountAddress,
+    total_staked: TokenAmountU64,
+    apr: u64,
+    stakes: StateMap<AccountAddress, StakeInfo, S>,
+    token_address: ContractAddress,
+    total_participants: u64,
+    nonces_registry: StateMap<AccountAddress, u64, S>,
+    unbonding_period: u64,
+    slashing_rate: u64,
+    rewards_pool: TokenAmountU64,
+    total_rewards_paid: TokenAmountU64,
+}
+
+/// Initialize the contract with the given stakes and nonces.
+#[init(contract = "concordium_staking", parameter = "InitParams")]
+fn contract_init(ctx: &InitContext, state_builder: &mut StateBuilder) -> InitResult<State> {
+    let params: InitParams = ctx.parameter_cursor().get()?;
+
+    let mut state = State {
+        paused: false,
+        admin: params.admin,
+        total_staked: TokenAmountU64(0),
+        apr: params.apr,
+        stakes: state_builder.new_map(),
+        token_address: params.token_address,
+        total_participants: 0,
+        nonces_registry: state_builder.new_map(),
+        unbonding_period: params.unbonding_period,
+        slashing_rate: params.slashing_rate,
+        rewards_pool: params.rewards_pool,
+        total_rewards_paid: TokenAmountU64(0),
+    };
+    for (account, stake) in params.stakes {
+        state.total_staked += TokenAmountU64(stake.amount);
+        state.total_participants += 1;
+        let _ = state.stakes.insert(account, stake);
+    }
+    for (account, nonce) in params.nonces {
+        let _ = state.nonces_registry.insert(account, nonce);
+    }
+
+    Ok(state)
+}
+
+/// Upgrade the contract and call the migration function if provided.
+#[receive(
+    contract = "concordium_staking",
+    name = "upgrade",
+    parameter = "UpgradeParams",
+    low_level
+)]
+fn contract_upgrade(ctx: &ReceiveContext, host: &mut LowLevelHost) -> ReceiveResult<()> {
+    let state: State = host.state().read_root()?;
+    ensure!(ctx.sender().matches_account(&state.admin));
+
+    let params: UpgradeParams = ctx.parameter_cursor().get()?;
+    host.upgrade(params.module)?;
+    if let Some((func, parameters)) = params.migrate {
+        host.invoke_contract_raw(
+            &ctx.self_address(),
+            parameters.as_parameter(),
+            func.as_entrypoint_name(),
+            Amount::zero()
+        )?;
+    }
+
+    Ok(())
+}
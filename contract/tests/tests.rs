@@ -1,107 +1,4383 @@
-// use concordium_smart_contract_testing::*;
-// use staking_contract::*;
-
-// /// A test account.
-// const ALICE: AccountAddress = AccountAddress([0u8; 32]);
-// const ALICE_ADDR: Address = Address::Account(ALICE);
-
-// /// The initial balance of the ALICE test account.
-// const ACC_INITIAL_BALANCE: Amount = Amount::from_ccd(10_000);
-
-// /// A [`Signer`] with one set of keys, used for signing transactions.
-// const SIGNER: Signer = Signer::with_one_key();
-
-// /// Test that invoking the `receive` endpoint with the `false` parameter
-// /// succeeds in updating the contract.
-// #[test]
-// fn test_throw_no_error() {
-//     let (mut chain, init) = initialize();
-
-//     // Update the contract via the `receive` entrypoint with the parameter `false`.
-//     chain
-//         .contract_update(
-//             SIGNER,
-//             ALICE,
-//             ALICE_ADDR,
-//             Energy::from(10_000),
-//             UpdateContractPayload {
-//                 address: init.contract_address,
-//                 amount: Amount::zero(),
-//                 receive_name: OwnedReceiveName::new_unchecked(
-//                     "staking_contract.receive".to_string(),
-//                 ),
-//                 message: OwnedParameter::from_serial(&false).expect("Parameter within size bounds"),
-//             },
-//         )
-//         .expect("Update succeeds with `false` as input.");
-// }
-
-// /// Test that invoking the `receive` endpoint with the `true` parameter
-// /// results in the `CustomError` being thrown.
-// #[test]
-// fn test_throw_error() {
-//     let (mut chain, init) = initialize();
-
-//     // Update the contract via the `receive` entrypoint with the parameter `true`.
-//     let update = chain
-//         .contract_update(
-//             SIGNER,
-//             ALICE,
-//             ALICE_ADDR,
-//             Energy::from(10_000),
-//             UpdateContractPayload {
-//                 address: init.contract_address,
-//                 amount: Amount::zero(),
-//                 receive_name: OwnedReceiveName::new_unchecked(
-//                     "staking_contract.receive".to_string(),
-//                 ),
-//                 message: OwnedParameter::from_serial(&true).expect("Parameter within size bounds"),
-//             },
-//         )
-//         .expect_err("Update fails with `true` as input.");
-
-//     // Check that the contract returned `CustomError`.
-//     let error: Cus = update.parse_return_value().expect("Deserialize `Error`");
-//     assert_eq!(error, Error::CustomError);
-// }
-
-// /// Helper method for initializing the contract.
-// ///
-// /// Does the following:
-// ///  - Creates the [`Chain`]
-// ///  - Creates one account, `Alice` with `10_000` CCD as the initial balance.
-// ///  - Initializes the contract.
-// ///  - Returns the [`Chain`] and the [`ContractInitSuccess`]
-// fn initialize() -> (Chain, ContractInitSuccess) {
-//     // Initialize the test chain.
-//     let mut chain = Chain::new();
-
-//     // Create the test account.
-//     chain.create_account(Account::new(ALICE, ACC_INITIAL_BALANCE));
-
-//     // Load the module.
-//     let module = module_load_v1("./concordium-out/module.wasm.v1").expect("Module exists at path");
-//     // Deploy the module.
-//     let deployment = chain
-//         .module_deploy_v1(SIGNER, ALICE, module)
-//         .expect("Deploy valid module");
-
-//     let parameter = CustomInputParameter { num: 0 };
-
-//     // Initialize the contract.
-//     let init = chain
-//         .contract_init(
-//             SIGNER,
-//             ALICE,
-//             Energy::from(10_000),
-//             InitContractPayload {
-//                 amount: Amount::zero(),
-//                 mod_ref: deployment.module_reference,
-//                 init_name: OwnedContractName::new_unchecked("init_staking_contract".to_string()),
-//                 param: OwnedParameter::from_serial(&parameter).expect("Parameter is valid."),
-//             },
-//         )
-//         .expect("Initializing contract");
-
-//     (chain, init)
-// }
+//! Integration tests of the staking contract against a mock EUROe token.
+//!
+//! The modules are built the first time a test deploys them. The chain only
+//! accepts MVP Wasm, and the prebuilt standard library of recent toolchains
+//! uses newer Wasm features, so the build recompiles it on nightly:
+//!
+//! ```sh
+//! rustup toolchain install nightly --component rust-src
+//! ```
+use concordium_cis2::*;
+use concordium_smart_contract_testing::*;
+use concordium_std::{
+    AccountAddress, AccountSignatures, CredentialSignatures, SchemaType, Serialize, Signature,
+    SignatureEd25519,
+};
+use ed25519_dalek::Signer as _;
+use staking_contract::*;
+use std::{collections::BTreeMap, path::PathBuf, process::Command, sync::OnceLock};
+
+/// The admin account.
+const ADMIN: AccountAddress = AccountAddress([1u8; 32]);
+const ADMIN_ADDR: Address = Address::Account(ADMIN);
+
+/// A staker account.
+const ALICE: AccountAddress = AccountAddress([2u8; 32]);
+const ALICE_ADDR: Address = Address::Account(ALICE);
+
+/// Another staker account.
+const BOB: AccountAddress = AccountAddress([3u8; 32]);
+const BOB_ADDR: Address = Address::Account(BOB);
+
+/// The initial CCD balance of the test accounts.
+const ACC_INITIAL_BALANCE: Amount = Amount::from_ccd(10_000);
+
+/// A [`Signer`] with one set of keys, used for signing transactions.
+const SIGNER: Signer = Signer::with_one_key();
+
+/// The unbonding period of the contract in seconds.
+const UNBONDING_PERIOD: u64 = 7 * 24 * 60 * 60;
+
+/// The energy given to each transaction.
+const ENERGY: Energy = Energy { energy: 100_000 };
+
+/// The crates of the modules deployed by the tests, as manifest and crate
+/// name.
+const CRATES: [(&str, &str); 4] = [
+    ("Cargo.toml", "staking_contract"),
+    ("tests/euroe-mock/Cargo.toml", "euroe_mock"),
+    ("tests/staking-v0/Cargo.toml", "staking_v0"),
+    ("tests/oracle-mock/Cargo.toml", "oracle_mock"),
+];
+
+/// Mint parameters of the mock token.
+#[derive(Serialize, SchemaType)]
+struct MintParams {
+    owner: Address,
+    amount: TokenAmountU64,
+}
+
+/// Reentry parameters of the mock token.
+#[derive(Serialize, SchemaType)]
+struct ReentryParams {
+    contract: ContractAddress,
+    entrypoint: OwnedEntrypointName,
+    parameter: Vec<u8>,
+}
+
+/// A stake in the layout of the first module.
+#[derive(Serialize, SchemaType)]
+struct StakeInfoV0 {
+    amount: u64,
+    timestamp: u64,
+    unbonding: Vec<UnbondingInfo>,
+    slashed: bool,
+    pending_rewards: u64,
+}
+
+/// Initialization parameters of the stand-in for the first module.
+#[derive(Serialize, SchemaType)]
+struct InitV0Params {
+    admin: AccountAddress,
+    token_address: ContractAddress,
+    unbonding_period: u64,
+    slashing_rate: u64,
+    apr: u64,
+    rewards_pool: TokenAmountU64,
+    stakes: Vec<(AccountAddress, StakeInfoV0)>,
+    nonces: Vec<(AccountAddress, u64)>,
+}
+
+/// The deployed contracts.
+struct Setup {
+    chain: Chain,
+    token: ContractAddress,
+    staking: ContractAddress,
+}
+
+/// Test the full lifecycle of a stake: stake, accrue, claim, unstake and
+/// complete the unstake, checking balances and events at each step.
+#[test]
+fn test_stake_claim_unstake_lifecycle() {
+    let Setup { mut chain, token, staking } = initialize();
+
+    // Fund the rewards pool from the admin.
+    mint(&mut chain, token, ADMIN_ADDR, 10_000_000);
+    let update = update_contract(
+        &mut chain,
+        ADMIN,
+        staking,
+        "fundRewards",
+        &TokenAmountU64(10_000_000)
+    ).expect("Fund rewards");
+    assert!(
+        contract_events(&update, staking).iter().any(|e| matches!(e, Event::RewardsFunded(_)))
+    );
+    assert_eq!(balance_of(&chain, token, Address::Contract(staking)), 10_000_000);
+
+    // Alice stakes through a transfer to `stake`.
+    mint(&mut chain, token, ALICE_ADDR, 100_000_000);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(100_000_000),
+        from: ALICE_ADDR,
+        to: Receiver::Contract(staking, OwnedEntrypointName::new_unchecked("stake".into())),
+        data: AdditionalData::empty(),
+    };
+    let update = update_contract(
+        &mut chain,
+        ALICE,
+        token,
+        "transfer",
+        &TransferParams::from(vec![transfer])
+    ).expect("Stake");
+    assert!(contract_events(&update, staking).iter().any(|e| matches!(e, Event::Staked(_))));
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 0);
+    assert_eq!(balance_of(&chain, token, Address::Contract(staking)), 110_000_000);
+
+    // Rewards accrue over time.
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let earned: u64 = invoke(&chain, staking, "getEarnedRewards", &ALICE)
+        .parse_return_value()
+        .expect("Parse earned rewards");
+    assert!(earned > 0);
+
+    // Alice claims the rewards.
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect("Claim rewards");
+    let claimed: TokenAmountU64 = update.parse_return_value().expect("Parse claimed rewards");
+    assert_eq!(claimed.0, earned);
+    assert!(contract_events(&update, staking).iter().any(|e| matches!(e, Event::Claimed(_))));
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), earned);
+
+    // Alice unstakes everything, which starts unbonding.
+    let update = update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(100_000_000),
+    }).expect("Unstake");
+    assert!(contract_events(&update, staking).iter().any(|e| matches!(e, Event::Unstaked(_))));
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), earned);
+
+    // Completing before the unbonding period ends fails.
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect_err("Complete unstake before unbonding ends");
+
+    // Completing after the unbonding period pays out the principal.
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    let update = update_contract(
+        &mut chain,
+        ALICE,
+        staking,
+        "completeUnstake",
+        &CompleteUnstakeParams { index: None }
+    ).expect("Complete unstake");
+    assert!(
+        contract_events(&update, staking).iter().any(|e| matches!(e, Event::UnstakeCompleted(_)))
+    );
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 100_000_000 + earned);
+    assert_eq!(
+        balance_of(&chain, token, Address::Contract(staking)),
+        10_000_000 - earned
+    );
+}
+
+/// Test that `updateApr` accepts an APR within the bounds set with
+/// `setAprBounds` and rejects a zero or too high APR.
+#[test]
+fn test_update_apr_bounds() {
+    let Setup { mut chain, staking, .. } = initialize();
+
+    update_contract(&mut chain, ADMIN, staking, "setAprBounds", &SetAprBoundsParams {
+        max_apr: Some(10_000),
+        forbid_zero_apr: true,
+    }).expect("Set APR bounds");
+
+    // An APR within the bounds is accepted.
+    update_contract(&mut chain, ADMIN, staking, "updateApr", &UpdateAprParams {
+        new_apr: 1_500,
+    }).expect("Update APR within bounds");
+    assert_eq!(view_apr(&chain, staking), 1_500);
+
+    // A zero APR is rejected.
+    update_contract(&mut chain, ADMIN, staking, "updateApr", &UpdateAprParams {
+        new_apr: 0,
+    }).expect_err("Update APR to zero");
+    assert_eq!(view_apr(&chain, staking), 1_500);
+
+    // An APR above the maximum is rejected.
+    update_contract(&mut chain, ADMIN, staking, "updateApr", &UpdateAprParams {
+        new_apr: 139_000_000,
+    }).expect_err("Update APR above the maximum");
+    assert_eq!(view_apr(&chain, staking), 1_500);
+}
+
+/// Test that event sequence numbers increase by one across a stake and a
+/// claim in separate transactions.
+#[test]
+fn test_event_sequence_numbers() {
+    let Setup { mut chain, token, staking } = initialize();
+
+    mint(&mut chain, token, ADMIN_ADDR, 1_000_000);
+    let update = update_contract(
+        &mut chain,
+        ADMIN,
+        staking,
+        "fundRewards",
+        &TokenAmountU64(1_000_000)
+    ).expect("Fund rewards");
+    let mut seqs: Vec<u64> = sequenced_events(&update, staking)
+        .iter()
+        .map(|e| e.event_seq)
+        .collect();
+
+    // Alice stakes.
+    mint(&mut chain, token, ALICE_ADDR, 100_000_000);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(100_000_000),
+        from: ALICE_ADDR,
+        to: Receiver::Contract(staking, OwnedEntrypointName::new_unchecked("stake".into())),
+        data: AdditionalData::empty(),
+    };
+    let update = update_contract(
+        &mut chain,
+        ALICE,
+        token,
+        "transfer",
+        &TransferParams::from(vec![transfer])
+    ).expect("Stake");
+    seqs.extend(sequenced_events(&update, staking).iter().map(|e| e.event_seq));
+
+    // Alice claims in a later block.
+    chain.tick_block_time(Duration::from_days(30)).expect("Advance time");
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect("Claim rewards");
+    seqs.extend(sequenced_events(&update, staking).iter().map(|e| e.event_seq));
+
+    let expected: Vec<u64> = (0..seqs.len() as u64).collect();
+    assert_eq!(seqs, expected);
+}
+
+/// Test that `simulateUnstake` predicts the unbonding entry, the crystallized
+/// rewards and the remaining stake of the following `unstake`.
+#[test]
+fn test_simulate_unstake() {
+    let Setup { mut chain, token, staking } = initialize();
+
+    mint(&mut chain, token, ALICE_ADDR, 100_000_000);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(100_000_000),
+        from: ALICE_ADDR,
+        to: Receiver::Contract(staking, OwnedEntrypointName::new_unchecked("stake".into())),
+        data: AdditionalData::empty(),
+    };
+    update_contract(
+        &mut chain,
+        ALICE,
+        token,
+        "transfer",
+        &TransferParams::from(vec![transfer])
+    ).expect("Stake");
+    chain.tick_block_time(Duration::from_days(30)).expect("Advance time");
+
+    // Unstaking more than the stake fails in the simulation too.
+    chain
+        .contract_invoke(ALICE, ALICE_ADDR, ENERGY, UpdateContractPayload {
+            amount: Amount::zero(),
+            address: staking,
+            receive_name: receive_name(&chain, staking, "simulateUnstake"),
+            message: OwnedParameter::from_serial(&SimulateUnstakeParams {
+                user: ALICE,
+                amount: TokenAmountU64(100_000_001),
+            }).expect("Parameter within size bounds"),
+        })
+        .expect_err("Simulate unstaking more than the stake");
+
+    let simulation: SimulateUnstakeResult = invoke(
+        &chain,
+        staking,
+        "simulateUnstake",
+        &SimulateUnstakeParams {
+            user: ALICE,
+            amount: TokenAmountU64(40_000_000),
+        }
+    )
+        .parse_return_value()
+        .expect("Parse simulation");
+    let before = stake_info(&chain, staking, ALICE).stake;
+
+    let update = update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(40_000_000),
+    }).expect("Unstake");
+    let unbonding: UnbondingInfo = update.parse_return_value().expect("Parse unbonding");
+    let after = stake_info(&chain, staking, ALICE).stake;
+
+    assert_eq!(simulation.unbonding, unbonding);
+    assert_eq!(simulation.remaining_stake, after.amount);
+    // Alice had no pending rewards before, so the rewards shown as pending
+    // are exactly those the unstake crystallized.
+    assert!(simulation.rewards_crystallized.0 > 0);
+    assert_eq!(simulation.rewards_crystallized.0, before.pending_rewards);
+    assert_eq!(after.pending_rewards, before.pending_rewards);
+}
+
+/// Test that a slash is kept in the slash history with its reason and that
+/// exempt accounts cannot be slashed.
+#[test]
+fn test_slash_history_and_exemption() {
+    let Setup { mut chain, token, staking } = initialize();
+
+    mint(&mut chain, token, ALICE_ADDR, 100_000_000);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(100_000_000),
+        from: ALICE_ADDR,
+        to: Receiver::Contract(staking, OwnedEntrypointName::new_unchecked("stake".into())),
+        data: AdditionalData::empty(),
+    };
+    update_contract(
+        &mut chain,
+        ALICE,
+        token,
+        "transfer",
+        &TransferParams::from(vec![transfer])
+    ).expect("Stake");
+
+    // Slash part of the stake with a reason.
+    update_contract(&mut chain, ADMIN, staking, "slash", &SlashParams {
+        staker: ALICE,
+        amount: TokenAmountU64(10_000_000),
+        reason_code: 7,
+    }).expect("Slash");
+    let history: Vec<SlashRecord> = invoke(&chain, staking, "getSlashHistory", &())
+        .parse_return_value()
+        .expect("Parse slash history");
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].staker, ALICE);
+    assert_eq!(history[0].amount, TokenAmountU64(10_000_000));
+    assert_eq!(history[0].reason_code, 7);
+
+    // An exempt account cannot be slashed.
+    update_contract(&mut chain, ADMIN, staking, "setSlashExempt", &SetSlashExemptParams {
+        account: ALICE,
+        exempt: true,
+    }).expect("Exempt Alice");
+    update_contract(&mut chain, ADMIN, staking, "slash", &SlashParams {
+        staker: ALICE,
+        amount: TokenAmountU64(10_000_000),
+        reason_code: 7,
+    }).expect_err("Slash an exempt account");
+
+    // Neither can the admin.
+    update_contract(&mut chain, ADMIN, staking, "slash", &SlashParams {
+        staker: ADMIN,
+        amount: TokenAmountU64(1),
+        reason_code: 7,
+    }).expect_err("Slash the admin");
+
+    let history: Vec<SlashRecord> = invoke(&chain, staking, "getSlashHistory", &())
+        .parse_return_value()
+        .expect("Parse slash history");
+    assert_eq!(history.len(), 1);
+}
+
+/// Test that `getStakeInfoAt` accrues rewards up to the given timestamp,
+/// matches `getStakeInfo` at the current time and rejects timestamps before
+/// the stake.
+#[test]
+fn test_get_stake_info_at() {
+    let Setup { mut chain, token, staking } = initialize();
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    let staked_at = chain.block_time().millis / 1000;
+
+    mint(&mut chain, token, ALICE_ADDR, 100_000_000);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(100_000_000),
+        from: ALICE_ADDR,
+        to: Receiver::Contract(staking, OwnedEntrypointName::new_unchecked("stake".into())),
+        data: AdditionalData::empty(),
+    };
+    update_contract(
+        &mut chain,
+        ALICE,
+        token,
+        "transfer",
+        &TransferParams::from(vec![transfer])
+    ).expect("Stake");
+
+    chain.tick_block_time(Duration::from_days(30)).expect("Advance time");
+    let intermediate = chain.block_time().millis / 1000;
+    chain.tick_block_time(Duration::from_days(30)).expect("Advance time");
+    let now = chain.block_time().millis / 1000;
+
+    let at_intermediate: StakeInfo = invoke(
+        &chain,
+        staking,
+        "getStakeInfoAt",
+        &GetStakeInfoAtParams {
+            user: ALICE,
+            at_timestamp: intermediate,
+        }
+    )
+        .parse_return_value()
+        .expect("Parse stake info");
+    let at_now: StakeInfo = invoke(
+        &chain,
+        staking,
+        "getStakeInfoAt",
+        &GetStakeInfoAtParams {
+            user: ALICE,
+            at_timestamp: now,
+        }
+    )
+        .parse_return_value()
+        .expect("Parse stake info");
+    let current = stake_info(&chain, staking, ALICE).stake;
+
+    assert!(at_intermediate.pending_rewards > 0);
+    assert!(at_intermediate.pending_rewards < at_now.pending_rewards);
+    assert_eq!(at_now, current);
+
+    // A timestamp before the stake is rejected.
+    chain
+        .contract_invoke(ADMIN, ADMIN_ADDR, ENERGY, UpdateContractPayload {
+            amount: Amount::zero(),
+            address: staking,
+            receive_name: receive_name(&chain, staking, "getStakeInfoAt"),
+            message: OwnedParameter::from_serial(&GetStakeInfoAtParams {
+                user: ALICE,
+                at_timestamp: staked_at - 1,
+            }).expect("Parameter within size bounds"),
+        })
+        .expect_err("Query before the stake");
+}
+
+/// Test that funds in the unbonding queue are reported as non-earning and
+/// accrue no rewards over time.
+#[test]
+fn test_unbonding_earns_no_rewards() {
+    let Setup { mut chain, token, staking } = initialize();
+
+    mint(&mut chain, token, ALICE_ADDR, 100_000_000);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(100_000_000),
+        from: ALICE_ADDR,
+        to: Receiver::Contract(staking, OwnedEntrypointName::new_unchecked("stake".into())),
+        data: AdditionalData::empty(),
+    };
+    update_contract(
+        &mut chain,
+        ALICE,
+        token,
+        "transfer",
+        &TransferParams::from(vec![transfer])
+    ).expect("Stake");
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+
+    // Unstake part of the stake.
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(40_000_000),
+    }).expect("Unstake");
+    let before = stake_info(&chain, staking, ALICE);
+    assert_eq!(before.earning_amount, 60_000_000);
+    assert_eq!(before.unbonding_amount, 40_000_000);
+
+    // Only the earning amount accrues rewards.
+    chain.tick_block_time(Duration::from_days(5)).expect("Advance time");
+    let partial = stake_info(&chain, staking, ALICE);
+    let partial_rewards = partial.stake.pending_rewards - before.stake.pending_rewards;
+    assert!(partial_rewards > 0);
+
+    // Once everything is unbonding, nothing accrues anymore.
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(60_000_000),
+    }).expect("Unstake");
+    let unbonding = stake_info(&chain, staking, ALICE);
+    assert_eq!(unbonding.earning_amount, 0);
+    assert_eq!(unbonding.unbonding_amount, 100_000_000);
+    chain.tick_block_time(Duration::from_days(5)).expect("Advance time");
+    let after = stake_info(&chain, staking, ALICE);
+    assert_eq!(after.stake.pending_rewards, unbonding.stake.pending_rewards);
+}
+
+/// Test that a claim on a drained rewards pool pauses reward payouts and logs
+/// `PoolDepleted` when the circuit breaker is enabled, and that claims work
+/// again once the admin funds the pool and unpauses.
+#[test]
+fn test_pause_on_pool_depletion() {
+    let Setup { mut chain, token, staking } = initialize();
+
+    update_contract(&mut chain, ADMIN, staking, "setAutoPauseOnDepletion", &true)
+        .expect("Enable the circuit breaker");
+
+    // Alice stakes while the rewards pool is empty.
+    mint(&mut chain, token, ALICE_ADDR, 100_000_000);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(100_000_000),
+        from: ALICE_ADDR,
+        to: Receiver::Contract(staking, OwnedEntrypointName::new_unchecked("stake".into())),
+        data: AdditionalData::empty(),
+    };
+    update_contract(
+        &mut chain,
+        ALICE,
+        token,
+        "transfer",
+        &TransferParams::from(vec![transfer])
+    ).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+
+    // The claim succeeds without paying and trips the circuit breaker.
+    let claim = ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    };
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &claim).expect(
+        "Claim on a drained pool"
+    );
+    let claimed: TokenAmountU64 = update.parse_return_value().expect("Parse claimed rewards");
+    assert_eq!(claimed, TokenAmountU64(0));
+    assert!(
+        contract_events(&update, staking).iter().any(|e| matches!(e, Event::PoolDepleted(_)))
+    );
+    let view: ViewResult = invoke(&chain, staking, "view", &())
+        .parse_return_value()
+        .expect("Parse view");
+    assert!(view.pause_flags.rewards_paused);
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 0);
+
+    // Further claims are rejected while paused.
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &claim).expect_err(
+        "Claim while reward payouts are paused"
+    );
+
+    // The admin funds the pool and unpauses, after which claims pay again.
+    mint(&mut chain, token, ADMIN_ADDR, 10_000_000);
+    update_contract(
+        &mut chain,
+        ADMIN,
+        staking,
+        "fundRewards",
+        &TokenAmountU64(10_000_000)
+    ).expect("Fund rewards");
+    update_contract(&mut chain, ADMIN, staking, "setPauseFlags", &PauseFlags::default()).expect(
+        "Unpause"
+    );
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &claim).expect(
+        "Claim after funding"
+    );
+    let claimed: TokenAmountU64 = update.parse_return_value().expect("Parse claimed rewards");
+    assert!(claimed.0 > 0);
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), claimed.0);
+}
+
+/// Test that `stakedBalanceOf` returns the active stake of each queried
+/// address in the order queried.
+#[test]
+fn test_staked_balance_of() {
+    let Setup { mut chain, token, staking } = initialize();
+
+    mint(&mut chain, token, ALICE_ADDR, 100_000_000);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(100_000_000),
+        from: ALICE_ADDR,
+        to: Receiver::Contract(staking, OwnedEntrypointName::new_unchecked("stake".into())),
+        data: AdditionalData::empty(),
+    };
+    update_contract(
+        &mut chain,
+        ALICE,
+        token,
+        "transfer",
+        &TransferParams::from(vec![transfer])
+    ).expect("Stake");
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(25_000_000),
+    }).expect("Unstake");
+
+    let query = BalanceOfQueryParams {
+        queries: [ALICE_ADDR, ADMIN_ADDR, Address::Contract(token)]
+            .into_iter()
+            .map(|address| BalanceOfQuery {
+                token_id: TokenIdUnit(),
+                address,
+            })
+            .collect(),
+    };
+    let response: BalanceOfQueryResponse<TokenAmountU64> = invoke(
+        &chain,
+        staking,
+        "stakedBalanceOf",
+        &query
+    )
+        .parse_return_value()
+        .expect("Parse staked balances");
+
+    // Only the active stake counts, not the unbonding funds.
+    assert_eq!(response.0, vec![
+        TokenAmountU64(75_000_000),
+        TokenAmountU64(0),
+        TokenAmountU64(0)
+    ]);
+}
+
+/// Test that receipt tokens are minted on stake, burned on unstake, and that
+/// transferring them moves the stake to the receiver.
+#[test]
+fn test_receipt_token() {
+    let Setup { mut chain, token, staking } = initialize();
+
+    // Staking mints receipt tokens.
+    mint(&mut chain, token, ALICE_ADDR, 100_000_000);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(100_000_000),
+        from: ALICE_ADDR,
+        to: Receiver::Contract(staking, OwnedEntrypointName::new_unchecked("stake".into())),
+        data: AdditionalData::empty(),
+    };
+    let update = update_contract(
+        &mut chain,
+        ALICE,
+        token,
+        "transfer",
+        &TransferParams::from(vec![transfer])
+    ).expect("Stake");
+    assert!(contract_events(&update, staking).iter().any(|e| matches!(e, Event::ReceiptMint(_))));
+    assert_eq!(balance_of(&chain, staking, ALICE_ADDR), 100_000_000);
+
+    // Unstaking burns them.
+    let update = update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(20_000_000),
+    }).expect("Unstake");
+    assert!(contract_events(&update, staking).iter().any(|e| matches!(e, Event::ReceiptBurn(_))));
+    assert_eq!(balance_of(&chain, staking, ALICE_ADDR), 80_000_000);
+
+    // Transferring them moves the stake to Bob.
+    let update = update_contract(
+        &mut chain,
+        ALICE,
+        staking,
+        "transfer",
+        &TransferParams::from(vec![receipt_transfer(ALICE, BOB, 30_000_000)])
+    ).expect("Transfer receipt tokens");
+    assert!(
+        contract_events(&update, staking).iter().any(|e| matches!(e, Event::ReceiptTransfer(_)))
+    );
+    assert_eq!(balance_of(&chain, staking, ALICE_ADDR), 50_000_000);
+    assert_eq!(balance_of(&chain, staking, BOB_ADDR), 30_000_000);
+    assert_eq!(stake_info(&chain, staking, BOB).earning_amount, 30_000_000);
+    let view: ViewResult = invoke(&chain, staking, "view", &())
+        .parse_return_value()
+        .expect("Parse view");
+    assert_eq!(view.total_staked, 80_000_000);
+
+    // Bob can only move Alice's tokens as her operator.
+    let params = TransferParams::from(vec![receipt_transfer(ALICE, BOB, 10_000_000)]);
+    update_contract(&mut chain, BOB, staking, "transfer", &params).expect_err(
+        "Transfer without being an operator"
+    );
+    let operator = UpdateOperatorParams(
+        vec![UpdateOperator {
+            update: OperatorUpdate::Add,
+            operator: BOB_ADDR,
+        }]
+    );
+    update_contract(&mut chain, ALICE, staking, "updateOperator", &operator).expect(
+        "Add Bob as operator"
+    );
+    update_contract(&mut chain, BOB, staking, "transfer", &params).expect(
+        "Transfer as an operator"
+    );
+    assert_eq!(balance_of(&chain, staking, ALICE_ADDR), 40_000_000);
+    assert_eq!(balance_of(&chain, staking, BOB_ADDR), 40_000_000);
+
+    // Bob's stake earns rewards like any other.
+    chain.tick_block_time(Duration::from_days(30)).expect("Advance time");
+    let earned: u64 = invoke(&chain, staking, "getEarnedRewards", &BOB)
+        .parse_return_value()
+        .expect("Parse earned rewards");
+    assert!(earned > 0);
+}
+
+/// Test that the admin can change the token address of an empty contract to
+/// a CIS-2 token, but not while stakes are active.
+#[test]
+fn test_set_token_address() {
+    let Setup { mut chain, token, staking } = initialize();
+
+    // Deploy a second instance of the token to migrate to.
+    let mod_ref = chain.get_contract(token).expect("Token exists").module_reference;
+    let new_token = chain
+        .contract_init(SIGNER, ADMIN, ENERGY, InitContractPayload {
+            amount: Amount::zero(),
+            mod_ref,
+            init_name: OwnedContractName::new_unchecked("init_euroe_mock".to_string()),
+            param: OwnedParameter::empty(),
+        })
+        .expect("Initializing new token")
+        .contract_address;
+
+    // The stand-in for the first module does not implement CIS-2.
+    let non_cis2 = init_module(
+        &mut chain,
+        "tests/staking-v0/Cargo.toml",
+        "init_concordium_staking",
+        OwnedParameter::from_serial(&InitV0Params {
+            admin: ADMIN,
+            token_address: token,
+            unbonding_period: UNBONDING_PERIOD,
+            slashing_rate: 0,
+            apr: 0,
+            rewards_pool: TokenAmountU64(0),
+            stakes: Vec::new(),
+            nonces: Vec::new(),
+        }).expect("Parameter within size bounds")
+    );
+
+    // Only the admin can change the token address.
+    update_contract(&mut chain, ALICE, staking, "setTokenAddress", &new_token).expect_err(
+        "Change the token address as non-admin"
+    );
+
+    // Contracts that do not support CIS-2 are rejected.
+    let update = update_contract(&mut chain, ADMIN, staking, "setTokenAddress", &non_cis2)
+        .expect_err("Change the token address to a non-CIS-2 contract");
+    assert_eq!(contract_error(&update), Error::InvalidTokenContract);
+
+    // The change is allowed while the contract is empty.
+    let update = update_contract(&mut chain, ADMIN, staking, "setTokenAddress", &new_token).expect(
+        "Change the token address of an empty contract"
+    );
+    assert!(
+        contract_events(&update, staking)
+            .iter()
+            .any(|e| matches!(e, Event::TokenAddressUpdated(_)))
+    );
+    let view: ViewResult = invoke(&chain, staking, "view", &())
+        .parse_return_value()
+        .expect("Parse view");
+    assert_eq!(view.token_address, new_token);
+
+    // Once Alice stakes the new token, the address can no longer change.
+    mint(&mut chain, new_token, ALICE_ADDR, 100_000_000);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(100_000_000),
+        from: ALICE_ADDR,
+        to: Receiver::Contract(staking, OwnedEntrypointName::new_unchecked("stake".into())),
+        data: AdditionalData::empty(),
+    };
+    update_contract(
+        &mut chain,
+        ALICE,
+        new_token,
+        "transfer",
+        &TransferParams::from(vec![transfer])
+    ).expect("Stake");
+    let update = update_contract(&mut chain, ADMIN, staking, "setTokenAddress", &token)
+        .expect_err("Change the token address with active stakes");
+    assert_eq!(contract_error(&update), Error::TokenFundsOutstanding);
+}
+
+/// Test that `withdrawExcessRewards` keeps the rewards accrued by all stakers
+/// in the pool, across stakes with the global and a locked APR.
+#[test]
+fn test_withdraw_excess_rewards() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ADMIN, staking, "setAprLocking", &SetAprLockingParams {
+        apr_locking: true,
+    }).expect("Lock APRs");
+    stake(&mut chain, token, staking, BOB, 50_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(100)).expect("Advance time");
+
+    // Alice's claim moves part of the owed rewards out of the pool.
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect("Claim rewards");
+    chain.tick_block_time(Duration::from_days(100)).expect("Advance time");
+
+    let owed = earned_rewards(&chain, staking, ALICE) + earned_rewards(&chain, staking, BOB);
+    let pool = view(&chain, staking).rewards_pool;
+
+    // Withdrawing into the owed rewards is rejected.
+    let update = update_contract(
+        &mut chain,
+        ADMIN,
+        staking,
+        "withdrawExcessRewards",
+        &TokenAmountU64(pool - owed + 1)
+    ).expect_err("Withdraw owed rewards");
+    assert_eq!(contract_error(&update), Error::WouldUndercollateralize);
+
+    // The excess can be withdrawn, up to a unit per position kept for
+    // rounding and a unit for the rewards accrued since.
+    update_contract(
+        &mut chain,
+        ADMIN,
+        staking,
+        "withdrawExcessRewards",
+        &TokenAmountU64(pool - owed - 3)
+    ).expect("Withdraw excess rewards");
+    assert_eq!(balance_of(&chain, token, ADMIN_ADDR), pool - owed - 3);
+}
+
+/// Test that `withdrawEuroe` pays out the surplus over the staked, unbonding
+/// and reward funds, but nothing backing them.
+#[test]
+fn test_withdraw_euroe_surplus() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 1_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(40_000_000),
+    }).expect("Unstake");
+
+    // Tokens sent to the contract outside of staking and funding are surplus.
+    mint(&mut chain, token, Address::Contract(staking), 1_000);
+
+    // Withdrawing into the funds backing stakes and rewards is rejected.
+    let update = update_contract(&mut chain, ADMIN, staking, "withdrawEuroe", &WithdrawEuroEParams {
+        withdraw_address: ADMIN,
+        amount: TokenAmountU64(1_001),
+    }).expect_err("Withdraw backing funds");
+    assert_eq!(contract_error(&update), Error::InsufficientFunds);
+
+    // The surplus can be withdrawn.
+    update_contract(&mut chain, ADMIN, staking, "withdrawEuroe", &WithdrawEuroEParams {
+        withdraw_address: ADMIN,
+        amount: TokenAmountU64(1_000),
+    }).expect("Withdraw surplus");
+    assert_eq!(balance_of(&chain, token, ADMIN_ADDR), 1_000);
+    assert_eq!(balance_of(&chain, token, Address::Contract(staking)), 101_000_000);
+}
+
+/// Test that a fully slashed staker cannot stake again, so no deposit is
+/// stuck in a slashed stake.
+#[test]
+fn test_stake_after_full_slash() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ADMIN, staking, "slash", &SlashParams {
+        staker: ALICE,
+        amount: TokenAmountU64(100_000_000),
+        reason_code: 1,
+    }).expect("Slash");
+
+    let update = stake(&mut chain, token, staking, ALICE, 50_000_000, AdditionalData::empty())
+        .expect_err("Stake after slash");
+    assert_eq!(hook_error(&update, staking), Error::AlreadySlashed);
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 50_000_000);
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.amount, 0);
+}
+
+/// Test that receipt tokens cannot be transferred to a slashed staker, whose
+/// stake could never be withdrawn.
+#[test]
+fn test_receipt_transfer_to_slashed() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 50_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ADMIN, staking, "slash", &SlashParams {
+        staker: BOB,
+        amount: TokenAmountU64(50_000_000),
+        reason_code: 1,
+    }).expect("Slash");
+
+    let update = update_contract(
+        &mut chain,
+        ALICE,
+        staking,
+        "transfer",
+        &TransferParams::from(vec![receipt_transfer(ALICE, BOB, 30_000_000)])
+    ).expect_err("Transfer to a slashed staker");
+    assert_eq!(contract_error(&update), Error::AlreadySlashed);
+    assert_eq!(balance_of(&chain, staking, ALICE_ADDR), 100_000_000);
+    assert_eq!(stake_info(&chain, staking, BOB).stake.amount, 0);
+}
+
+/// Test that many small receipt transfers do not grow the positions of the
+/// receiver, so they cannot make the receiver's stake too costly to use.
+#[test]
+fn test_receipt_transfers_merge_positions() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 50_000_000, AdditionalData::empty()).expect("Stake");
+
+    for _ in 0..40 {
+        chain.tick_block_time(Duration::from_seconds(1)).expect("Advance time");
+        update_contract(
+            &mut chain,
+            ALICE,
+            staking,
+            "transfer",
+            &TransferParams::from(vec![receipt_transfer(ALICE, BOB, 1)])
+        ).expect("Transfer receipt tokens");
+    }
+    let bob = stake_info(&chain, staking, BOB).stake;
+    assert_eq!(bob.positions.len(), 1);
+    assert_eq!(bob.amount, 50_000_040);
+
+    // Bob can still unstake all of it.
+    update_contract(&mut chain, BOB, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(50_000_040),
+    }).expect("Unstake");
+}
+
+/// Test that a slash reduces the funds unbonding at that time by the slashing
+/// rate, whether or not the whole stake is slashed, and that slashed stakers
+/// can still complete their unbonding.
+#[test]
+fn test_complete_unstake_after_slash() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 100_000_000, AdditionalData::empty()).expect("Stake");
+    for account in [ALICE, BOB] {
+        update_contract(&mut chain, account, staking, "unstake", &UnstakeParams {
+            amount: TokenAmountU64(40_000_000),
+        }).expect("Unstake");
+    }
+
+    // Slashing all of Alice's active stake marks her as slashed.
+    update_contract(&mut chain, ADMIN, staking, "slash", &SlashParams {
+        staker: ALICE,
+        amount: TokenAmountU64(60_000_000),
+        reason_code: 1,
+    }).expect("Slash Alice");
+    assert!(stake_info(&chain, staking, ALICE).stake.slashed);
+    assert_eq!(stake_info(&chain, staking, ALICE).unbonding_amount, 36_000_000);
+
+    // Slashing part of Bob's stake reduces his unbonding funds alike, but not
+    // what he unstakes afterwards.
+    update_contract(&mut chain, ADMIN, staking, "slash", &SlashParams {
+        staker: BOB,
+        amount: TokenAmountU64(10_000_000),
+        reason_code: 1,
+    }).expect("Slash Bob");
+    assert!(!stake_info(&chain, staking, BOB).stake.slashed);
+    update_contract(&mut chain, BOB, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(50_000_000),
+    }).expect("Unstake after slash");
+    assert_eq!(view(&chain, staking).total_unbonding, 36_000_000 + 36_000_000 + 50_000_000);
+
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    for account in [ALICE, BOB] {
+        update_contract(&mut chain, account, staking, "completeUnstake", &CompleteUnstakeParams {
+            index: None,
+        }).expect("Complete unstake");
+    }
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 36_000_000);
+    assert_eq!(balance_of(&chain, token, BOB_ADDR), 86_000_000);
+    assert_eq!(view(&chain, staking).total_unbonding, 0);
+
+    // Alice stays slashed once her last entry is paid out.
+    assert!(stake_info(&chain, staking, ALICE).stake.slashed);
+    let update = stake(&mut chain, token, staking, ALICE, 1_000_000, AdditionalData::empty())
+        .expect_err("Stake after completing as a slashed staker");
+    assert_eq!(hook_error(&update, staking), Error::AlreadySlashed);
+}
+
+/// Test that a failing token transfer leaves the staking contract unchanged:
+/// state is updated before the transfer and the rejection reverts it.
+#[test]
+fn test_failing_token_transfer() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(40_000_000),
+    }).expect("Unstake");
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+
+    update_contract(&mut chain, ADMIN, token, "setTransfersFailing", &true)
+        .expect("Fail transfers");
+    let before = snapshot(&chain, staking, ALICE);
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect_err("Claim with a failing transfer");
+    assert_eq!(contract_error(&update), Error::InvokeContractError);
+    let update = update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect_err("Complete unstake with a failing transfer");
+    assert_eq!(contract_error(&update), Error::InvokeContractError);
+    let update = update_contract(&mut chain, ADMIN, staking, "withdrawExcessRewards", &TokenAmountU64(1))
+        .expect_err("Withdraw rewards with a failing transfer");
+    assert_eq!(contract_error(&update), Error::InvokeContractError);
+    assert_eq!(snapshot(&chain, staking, ALICE), before);
+
+    // Once transfers work again, the same calls succeed.
+    update_contract(&mut chain, ADMIN, token, "setTransfersFailing", &false)
+        .expect("Restore transfers");
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect("Claim rewards");
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect("Complete unstake");
+    assert!(balance_of(&chain, token, ALICE_ADDR) > 40_000_000);
+}
+
+/// Test that unstaking with a rewards pool too small for the accrued rewards
+/// moves only the principal to unbonding and leaves the rewards pending.
+#[test]
+fn test_unstake_with_small_rewards_pool() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 1_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(30)).expect("Advance time");
+    let earned = earned_rewards(&chain, staking, ALICE);
+    assert!(earned > 1_000);
+
+    let unbonding: UnbondingInfo = update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(100_000_000),
+    })
+    .expect("Unstake")
+    .parse_return_value()
+    .expect("Parse unbonding entry");
+    assert_eq!(unbonding.amount, TokenAmountU64(100_000_000));
+    assert_eq!(earned_rewards(&chain, staking, ALICE), earned);
+    let view = view(&chain, staking);
+    assert_eq!(view.rewards_pool, 1_000);
+    assert_eq!(view.total_rewards_paid, 0);
+
+    // The rewards can only be claimed once the pool covers them.
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect_err("Claim from a small pool");
+    assert_eq!(contract_error(&update), Error::InsufficientRewardsPool);
+
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect("Complete unstake");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 100_000_000);
+}
+
+/// Test that `view` reports the time of the last state change, which
+/// advances with a stake and a claim but not with a rejected call.
+#[test]
+fn test_last_mutation_time() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    let now = |chain: &Chain| chain.block_time().millis / 1000;
+
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    assert_eq!(view(&chain, staking).last_mutation_time, now(&chain));
+    let staked_time = now(&chain);
+
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    update_contract(&mut chain, BOB, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect_err("Claim without a stake");
+    assert_eq!(view(&chain, staking).last_mutation_time, staked_time);
+
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect("Claim rewards");
+    assert_eq!(view(&chain, staking).last_mutation_time, now(&chain));
+    assert!(now(&chain) > staked_time);
+}
+
+/// Test that daily claims pay the same as one claim at the end within a
+/// token unit, whether rewards are rounded down or to the nearest unit, and
+/// that changing the rounding is logged.
+#[test]
+fn test_reward_rounding_payouts() {
+    for rounding in [RoundingMode::Floor, RoundingMode::Nearest] {
+        let Setup { mut chain, token, staking } = initialize();
+        fund_rewards(&mut chain, token, staking, 10_000_000);
+        let update = update_contract(&mut chain, ADMIN, staking, "setRewardRounding", &rounding)
+            .expect("Set reward rounding");
+        assert!(
+            contract_events(&update, staking)
+                .iter()
+                .any(|e| matches!(e, Event::RewardRoundingUpdated(_)))
+        );
+
+        // Alice claims every day, Bob once at the end.
+        stake(&mut chain, token, staking, ALICE, 1_000_003, AdditionalData::empty()).expect("Stake");
+        stake(&mut chain, token, staking, BOB, 1_000_003, AdditionalData::empty()).expect("Stake");
+        for _ in 0..30 {
+            chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+            update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+                amount: None,
+                recipient: None,
+            }).expect("Claim rewards");
+        }
+        update_contract(&mut chain, BOB, staking, "claimRewards", &ClaimRewardsParams {
+            amount: None,
+            recipient: None,
+        }).expect("Claim rewards");
+
+        let alice = balance_of(&chain, token, ALICE_ADDR);
+        let bob = balance_of(&chain, token, BOB_ADDR);
+        assert!(alice.abs_diff(bob) <= 1, "{rounding:?}: {alice} and {bob}");
+    }
+}
+
+/// Test that the CIS-2 events of the receipt token are logged in their
+/// standard format, without a sequence number.
+#[test]
+fn test_standard_events() {
+    let Setup { mut chain, token, staking } = initialize();
+    let update = stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty())
+        .expect("Stake");
+    let minted: Vec<Cis2Event<ContractTokenId, TokenAmountU64>> = logged_events(&update, staking)
+        .filter_map(|event| event.parse().ok())
+        .collect();
+    assert_eq!(minted, vec![Cis2Event::Mint(MintEvent {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(100_000_000),
+        owner: ALICE_ADDR,
+    })]);
+
+    let update = update_contract(
+        &mut chain,
+        ALICE,
+        staking,
+        "transfer",
+        &TransferParams::from(vec![receipt_transfer(ALICE, BOB, 30_000_000)])
+    ).expect("Transfer receipt tokens");
+    let transferred: Vec<Cis2Event<ContractTokenId, TokenAmountU64>> = logged_events(
+        &update,
+        staking
+    )
+        .map(|event| event.parse().expect("Parse CIS-2 event"))
+        .collect();
+    assert_eq!(transferred, vec![Cis2Event::Transfer(TransferEvent {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(30_000_000),
+        from: ALICE_ADDR,
+        to: BOB_ADDR,
+    })]);
+}
+
+/// Test that a `withdrawEuroe` permit is executed only with a valid signature
+/// of the admin.
+#[test]
+fn test_permit_admin_signatures() {
+    let Setup { mut chain, token, staking } = initialize();
+    mint(&mut chain, token, Address::Contract(staking), 1_000);
+    let payload = WithdrawEuroEParams {
+        withdraw_address: ADMIN,
+        amount: TokenAmountU64(1_000),
+    };
+
+    // A message of the admin signed with another key is rejected.
+    let mut param = permit(&chain, staking, ADMIN, "withdrawEuroe", &payload);
+    param.signature = sign(ALICE, &view_message_hash(&chain, staking, &param));
+    let update = update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect_err("Permit with a wrong signature");
+    assert_eq!(contract_error(&update), Error::WrongSignature);
+
+    // A signed message of a non-admin is rejected by the entrypoint.
+    let param = permit(&chain, staking, ALICE, "withdrawEuroe", &payload);
+    let update = update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect_err("Permit of a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+
+    // The signed message of the admin is executed for anyone submitting it.
+    let param = permit(&chain, staking, ADMIN, "withdrawEuroe", &payload);
+    update_contract(&mut chain, BOB, staking, "permit", &param).expect("Permit of the admin");
+    assert_eq!(balance_of(&chain, token, ADMIN_ADDR), 1_000);
+    assert_eq!(user_nonce(&chain, staking, ADMIN), 1);
+}
+
+/// Test that `permitUpgrade` upgrades only with a valid signature of the
+/// admin on an `upgrade` message.
+#[test]
+fn test_permit_upgrade() {
+    let Setup { mut chain, staking, .. } = initialize();
+    let payload = UpgradeParams {
+        module: chain.get_contract(staking).expect("Contract exists").module_reference,
+        migrate: None,
+    };
+
+    // A message of the admin signed with another key is rejected.
+    let mut param = permit(&chain, staking, ADMIN, "upgrade", &payload);
+    param.signature = sign(ALICE, &view_message_hash(&chain, staking, &param));
+    let update = update_contract(&mut chain, BOB, staking, "permitUpgrade", &param)
+        .expect_err("Upgrade with a wrong signature");
+    assert_eq!(contract_error(&update), Error::WrongSignature);
+
+    // Only the admin can sign upgrades.
+    let param = permit(&chain, staking, ALICE, "upgrade", &payload);
+    let update = update_contract(&mut chain, BOB, staking, "permitUpgrade", &param)
+        .expect_err("Upgrade signed by a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+
+    // Messages for other entrypoints cannot upgrade.
+    let param = permit(&chain, staking, ADMIN, "withdrawEuroe", &payload);
+    let update = update_contract(&mut chain, BOB, staking, "permitUpgrade", &param)
+        .expect_err("Upgrade with a message for another entrypoint");
+    assert_eq!(contract_error(&update), Error::WrongEntryPoint);
+
+    let param = permit(&chain, staking, ADMIN, "upgrade", &payload);
+    let update = update_contract(&mut chain, BOB, staking, "permitUpgrade", &param)
+        .expect("Upgrade signed by the admin");
+    assert!(update.effective_trace_elements().any(|element| matches!(
+        element,
+        ContractTraceElement::Upgraded { address, .. } if *address == staking
+    )));
+    assert_eq!(user_nonce(&chain, staking, ADMIN), 1);
+}
+
+/// Test upgrading a contract of the first module and migrating its state:
+/// the stakes keep their funds and accrued rewards, and the totals the first
+/// module did not track are rebuilt.
+#[test]
+fn test_upgrade_and_migrate() {
+    let mut chain = Chain::new();
+    for address in [ADMIN, ALICE, BOB] {
+        chain.create_account(account(address));
+    }
+    let token = init_module(
+        &mut chain,
+        "tests/euroe-mock/Cargo.toml",
+        "init_euroe_mock",
+        OwnedParameter::empty()
+    );
+    let params = InitV0Params {
+        admin: ADMIN,
+        token_address: token,
+        unbonding_period: UNBONDING_PERIOD,
+        slashing_rate: 1_000,
+        apr: 1_000,
+        rewards_pool: TokenAmountU64(20_000_000),
+        stakes: vec![
+            (ALICE, StakeInfoV0 {
+                amount: 100_000_000,
+                timestamp: 0,
+                unbonding: Vec::new(),
+                slashed: false,
+                pending_rewards: 5,
+            }),
+            (BOB, StakeInfoV0 {
+                amount: 0,
+                timestamp: 0,
+                unbonding: vec![UnbondingInfo {
+                    amount: TokenAmountU64(40_000_000),
+                    unlock_time: UNBONDING_PERIOD,
+                }],
+                slashed: false,
+                pending_rewards: 0,
+            })
+        ],
+        nonces: vec![(ALICE, 3)],
+    };
+    let staking = init_module(
+        &mut chain,
+        "tests/staking-v0/Cargo.toml",
+        "init_concordium_staking",
+        OwnedParameter::from_serial(&params).expect("Parameter within size bounds")
+    );
+    mint(&mut chain, token, Address::Contract(staking), 160_000_000);
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+
+    let module = deploy_module(&mut chain, "Cargo.toml");
+    update_contract(&mut chain, ADMIN, staking, "upgrade", &UpgradeParams {
+        module,
+        migrate: Some((
+            OwnedEntrypointName::new_unchecked("migrate".to_string()),
+            OwnedParameter::empty(),
+        )),
+    }).expect("Upgrade and migrate");
+
+    // The rewards accrued in the first module are kept.
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.amount, 100_000_000);
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 10_000_005);
+    assert_eq!(user_nonce(&chain, staking, ALICE), 3);
+    let view = view(&chain, staking);
+    assert_eq!(view.total_staked, 100_000_000);
+    assert_eq!(view.total_participants, 2);
+    assert_eq!(view.total_unbonding, 40_000_000);
+    assert_eq!(view.rewards_pool, 20_000_000);
+    assert_eq!(view.apr, 1_000);
+
+    // The rebuilt reward liability keeps the owed rewards in the pool.
+    let update = update_contract(
+        &mut chain,
+        ADMIN,
+        staking,
+        "withdrawExcessRewards",
+        &TokenAmountU64(20_000_000 - 10_000_005 + 1)
+    ).expect_err("Withdraw owed rewards");
+    assert_eq!(contract_error(&update), Error::WouldUndercollateralize);
+
+    // The migrated stakes can be used.
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect("Claim rewards");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 10_000_005);
+    update_contract(&mut chain, BOB, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect("Complete unstake");
+    assert_eq!(balance_of(&chain, token, BOB_ADDR), 40_000_000);
+
+    // Only the contract itself can migrate.
+    let update = update_contract(&mut chain, ADMIN, staking, "migrate", &())
+        .expect_err("Migrate as an account");
+    assert_eq!(contract_error(&update), Error::OnlySelf);
+}
+
+/// Test that a staker is counted once while funds are unbonding, and again
+/// after re-staking once everything was withdrawn.
+#[test]
+fn test_participant_count_on_restake() {
+    let Setup { mut chain, token, staking } = initialize();
+    let unstake_all = UnstakeParams {
+        amount: TokenAmountU64(100_000_000),
+    };
+
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    assert_eq!(view(&chain, staking).total_participants, 1);
+
+    // A full unstake keeps Alice counted while her funds are unbonding.
+    update_contract(&mut chain, ALICE, staking, "unstake", &unstake_all).expect("Unstake");
+    assert_eq!(view(&chain, staking).total_participants, 1);
+
+    // Re-staking during unbonding does not count her twice.
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    assert_eq!(view(&chain, staking).total_participants, 1);
+
+    // Once everything is withdrawn she is no longer counted.
+    update_contract(&mut chain, ALICE, staking, "unstake", &unstake_all).expect("Unstake");
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect("Complete unstake");
+    assert_eq!(view(&chain, staking).total_participants, 0);
+
+    // Staking again counts her as a new participant.
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    assert_eq!(view(&chain, staking).total_participants, 1);
+}
+
+/// Test that `getRewardsValue` values the accrued rewards at the price of the
+/// oracle, and falls back to the raw amount while the oracle fails.
+#[test]
+fn test_rewards_value() {
+    let Setup { mut chain, token, staking } = initialize();
+    let oracle = init_module(
+        &mut chain,
+        "tests/oracle-mock/Cargo.toml",
+        "init_oracle_mock",
+        OwnedParameter::empty()
+    );
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let rewards = earned_rewards(&chain, staking, ALICE);
+    let rewards_value = |chain: &Chain| -> RewardsValueResult {
+        invoke(chain, staking, "getRewardsValue", &ALICE)
+            .parse_return_value()
+            .expect("Parse rewards value")
+    };
+
+    // Without an oracle no price is available.
+    let value = rewards_value(&chain);
+    assert_eq!(value.rewards, TokenAmountU64(rewards));
+    assert!(!value.price_available);
+
+    // The rewards are valued at the oracle price, with six decimals.
+    update_contract(&mut chain, ADMIN, staking, "setPriceOracle", &SetPriceOracleParams {
+        price_oracle: Some(oracle),
+    }).expect("Set price oracle");
+    update_contract(&mut chain, ADMIN, oracle, "setPrice", &Some(1_500_000u64))
+        .expect("Set price");
+    let value = rewards_value(&chain);
+    assert_eq!(value.rewards, TokenAmountU64(rewards));
+    assert_eq!(value.value, rewards * 3 / 2);
+    assert!(value.price_available);
+
+    // A failing oracle falls back to the raw amount.
+    update_contract(&mut chain, ADMIN, oracle, "setPrice", &None::<u64>).expect("Clear price");
+    let value = rewards_value(&chain);
+    assert_eq!(value.rewards, TokenAmountU64(rewards));
+    assert_eq!(value.value, 0);
+    assert!(!value.price_available);
+}
+
+/// Test that a stake made with APR locking keeps its rate when the global APR
+/// changes, and that a top-up locks the weighted average rate.
+#[test]
+fn test_apr_locking() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 100_000_000);
+
+    // Bob stakes at the global APR, Alice locks in the initial APR of 139.
+    stake(&mut chain, token, staking, BOB, 100_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ADMIN, staking, "setAprLocking", &SetAprLockingParams {
+        apr_locking: true,
+    }).expect("Lock APRs");
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.locked_apr, Some(139));
+
+    update_contract(&mut chain, ADMIN, staking, "updateApr", &UpdateAprParams {
+        new_apr: 1_000,
+    }).expect("Update APR");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 1_390_000);
+    assert_eq!(earned_rewards(&chain, staking, BOB), 10_000_000);
+
+    // Adding an equal amount at an APR of 1000 locks the average, rounded down.
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.locked_apr, Some(569));
+}
+
+/// Test that a batch of more than `MAX_BATCH_SIZE` entries is rejected.
+#[test]
+fn test_batch_too_large() {
+    let Setup { chain, staking, .. } = initialize();
+    let batch = |len: usize| BatchGetStakeInfoParams {
+        accounts: vec![ALICE; len],
+    };
+    let payload = |len: usize| UpdateContractPayload {
+        amount: Amount::zero(),
+        address: staking,
+        receive_name: receive_name(&chain, staking, "batchGetStakeInfo"),
+        message: OwnedParameter::from_serial(&batch(len)).expect("Parameter within size bounds"),
+    };
+
+    chain.contract_invoke(ADMIN, ADMIN_ADDR, ENERGY, payload(50)).expect("Query a full batch");
+    let invoke = chain
+        .contract_invoke(ADMIN, ADMIN_ADDR, ENERGY, payload(51))
+        .expect_err("Query an over-limit batch");
+    assert_eq!(contract_error(&invoke), Error::BatchTooLarge);
+}
+
+/// Test that `verifyTokenIntegration` accepts the CIS-2 mock token and
+/// rejects a token address of a contract without CIS-2 support.
+#[test]
+fn test_verify_token_integration() {
+    let Setup { mut chain, staking, .. } = initialize();
+    let verify = |chain: &Chain, staking: ContractAddress| -> bool {
+        invoke(chain, staking, "verifyTokenIntegration", &())
+            .parse_return_value()
+            .expect("Parse token integration")
+    };
+    assert!(verify(&chain, staking));
+
+    // The oracle mock does not implement CIS-2.
+    let oracle = init_module(
+        &mut chain,
+        "tests/oracle-mock/Cargo.toml",
+        "init_oracle_mock",
+        OwnedParameter::empty()
+    );
+    let params = InitContractParams {
+        admin: ADMIN,
+        token_address: oracle,
+        unbonding_period: UNBONDING_PERIOD,
+        slashing_rate: 1_000,
+    };
+    let misconfigured = init_staking(&mut chain, staking, &params).expect("Initializing contract");
+    assert!(!verify(&chain, misconfigured));
+}
+
+/// Test that `claimRewards` and a `claimRewards` permit return the amount
+/// logged in the claim event.
+#[test]
+fn test_claim_return_value() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    let claim = ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    };
+    let claimed_event = |update: &ContractInvokeSuccess| {
+        contract_events(update, staking)
+            .into_iter()
+            .find_map(|event| match event {
+                Event::Claimed(event) => Some(event.rewards_claimed),
+                _ => None,
+            })
+            .expect("Claim event")
+    };
+
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &claim)
+        .expect("Claim rewards");
+    let claimed: TokenAmountU64 = update.parse_return_value().expect("Parse claimed rewards");
+    assert!(claimed.0 > 0);
+    assert_eq!(claimed, claimed_event(&update));
+
+    chain.tick_block_time(Duration::from_days(30)).expect("Advance time");
+    let param = permit(&chain, staking, ALICE, "claimRewards", &claim);
+    let update = update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect("Claim rewards with a permit");
+    let permit_claimed: Option<TokenAmountU64> = update
+        .parse_return_value()
+        .expect("Parse claimed rewards");
+    assert_eq!(permit_claimed, Some(claimed_event(&update)));
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), claimed.0 + claimed_event(&update).0);
+}
+
+/// Test that the contract cannot be initialized with the zero account as the
+/// admin, and that it cannot be proposed as the next admin.
+#[test]
+fn test_invalid_admin() {
+    let Setup { mut chain, staking, token } = initialize();
+    let zero = AccountAddress([0u8; 32]);
+
+    let params = InitContractParams {
+        admin: zero,
+        token_address: token,
+        unbonding_period: UNBONDING_PERIOD,
+        slashing_rate: 1_000,
+    };
+    let init = init_staking(&mut chain, staking, &params)
+        .expect_err("Initialize with the zero admin");
+    assert_eq!(init_reject_reason(init), reject_reason(Error::InvalidAdmin));
+
+    let update = update_contract(&mut chain, ADMIN, staking, "proposeAdmin", &zero)
+        .expect_err("Propose the zero admin");
+    assert_eq!(contract_error(&update), Error::InvalidAdmin);
+}
+
+/// Test that `getUserShare` reports the share of each staker of the total
+/// stake in basis points.
+#[test]
+fn test_user_share() {
+    let Setup { mut chain, token, staking } = initialize();
+    let share = |chain: &Chain, account: AccountAddress| -> u64 {
+        invoke(chain, staking, "getUserShare", &account)
+            .parse_return_value()
+            .expect("Parse user share")
+    };
+
+    // Nothing is staked yet.
+    assert_eq!(share(&chain, ALICE), 0);
+
+    stake(&mut chain, token, staking, ALICE, 300_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 100_000_000, AdditionalData::empty()).expect("Stake");
+    assert_eq!(share(&chain, ALICE), 7_500);
+    assert_eq!(share(&chain, BOB), 2_500);
+    assert_eq!(share(&chain, ADMIN), 0);
+}
+
+/// Test that with an epoch configuration funds unstaked mid-epoch unlock at
+/// an epoch boundary after the minimum number of full epochs.
+#[test]
+fn test_epoch_unbonding() {
+    let Setup { mut chain, token, staking } = initialize();
+    let epoch_length = 24 * 60 * 60;
+    update_contract(&mut chain, ADMIN, staking, "setEpochConfig", &SetEpochConfigParams {
+        epoch_config: Some(EpochConfig {
+            epoch_length,
+            genesis_time: 3_600,
+            min_unbonding_epochs: 2,
+        }),
+    }).expect("Set epoch config");
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+
+    // Unstake halfway through epoch 2, so the funds unlock at the start of
+    // epoch 5.
+    let unstake_time = 3_600 + 5 * epoch_length / 2;
+    chain.tick_block_time(Duration::from_seconds(unstake_time)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(100_000_000),
+    }).expect("Unstake");
+    let unlock_time = 3_600 + 5 * epoch_length;
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.unbonding[0].unlock_time, unlock_time);
+
+    // The funds cannot be withdrawn before the boundary.
+    chain.tick_block_time(Duration::from_seconds(unlock_time - unstake_time - 1))
+        .expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect_err("Complete unstake before the epoch boundary");
+
+    chain.tick_block_time(Duration::from_seconds(1)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect("Complete unstake at the epoch boundary");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 100_000_000);
+}
+
+/// Test that a relayer can complete the unstake of a signer with a
+/// `completeUnstake` permit with an empty payload.
+#[test]
+fn test_permit_complete_unstake() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(100_000_000),
+    }).expect("Unstake");
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+
+    let param = permit(&chain, staking, ALICE, "completeUnstake", &());
+    let update = update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect("Complete unstake with a permit");
+    assert!(
+        contract_events(&update, staking).iter().any(|e| matches!(e, Event::UnstakeCompleted(_)))
+    );
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 100_000_000);
+    assert_eq!(balance_of(&chain, token, BOB_ADDR), 0);
+}
+
+/// Test that transfers of the entire token balance of the contract succeed,
+/// for `completeUnstake`, `claimRewards` and `withdrawEuroe`.
+#[test]
+fn test_transfer_entire_balance() {
+    let Setup { mut chain, token, staking } = initialize();
+    let contract_balance = |chain: &Chain| balance_of(chain, token, Address::Contract(staking));
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+
+    // Fund exactly the accrued rewards, then unstake everything.
+    let earned = earned_rewards(&chain, staking, ALICE);
+    fund_rewards(&mut chain, token, staking, earned);
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(100_000_000),
+    }).expect("Unstake");
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+
+    // Completing leaves exactly the rewards, and claiming them empties the
+    // contract.
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect("Complete unstake");
+    assert_eq!(contract_balance(&chain), earned);
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect("Claim the entire pool");
+    assert_eq!(contract_balance(&chain), 0);
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 100_000_000 + earned);
+
+    // A surplus equal to the whole balance can be withdrawn at once.
+    mint(&mut chain, token, Address::Contract(staking), 1_000);
+    update_contract(&mut chain, ADMIN, staking, "withdrawEuroe", &WithdrawEuroEParams {
+        withdraw_address: ADMIN,
+        amount: TokenAmountU64(1_000),
+    }).expect("Withdraw the entire balance");
+    assert_eq!(contract_balance(&chain), 0);
+}
+
+/// Test that a slash moves the slashed stake out of the total stake into the
+/// rewards pool.
+#[test]
+fn test_slash_totals() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 1_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 50_000_000, AdditionalData::empty()).expect("Stake");
+    let before = view(&chain, staking);
+    assert_eq!(before.total_staked, 150_000_000);
+    assert_eq!(before.rewards_pool, 1_000_000);
+
+    let update = update_contract(&mut chain, ADMIN, staking, "slash", &SlashParams {
+        staker: ALICE,
+        amount: TokenAmountU64(10_000_000),
+        reason_code: 1,
+    }).expect("Slash");
+    assert!(contract_events(&update, staking).iter().any(|e| matches!(e, Event::Slashed(_))));
+
+    let after = view(&chain, staking);
+    assert_eq!(after.total_staked, 140_000_000);
+    assert_eq!(after.rewards_pool, 11_000_000);
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.amount, 90_000_000);
+    assert_eq!(stake_info(&chain, staking, BOB).stake.amount, 50_000_000);
+}
+
+/// Test that an unstake through a permit queues the funds for unbonding like
+/// a direct unstake, instead of paying them out.
+#[test]
+fn test_permit_unstake_unbonds() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+
+    let param = permit(&chain, staking, ALICE, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(100_000_000),
+    });
+    update_contract(&mut chain, BOB, staking, "permit", &param).expect("Unstake with a permit");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 0);
+    let unbonding = stake_info(&chain, staking, ALICE).stake.unbonding;
+    assert_eq!(unbonding.len(), 1);
+    assert_eq!(unbonding[0].unlock_time, UNBONDING_PERIOD);
+
+    // The funds are paid out only once they unlock.
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect_err("Complete unstake before unbonding ends");
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect("Complete unstake");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 100_000_000);
+}
+
+/// Test that the participant count only drops once a staker has neither an
+/// active stake nor unbonding funds, and never below zero.
+#[test]
+fn test_participant_count_on_unstake() {
+    let Setup { mut chain, token, staking } = initialize();
+    let participants = |chain: &Chain| view(chain, staking).total_participants;
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 100_000_000, AdditionalData::empty()).expect("Stake");
+    assert_eq!(participants(&chain), 2);
+
+    // A partial unstake keeps Alice counted.
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(40_000_000),
+    }).expect("Partial unstake");
+    assert_eq!(participants(&chain), 2);
+
+    // A full unstake keeps Bob counted until his funds are withdrawn.
+    update_contract(&mut chain, BOB, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(100_000_000),
+    }).expect("Full unstake");
+    assert_eq!(participants(&chain), 2);
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    let complete = CompleteUnstakeParams { index: None };
+    update_contract(&mut chain, BOB, staking, "completeUnstake", &complete)
+        .expect("Complete unstake");
+    assert_eq!(participants(&chain), 1);
+
+    // Completing again neither succeeds nor changes the count.
+    update_contract(&mut chain, BOB, staking, "completeUnstake", &complete)
+        .expect_err("Complete unstake without unbonding funds");
+    assert_eq!(participants(&chain), 1);
+
+    // Alice withdrawing her partial unstake keeps her counted.
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &complete)
+        .expect("Complete unstake");
+    assert_eq!(participants(&chain), 1);
+
+    // Bob re-staking after his full exit counts him again.
+    stake(&mut chain, token, staking, BOB, 100_000_000, AdditionalData::empty()).expect("Stake");
+    assert_eq!(participants(&chain), 2);
+}
+
+/// Test that `restake` moves the pending rewards from the rewards pool into
+/// the stake, and rejects when there is nothing to compound or the pool
+/// cannot cover it.
+#[test]
+fn test_restake() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+
+    // Nothing accrued yet.
+    let update = update_contract(&mut chain, ALICE, staking, "restake", &())
+        .expect_err("Restake without rewards");
+    assert_eq!(contract_error(&update), Error::NoRewardsAvailable);
+
+    // The empty pool cannot cover the accrued rewards.
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let update = update_contract(&mut chain, ALICE, staking, "restake", &())
+        .expect_err("Restake with an empty pool");
+    assert_eq!(contract_error(&update), Error::InsufficientRewardsPool);
+
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    let earned = earned_rewards(&chain, staking, ALICE);
+    update_contract(&mut chain, ALICE, staking, "restake", &()).expect("Restake");
+
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.amount, 100_000_000 + earned);
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 0);
+    let view = view(&chain, staking);
+    assert_eq!(view.total_staked, 100_000_000 + earned);
+    assert_eq!(view.rewards_pool, 10_000_000 - earned);
+}
+
+/// Test claiming part of the rewards, the rest, and more than accrued.
+#[test]
+fn test_partial_claim() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let earned = earned_rewards(&chain, staking, ALICE);
+    let claim = |amount: Option<u64>| ClaimRewardsParams {
+        amount: amount.map(TokenAmountU64),
+        recipient: None,
+    };
+
+    // Claiming more than accrued is rejected.
+    let update = update_contract(
+        &mut chain,
+        ALICE,
+        staking,
+        "claimRewards",
+        &claim(Some(earned + 1))
+    ).expect_err("Claim more than accrued");
+    assert_eq!(contract_error(&update), Error::ClaimExceedsRewards);
+
+    // A partial claim leaves the rest pending, without crediting it twice.
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &claim(Some(1_000)))
+        .expect("Partial claim");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 1_000);
+    assert_eq!(earned_rewards(&chain, staking, ALICE), earned - 1_000);
+
+    // Claiming without an amount takes the rest.
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &claim(None)).expect("Full claim");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), earned);
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 0);
+}
+
+/// Test that `getUnbonding` lists each unstake with its unlock time, and
+/// nothing for an unknown account.
+#[test]
+fn test_get_unbonding() {
+    let Setup { mut chain, token, staking } = initialize();
+    let unbonding = |chain: &Chain, account: AccountAddress| -> UnbondingResult {
+        invoke(chain, staking, "getUnbonding", &account)
+            .parse_return_value()
+            .expect("Parse unbonding")
+    };
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(10_000_000),
+    }).expect("Unstake");
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(20_000_000),
+    }).expect("Unstake");
+
+    let result = unbonding(&chain, ALICE);
+    assert_eq!(result.unbonding_period, UNBONDING_PERIOD);
+    assert_eq!(result.unbonding, vec![
+        UnbondingInfo {
+            amount: TokenAmountU64(10_000_000),
+            unlock_time: UNBONDING_PERIOD,
+        },
+        UnbondingInfo {
+            amount: TokenAmountU64(20_000_000),
+            unlock_time: 24 * 60 * 60 + UNBONDING_PERIOD,
+        }
+    ]);
+    assert!(unbonding(&chain, BOB).unbonding.is_empty());
+}
+
+/// Test paging through more stakers than fit in a page with `getStakers`.
+#[test]
+fn test_get_stakers_paging() {
+    let Setup { mut chain, token, staking } = initialize();
+    let stakers: Vec<AccountAddress> = (10..15).map(|i| AccountAddress([i; 32])).collect();
+    for (i, staker) in stakers.iter().enumerate() {
+        chain.create_account(account(*staker));
+        stake(&mut chain, token, staking, *staker, 1_000 * (i as u64 + 1), AdditionalData::empty())
+            .expect("Stake");
+    }
+    let page = |skip: u64| -> Vec<(AccountAddress, StakeInfo)> {
+        invoke(&chain, staking, "getStakers", &PageParams { skip, take: 2 })
+            .parse_return_value()
+            .expect("Parse stakers")
+    };
+
+    // The pages cover every staker once, in address order.
+    let pages = [page(0), page(2), page(4)];
+    assert_eq!(pages.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 2, 1]);
+    let listed: Vec<(AccountAddress, u64)> = pages
+        .concat()
+        .into_iter()
+        .map(|(account, stake)| (account, stake.amount))
+        .collect();
+    let expected: Vec<(AccountAddress, u64)> = stakers
+        .iter()
+        .enumerate()
+        .map(|(i, staker)| (*staker, 1_000 * (i as u64 + 1)))
+        .collect();
+    assert_eq!(listed, expected);
+
+    // The same cursor returns the same page, and paging past the end nothing.
+    assert_eq!(page(2), pages[1]);
+    assert!(page(5).is_empty());
+}
+
+/// Test the two-step admin transfer, and that only the admin can propose and
+/// only the proposed account can accept.
+#[test]
+fn test_admin_transfer() {
+    let Setup { mut chain, staking, .. } = initialize();
+
+    let update = update_contract(&mut chain, ALICE, staking, "proposeAdmin", &ALICE)
+        .expect_err("Propose as non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+
+    update_contract(&mut chain, ADMIN, staking, "proposeAdmin", &BOB).expect("Propose Bob");
+    let update = update_contract(&mut chain, ALICE, staking, "acceptAdmin", &())
+        .expect_err("Accept as another account");
+    assert_eq!(contract_error(&update), Error::UnAuthorized);
+    assert_eq!(view(&chain, staking).admin, ADMIN);
+
+    let update = update_contract(&mut chain, BOB, staking, "acceptAdmin", &()).expect("Accept");
+    assert!(
+        contract_events(&update, staking)
+            .iter()
+            .any(|e| matches!(e, Event::AdminTransferred(_)))
+    );
+    assert_eq!(view(&chain, staking).admin, BOB);
+
+    // The previous admin lost its rights.
+    let update = update_contract(&mut chain, ADMIN, staking, "proposeAdmin", &ADMIN)
+        .expect_err("Propose as the previous admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+}
+
+/// Test that the privileged entrypoints are gated on the admin, not on the
+/// account that owns the contract instance.
+#[test]
+fn test_admin_not_owner() {
+    let Setup { mut chain, token, staking } = initialize();
+    let params = InitContractParams {
+        admin: ALICE,
+        token_address: token,
+        unbonding_period: UNBONDING_PERIOD,
+        slashing_rate: 1_000,
+    };
+    // The admin account owns the instance, Alice is its admin.
+    let staking = init_staking(&mut chain, staking, &params).expect("Initializing contract");
+    mint(&mut chain, token, Address::Contract(staking), 1_000);
+    let pause = SetPausedParams { paused: true };
+    let update_apr = UpdateAprParams { new_apr: 1_000 };
+    let withdraw = WithdrawEuroEParams {
+        withdraw_address: ADMIN,
+        amount: TokenAmountU64(1_000),
+    };
+
+    // The owner is rejected.
+    let update = update_contract(&mut chain, ADMIN, staking, "setPaused", &pause)
+        .expect_err("Pause as the owner");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    let update = update_contract(&mut chain, ADMIN, staking, "updateApr", &update_apr)
+        .expect_err("Update the APR as the owner");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    let update = update_contract(&mut chain, ADMIN, staking, "withdrawEuroe", &withdraw)
+        .expect_err("Withdraw as the owner");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+
+    // The admin is accepted.
+    update_contract(&mut chain, ALICE, staking, "updateApr", &update_apr).expect("Update APR");
+    update_contract(&mut chain, ALICE, staking, "withdrawEuroe", &withdraw).expect("Withdraw");
+    update_contract(&mut chain, ALICE, staking, "setPaused", &pause).expect("Pause");
+    assert!(view(&chain, staking).paused);
+}
+
+/// Test that `max_reward_per_claim` caps a claim and leaves the remainder
+/// pending for a later claim.
+#[test]
+fn test_max_reward_per_claim() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let earned = earned_rewards(&chain, staking, ALICE);
+    let cap = earned / 2 + 1;
+    let params = SetMaxRewardPerClaimParams {
+        max_reward_per_claim: Some(TokenAmountU64(cap)),
+    };
+    let claim = ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    };
+    let claimed_event = |update: &ContractInvokeSuccess| {
+        contract_events(update, staking)
+            .into_iter()
+            .find_map(|event| match event {
+                Event::Claimed(event) => Some(event.rewards_claimed.0),
+                _ => None,
+            })
+            .expect("Claim event")
+    };
+
+    let update = update_contract(&mut chain, ALICE, staking, "setMaxRewardPerClaim", &params)
+        .expect_err("Set the cap as a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    update_contract(&mut chain, ADMIN, staking, "setMaxRewardPerClaim", &params)
+        .expect("Set the cap");
+
+    // The claim is capped and the remainder stays pending.
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &claim)
+        .expect("Capped claim");
+    assert_eq!(claimed_event(&update), cap);
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), cap);
+    assert_eq!(earned_rewards(&chain, staking, ALICE), earned - cap);
+
+    // A second claim retrieves the remainder, which is within the cap.
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &claim)
+        .expect("Claim the remainder");
+    assert_eq!(claimed_event(&update), earned - cap);
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), earned);
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 0);
+}
+
+/// Test that the stake and unstake events carry the total staked after the
+/// operation, as reported by `view`.
+#[test]
+fn test_total_staked_after() {
+    let Setup { mut chain, token, staking } = initialize();
+    let total_staked_after = |update: &ContractInvokeSuccess| {
+        contract_events(update, staking)
+            .into_iter()
+            .find_map(|event| match event {
+                Event::Staked(event) => Some(event.total_staked_after.0),
+                Event::Unstaked(event) => Some(event.total_staked_after.0),
+                _ => None,
+            })
+            .expect("Stake or unstake event")
+    };
+
+    let update = stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty())
+        .expect("Stake");
+    assert_eq!(total_staked_after(&update), 100_000_000);
+    assert_eq!(view(&chain, staking).total_staked, 100_000_000);
+
+    let update = stake(&mut chain, token, staking, BOB, 50_000_000, AdditionalData::empty())
+        .expect("Stake");
+    assert_eq!(total_staked_after(&update), 150_000_000);
+    assert_eq!(view(&chain, staking).total_staked, 150_000_000);
+
+    let update = update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(30_000_000),
+    }).expect("Unstake");
+    assert_eq!(total_staked_after(&update), 120_000_000);
+    assert_eq!(view(&chain, staking).total_staked, 120_000_000);
+}
+
+/// Test that unstaking settles the rewards earned on the full principal up to
+/// the unstake, and that only the remaining principal earns afterwards.
+#[test]
+fn test_unstake_settles_rewards() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let earned = earned_rewards(&chain, staking, ALICE);
+    assert!(earned > 0);
+
+    let update = update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(50_000_000),
+    }).expect("Unstake half");
+    let rewards_earned = contract_events(&update, staking)
+        .into_iter()
+        .find_map(|event| match event {
+            Event::Unstaked(event) => Some(event.rewards_earned.0),
+            _ => None,
+        })
+        .expect("Unstake event");
+    assert_eq!(rewards_earned, earned);
+    let info = stake_info(&chain, staking, ALICE).stake;
+    assert_eq!(info.pending_rewards, earned);
+    assert_eq!(info.timestamp, chain.block_time().millis / 1000);
+
+    // Only the remaining half earns from now on.
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    assert_eq!(earned_rewards(&chain, staking, ALICE), earned + earned / 2);
+}
+
+/// Test that a first stake below the minimum is rejected, while a top-up that
+/// keeps the staker above it is accepted.
+#[test]
+fn test_minimum_stake() {
+    let Setup { mut chain, token, staking } = initialize();
+    let minimum = TokenAmountU64(10_000_000);
+
+    let update = update_contract(&mut chain, ALICE, staking, "setMinimumStake", &minimum)
+        .expect_err("Set the minimum as a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    update_contract(&mut chain, ADMIN, staking, "setMinimumStake", &minimum)
+        .expect("Set the minimum");
+
+    let update = stake(&mut chain, token, staking, ALICE, 9_999_999, AdditionalData::empty())
+        .expect_err("Stake below the minimum");
+    assert_eq!(hook_error(&update, staking), Error::BelowMinimumStake);
+    assert_eq!(view(&chain, staking).total_participants, 0);
+
+    stake(&mut chain, token, staking, ALICE, 10_000_000, AdditionalData::empty())
+        .expect("Stake the minimum");
+    stake(&mut chain, token, staking, ALICE, 1, AdditionalData::empty()).expect("Top up");
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.amount, 10_000_001);
+}
+
+/// Test that `emergencyWithdraw` is only available while paused, and that it
+/// returns the principal immediately while forfeiting the rewards.
+#[test]
+fn test_emergency_withdraw() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 50_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    assert!(earned_rewards(&chain, staking, ALICE) > 0);
+
+    let update = update_contract(&mut chain, ALICE, staking, "emergencyWithdraw", &())
+        .expect_err("Emergency withdraw while unpaused");
+    assert_eq!(contract_error(&update), Error::ContractNotPaused);
+
+    update_contract(&mut chain, ADMIN, staking, "setPaused", &SetPausedParams {
+        paused: true,
+    }).expect("Pause");
+    let update = update_contract(&mut chain, ALICE, staking, "emergencyWithdraw", &())
+        .expect("Emergency withdraw while paused");
+    let event = contract_events(&update, staking)
+        .into_iter()
+        .find_map(|event| match event {
+            Event::Unstaked(event) => Some(event),
+            _ => None,
+        })
+        .expect("Unstake event");
+    assert_eq!(event.unstaked_amount, TokenAmountU64(100_000_000));
+    assert_eq!(event.rewards_earned, TokenAmountU64(0));
+
+    // The principal is returned without rewards and the stake is cleared.
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 100_000_000);
+    let view = view(&chain, staking);
+    assert_eq!(view.total_staked, 50_000_000);
+    assert_eq!(view.total_participants, 1);
+    assert_eq!(view.rewards_pool, 10_000_000);
+    update_contract(&mut chain, ALICE, staking, "emergencyWithdraw", &())
+        .expect_err("Emergency withdraw twice");
+}
+
+/// Test that an APR change applies only from the moment it is made, so the
+/// rewards accrued before it keep the old rate.
+#[test]
+fn test_apr_change_mid_period() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 100_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 1_390_000);
+    update_contract(&mut chain, ADMIN, staking, "updateApr", &UpdateAprParams {
+        new_apr: 1_000,
+    }).expect("Raise the APR");
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 1_390_000);
+
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 1_390_000 + 10_000_000);
+    update_contract(&mut chain, ADMIN, staking, "updateApr", &UpdateAprParams {
+        new_apr: 500,
+    }).expect("Cut the APR");
+
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 1_390_000 + 10_000_000 + 5_000_000);
+}
+
+/// Test that under a constant APR the accumulator pays what the per-stake
+/// formula `amount * apr * elapsed / (year * 10_000)` pays, for stakers
+/// joining at different times and checkpoints in between.
+#[test]
+fn test_accumulator_matches_formula() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 100_000_000);
+    let formula = |amount: u64, elapsed: u64| -> u64 {
+        let year = 365 * 24 * 60 * 60;
+        ((amount as u128) * 139 * (elapsed as u128) / (year * 10_000)) as u64
+    };
+
+    stake(&mut chain, token, staking, ALICE, 123_456_789, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_seconds(1_234_567)).expect("Advance time");
+    stake(&mut chain, token, staking, BOB, 987_654_321, AdditionalData::empty()).expect("Stake");
+    let mut alice_elapsed = 1_234_567;
+    let mut bob_elapsed = 0;
+
+    // Each stake checkpoints the accumulator.
+    for seconds in [1, 59, 86_399, 7_654_321, 31_536_000] {
+        chain.tick_block_time(Duration::from_seconds(seconds)).expect("Advance time");
+        alice_elapsed += seconds;
+        bob_elapsed += seconds;
+        assert_eq!(earned_rewards(&chain, staking, ALICE), formula(123_456_789, alice_elapsed));
+        assert_eq!(earned_rewards(&chain, staking, BOB), formula(987_654_321, bob_elapsed));
+        stake(&mut chain, token, staking, ADMIN, 1_000, AdditionalData::empty()).expect("Stake");
+    }
+}
+
+/// Test that cancelling an unbonding entry stakes its funds again, earning
+/// rewards from the cancellation on, and that out-of-range and matured
+/// entries cannot be cancelled.
+#[test]
+fn test_cancel_unbonding() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(40_000_000),
+    }).expect("Unstake");
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    let cancel = |index: u32| CancelUnbondingParams { index };
+
+    let update = update_contract(&mut chain, ALICE, staking, "cancelUnbonding", &cancel(1))
+        .expect_err("Cancel an out-of-range entry");
+    assert_eq!(contract_error(&update), Error::InvalidUnbondingIndex);
+
+    update_contract(&mut chain, ALICE, staking, "cancelUnbonding", &cancel(0))
+        .expect("Cancel unbonding");
+    let info = stake_info(&chain, staking, ALICE);
+    assert_eq!(info.stake.amount, 100_000_000);
+    assert_eq!(info.stake.timestamp, 2 * 24 * 60 * 60);
+    assert!(info.stake.unbonding.is_empty());
+    let view = view(&chain, staking);
+    assert_eq!(view.total_staked, 100_000_000);
+    assert_eq!(view.total_unbonding, 0);
+
+    // The full principal earns from the cancellation on.
+    let earned = earned_rewards(&chain, staking, ALICE);
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    assert_eq!(earned_rewards(&chain, staking, ALICE), earned + 1_390_000);
+
+    // A matured entry must be completed instead.
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(40_000_000),
+    }).expect("Unstake");
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    let update = update_contract(&mut chain, ALICE, staking, "cancelUnbonding", &cancel(0))
+        .expect_err("Cancel a matured entry");
+    assert_eq!(contract_error(&update), Error::UnbondingAlreadyMatured);
+}
+
+/// Test that `view` returns the unbonding period and slashing rate given at
+/// initialization.
+#[test]
+fn test_view_init_params() {
+    let Setup { chain, staking, .. } = initialize();
+    let view = view(&chain, staking);
+    assert_eq!(view.unbonding_period, UNBONDING_PERIOD);
+    assert_eq!(view.slashing_rate, 1_000);
+}
+
+/// Test that changing the unbonding period only affects later unstakes,
+/// while queued entries keep their unlock time.
+#[test]
+fn test_set_unbonding_period() {
+    let Setup { mut chain, token, staking } = initialize();
+    let new_period = 2 * UNBONDING_PERIOD;
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(10_000_000),
+    }).expect("Unstake");
+
+    let update = update_contract(&mut chain, ALICE, staking, "setUnbondingPeriod", &new_period)
+        .expect_err("Set the unbonding period as a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    let update = update_contract(&mut chain, ADMIN, staking, "setUnbondingPeriod", &new_period)
+        .expect("Set the unbonding period");
+    let event = contract_events(&update, staking)
+        .into_iter()
+        .find_map(|event| match event {
+            Event::UnbondingPeriodUpdated(event) => Some(event),
+            _ => None,
+        })
+        .expect("Unbonding period event");
+    assert_eq!(event.old_unbonding_period, UNBONDING_PERIOD);
+    assert_eq!(event.new_unbonding_period, new_period);
+    assert_eq!(view(&chain, staking).unbonding_period, new_period);
+
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(20_000_000),
+    }).expect("Unstake");
+    let day = 24 * 60 * 60;
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.unbonding, vec![
+        UnbondingInfo {
+            amount: TokenAmountU64(10_000_000),
+            unlock_time: UNBONDING_PERIOD,
+        },
+        UnbondingInfo {
+            amount: TokenAmountU64(20_000_000),
+            unlock_time: day + new_period,
+        },
+    ]);
+}
+
+/// Test that the slashing rate can be updated within 100%, and that a rate
+/// above it is rejected by `setSlashingRate` and at initialization.
+#[test]
+fn test_set_slashing_rate() {
+    let Setup { mut chain, token, staking } = initialize();
+
+    let update = update_contract(&mut chain, ALICE, staking, "setSlashingRate", &2_000u64)
+        .expect_err("Set the slashing rate as a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    let update = update_contract(&mut chain, ADMIN, staking, "setSlashingRate", &10_001u64)
+        .expect_err("Set a slashing rate above 100%");
+    assert_eq!(contract_error(&update), Error::InvalidSlashingRate);
+
+    let update = update_contract(&mut chain, ADMIN, staking, "setSlashingRate", &10_000u64)
+        .expect("Set the slashing rate");
+    let event = contract_events(&update, staking)
+        .into_iter()
+        .find_map(|event| match event {
+            Event::SlashingRateUpdated(event) => Some(event),
+            _ => None,
+        })
+        .expect("Slashing rate event");
+    assert_eq!(event.old_slashing_rate, 1_000);
+    assert_eq!(event.new_slashing_rate, 10_000);
+    assert_eq!(view(&chain, staking).slashing_rate, 10_000);
+
+    let params = InitContractParams {
+        admin: ADMIN,
+        token_address: token,
+        unbonding_period: UNBONDING_PERIOD,
+        slashing_rate: 10_001,
+    };
+    let init = init_staking(&mut chain, staking, &params)
+        .expect_err("Initialize with a slashing rate above 100%");
+    assert_eq!(init_reject_reason(init), reject_reason(Error::InvalidSlashingRate));
+}
+
+/// Test that `completeUnstake` with an index withdraws only that matured
+/// entry, and rejects unmatured or out-of-range entries.
+#[test]
+fn test_complete_unstake_index() {
+    let Setup { mut chain, token, staking } = initialize();
+    let complete = |index: Option<u32>| CompleteUnstakeParams { index };
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    for amount in [10_000_000, 20_000_000, 30_000_000] {
+        update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+            amount: TokenAmountU64(amount),
+        }).expect("Unstake");
+        chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    }
+    // The first two entries have matured, the last has not.
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD - 2 * 24 * 60 * 60))
+        .expect("Advance time");
+
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &complete(Some(1)))
+        .expect("Complete the second entry");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 20_000_000);
+    let unbonding: Vec<_> = stake_info(&chain, staking, ALICE).stake.unbonding
+        .iter()
+        .map(|unbonding| unbonding.amount.0)
+        .collect();
+    assert_eq!(unbonding, vec![10_000_000, 30_000_000]);
+    assert_eq!(view(&chain, staking).total_unbonding, 40_000_000);
+
+    for index in [1, 2] {
+        let update = update_contract(&mut chain, ALICE, staking, "completeUnstake", &complete(
+            Some(index)
+        )).expect_err("Complete an unmatured or out-of-range entry");
+        assert_eq!(contract_error(&update), Error::UnbondingPeriodNotMet);
+    }
+
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &complete(None))
+        .expect("Complete all matured entries");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 30_000_000);
+    assert_eq!(view(&chain, staking).total_unbonding, 30_000_000);
+}
+
+/// Test that `solvency` compares the token balance of the contract with the
+/// staked, unbonding and reward funds it owes.
+#[test]
+fn test_solvency() {
+    let Setup { mut chain, token, staking } = initialize();
+    let solvency = |chain: &Chain| -> SolvencyResult {
+        invoke(chain, staking, "solvency", &()).parse_return_value().expect("Parse solvency")
+    };
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(20_000_000),
+    }).expect("Unstake");
+    assert_eq!(solvency(&chain), SolvencyResult {
+        balance: TokenAmountU64(110_000_000),
+        obligations: TokenAmountU64(110_000_000),
+        solvent: true,
+    });
+
+    // Tokens sent directly to the contract are a surplus.
+    mint(&mut chain, token, Address::Contract(staking), 5_000_000);
+    assert_eq!(solvency(&chain), SolvencyResult {
+        balance: TokenAmountU64(115_000_000),
+        obligations: TokenAmountU64(110_000_000),
+        solvent: true,
+    });
+
+    // Losing tokens leaves the obligations uncovered.
+    update_contract(&mut chain, ADMIN, token, "burn", &MintParams {
+        owner: Address::Contract(staking),
+        amount: TokenAmountU64(10_000_000),
+    }).expect("Burn");
+    assert_eq!(solvency(&chain), SolvencyResult {
+        balance: TokenAmountU64(105_000_000),
+        obligations: TokenAmountU64(110_000_000),
+        solvent: false,
+    });
+}
+
+/// Test that a token contract calling back into `claimRewards` during a
+/// transfer is rejected, so the rewards are paid only once.
+#[test]
+fn test_reentrancy() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let earned = earned_rewards(&chain, staking, ALICE);
+    let claim = ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    };
+
+    // The token re-enters with a claim signed by Alice, as accounts cannot be
+    // impersonated.
+    let param = permit(&chain, staking, ALICE, "claimRewards", &claim);
+    update_contract(&mut chain, ADMIN, token, "setReentry", &Some(ReentryParams {
+        contract: staking,
+        entrypoint: OwnedEntrypointName::new_unchecked("permit".into()),
+        parameter: to_bytes(&param),
+    })).expect("Set reentry");
+
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &claim)
+        .expect("Claim rewards");
+    assert_eq!(find_rejection(&update.trace_elements, staking), Some(Error::Reentrancy));
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), earned);
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 0);
+    assert_eq!(user_nonce(&chain, staking, ALICE), 0);
+}
+
+/// Test that `batchGetStakeInfo` returns the stakes in the order asked, with
+/// fresh rewards as `getStakeInfo` computes them and defaults for unknown
+/// accounts.
+#[test]
+fn test_batch_get_stake_info() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 50_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(30)).expect("Advance time");
+    let unknown = AccountAddress([4u8; 32]);
+
+    let accounts = vec![BOB, unknown, ALICE, BOB];
+    let params = BatchGetStakeInfoParams {
+        accounts: accounts.clone(),
+    };
+    let stakes: Vec<StakeInfo> = invoke(&chain, staking, "batchGetStakeInfo", &params)
+        .parse_return_value()
+        .expect("Parse stake infos");
+    let expected: Vec<StakeInfo> = accounts
+        .iter()
+        .map(|account| stake_info(&chain, staking, *account).stake)
+        .collect();
+    assert_eq!(stakes, expected);
+    assert!(stakes[0].pending_rewards > 0);
+    assert_eq!(stakes[1].amount, 0);
+    assert_eq!(stakes[2].amount, 100_000_000);
+}
+
+/// Test that `completeUnstake`, with an index, `restake` and `fundRewards`
+/// can be submitted by a relayer with a signed permit message.
+#[test]
+fn test_permit_entrypoints() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    for amount in [10_000_000, 20_000_000] {
+        update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+            amount: TokenAmountU64(amount),
+        }).expect("Unstake");
+    }
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+
+    let param = permit(&chain, staking, ALICE, "completeUnstake", &CompleteUnstakeParams {
+        index: Some(1),
+    });
+    update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect("Complete unstake with a permit");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 20_000_000);
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.unbonding.len(), 1);
+
+    let earned = earned_rewards(&chain, staking, ALICE);
+    let param = permit(&chain, staking, ALICE, "restake", &());
+    update_contract(&mut chain, BOB, staking, "permit", &param).expect("Restake with a permit");
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.amount, 70_000_000 + earned);
+
+    let param = permit(&chain, staking, ALICE, "fundRewards", &TokenAmountU64(5_000_000));
+    update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect("Fund rewards with a permit");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 15_000_000);
+    assert_eq!(view(&chain, staking).rewards_pool, 15_000_000 - earned);
+    assert_eq!(user_nonce(&chain, staking, ALICE), 3);
+}
+
+/// Test that an unbonding period overflowing the unlock time makes `unstake`
+/// fail with an error rather than trap.
+#[test]
+fn test_unlock_time_overflow() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ADMIN, staking, "setUnbondingPeriod", &u64::MAX)
+        .expect("Set an extreme unbonding period");
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+
+    let update = update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(10_000_000),
+    }).expect_err("Unstake with an overflowing unlock time");
+    assert_eq!(contract_error(&update), Error::ArithmeticOverflow);
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.amount, 100_000_000);
+}
+
+/// Test that two deposits each accrue rewards from their own start time,
+/// and that the stake aggregates them.
+#[test]
+fn test_positions_accrue_from_deposit() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    update_contract(&mut chain, ADMIN, staking, "setLockTiers", &SetLockTiersParams {
+        lock_tiers: vec![LockTier {
+            lock_days: 30,
+            bonus_bps: 0,
+        }],
+    }).expect("Set lock tiers");
+    let year = 365 * 24 * 60 * 60;
+
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    // A locked deposit takes a position of its own.
+    let locked = AdditionalData::from(to_bytes(&StakeData {
+        lock_days: 30,
+        beneficiary: None,
+    }));
+    stake(&mut chain, token, staking, ALICE, 100_000_000, locked).expect("Stake locked");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+
+    let info = stake_info(&chain, staking, ALICE).stake;
+    let positions: Vec<_> = info.positions
+        .iter()
+        .map(|position| (position.amount, position.timestamp))
+        .collect();
+    assert_eq!(positions, vec![(100_000_000, 0), (100_000_000, year)]);
+    assert_eq!(info.amount, 200_000_000);
+    // Two years on the first deposit and one on the second.
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 2 * 1_390_000 + 1_390_000);
+}
+
+/// Test that a token other than EUROe sent by the token contract is rejected
+/// with `UnexpectedTokenId`.
+#[test]
+fn test_unexpected_token_id() {
+    let Setup { mut chain, token, staking } = initialize();
+    let params = OnReceivingCis2Params {
+        token_id: TokenIdVec(vec![1]),
+        amount: TokenAmountU64(100_000_000),
+        from: ALICE_ADDR,
+        data: AdditionalData::empty(),
+    };
+    // The token contract calls `stake` with another token after a transfer.
+    update_contract(&mut chain, ADMIN, token, "setReentry", &Some(ReentryParams {
+        contract: staking,
+        entrypoint: OwnedEntrypointName::new_unchecked("stake".into()),
+        parameter: to_bytes(&params),
+    })).expect("Set reentry");
+    mint(&mut chain, token, ALICE_ADDR, 1_000);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(1_000),
+        from: ALICE_ADDR,
+        to: Receiver::Account(BOB),
+        data: AdditionalData::empty(),
+    };
+
+    let update = update_contract(&mut chain, ALICE, token, "transfer", &TransferParams::from(
+        vec![transfer]
+    )).expect("Transfer");
+    assert_eq!(find_rejection(&update.trace_elements, staking), Some(Error::UnexpectedTokenId));
+    assert_eq!(view(&chain, staking).total_staked, 0);
+}
+
+/// Test that each pause flag halts only its own operations, and that
+/// `setPaused` sets all of them.
+#[test]
+fn test_pause_flags() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    let unstake = UnstakeParams {
+        amount: TokenAmountU64(1_000_000),
+    };
+    let claim = ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    };
+    let set_flags = |chain: &mut Chain, flags: PauseFlags| {
+        update_contract(chain, ADMIN, staking, "setPauseFlags", &flags).expect("Set pause flags");
+        chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    };
+
+    set_flags(&mut chain, PauseFlags {
+        stake_paused: true,
+        ..PauseFlags::default()
+    });
+    let update = stake(&mut chain, token, staking, BOB, 1_000_000, AdditionalData::empty())
+        .expect_err("Stake while staking is paused");
+    assert_eq!(hook_error(&update, staking), Error::ContractPaused);
+    update_contract(&mut chain, ALICE, staking, "unstake", &unstake).expect("Unstake");
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &claim).expect("Claim");
+
+    set_flags(&mut chain, PauseFlags {
+        unstake_paused: true,
+        ..PauseFlags::default()
+    });
+    let update = update_contract(&mut chain, ALICE, staking, "unstake", &unstake)
+        .expect_err("Unstake while unstaking is paused");
+    assert_eq!(contract_error(&update), Error::ContractPaused);
+    stake(&mut chain, token, staking, BOB, 1_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &claim).expect("Claim");
+
+    set_flags(&mut chain, PauseFlags {
+        claim_paused: true,
+        ..PauseFlags::default()
+    });
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &claim)
+        .expect_err("Claim while claiming is paused");
+    assert_eq!(contract_error(&update), Error::ContractPaused);
+    stake(&mut chain, token, staking, BOB, 1_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ALICE, staking, "unstake", &unstake).expect("Unstake");
+
+    // Completing an unstake is never paused.
+    update_contract(&mut chain, ADMIN, staking, "setPaused", &SetPausedParams {
+        paused: true,
+    }).expect("Pause");
+    assert_eq!(view(&chain, staking).pause_flags, PauseFlags::all(true));
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect("Complete unstake while paused");
+
+    update_contract(&mut chain, ADMIN, staking, "setPaused", &SetPausedParams {
+        paused: false,
+    }).expect("Unpause");
+    assert_eq!(view(&chain, staking).pause_flags, PauseFlags::default());
+}
+
+/// Test that `setPaused` logs the new pause flags with the caller.
+#[test]
+fn test_pause_changed_event() {
+    let Setup { mut chain, staking, .. } = initialize();
+    let pause_changed = |update: &ContractInvokeSuccess| {
+        contract_events(update, staking)
+            .into_iter()
+            .find_map(|event| match event {
+                Event::PauseChanged(event) => Some(event),
+                _ => None,
+            })
+            .expect("Pause event")
+    };
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+
+    let update = update_contract(&mut chain, ADMIN, staking, "setPaused", &SetPausedParams {
+        paused: true,
+    }).expect("Pause");
+    let event = pause_changed(&update);
+    assert_eq!(event.paused, PauseFlags::all(true));
+    assert_eq!(event.caller, ADMIN);
+    assert_eq!(event.update_timestamp, 24 * 60 * 60);
+
+    let update = update_contract(&mut chain, ADMIN, staking, "setPaused", &SetPausedParams {
+        paused: false,
+    }).expect("Unpause");
+    assert_eq!(pause_changed(&update).paused, PauseFlags::default());
+}
+
+/// Test that staked, unbonding and reward funds each count as obligations
+/// that `withdrawEuroe` cannot dip into.
+#[test]
+fn test_withdraw_euroe_obligations() {
+    for obligation in ["staked", "unbonding", "rewards"] {
+        let Setup { mut chain, token, staking } = initialize();
+        match obligation {
+            "staked" | "unbonding" => {
+                stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty())
+                    .expect("Stake");
+            }
+            _ => fund_rewards(&mut chain, token, staking, 100_000_000),
+        }
+        if obligation == "unbonding" {
+            update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+                amount: TokenAmountU64(100_000_000),
+            }).expect("Unstake");
+        }
+        mint(&mut chain, token, Address::Contract(staking), 500);
+        let withdraw = |amount: u64| WithdrawEuroEParams {
+            withdraw_address: BOB,
+            amount: TokenAmountU64(amount),
+        };
+
+        let update = update_contract(&mut chain, ADMIN, staking, "withdrawEuroe", &withdraw(501))
+            .expect_err("Withdraw into the obligations");
+        assert_eq!(contract_error(&update), Error::InsufficientFunds, "{obligation}");
+        update_contract(&mut chain, ADMIN, staking, "withdrawEuroe", &withdraw(500))
+            .expect("Withdraw the surplus");
+        assert_eq!(balance_of(&chain, token, BOB_ADDR), 500);
+    }
+}
+
+/// Test that `metadata` returns the token address given at initialization,
+/// the APR denominator, the permit entrypoints and the contract version.
+#[test]
+fn test_metadata() {
+    let Setup { chain, token, staking } = initialize();
+    let metadata: MetadataResult = invoke(&chain, staking, "metadata", &())
+        .parse_return_value()
+        .expect("Parse metadata");
+    let entrypoints = [
+        "unstake",
+        "claimRewards",
+        "completeUnstake",
+        "restake",
+        "fundRewards",
+        "withdrawEuroe",
+        "slash",
+    ];
+    assert_eq!(metadata, MetadataResult {
+        token_address: token,
+        apr_denominator: 10_000,
+        supported_permit_entrypoints: entrypoints
+            .iter()
+            .map(|entrypoint| OwnedEntrypointName::new_unchecked(entrypoint.to_string()))
+            .collect(),
+        version: 1,
+    });
+}
+
+/// Test that a permit is valid until the block time reaches its timestamp in
+/// milliseconds, and that a timestamp given in seconds is expired.
+#[test]
+fn test_permit_expiry() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(30)).expect("Advance time");
+    let claim = ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    };
+
+    // A timestamp in seconds lies in the past of the block time.
+    let mut param = permit(&chain, staking, ALICE, "claimRewards", &claim);
+    param.message.timestamp = Timestamp::from_timestamp_millis(
+        chain.block_time().millis / 1000 + 60 * 60
+    );
+    param.signature = sign(ALICE, &view_message_hash(&chain, staking, &param));
+    let update = update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect_err("Permit with a timestamp in seconds");
+    assert_eq!(contract_error(&update), Error::Expired);
+
+    // Just expired at the timestamp.
+    let param = permit(&chain, staking, ALICE, "claimRewards", &claim);
+    chain.tick_block_time(Duration::from_hours(1)).expect("Advance time");
+    let update = update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect_err("Permit at its timestamp");
+    assert_eq!(contract_error(&update), Error::Expired);
+
+    // Just valid a millisecond before.
+    let param = permit(&chain, staking, ALICE, "claimRewards", &claim);
+    chain.tick_block_time(Duration::from_millis(60 * 60 * 1000 - 1)).expect("Advance time");
+    update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect("Permit a millisecond before its timestamp");
+    assert_eq!(user_nonce(&chain, staking, ALICE), 1);
+}
+
+/// Test that the admin can sweep the matured unbonding of a staker into
+/// their account, after a slash reduced it.
+#[test]
+fn test_sweep_expired_unbonding() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(30_000_000),
+    }).expect("Unstake");
+    // The slash takes 10% of the unbonding funds.
+    update_contract(&mut chain, ADMIN, staking, "slash", &SlashParams {
+        staker: ALICE,
+        amount: TokenAmountU64(10_000_000),
+        reason_code: 1,
+    }).expect("Slash");
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+
+    let update = update_contract(&mut chain, BOB, staking, "sweepExpiredUnbonding", &ALICE)
+        .expect_err("Sweep as a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    let update = update_contract(&mut chain, ADMIN, staking, "sweepExpiredUnbonding", &ALICE)
+        .expect("Sweep");
+    assert!(
+        contract_events(&update, staking).iter().any(|e| matches!(e, Event::UnstakeCompleted(_)))
+    );
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 27_000_000);
+    assert_eq!(balance_of(&chain, token, ADMIN_ADDR), 0);
+    assert!(stake_info(&chain, staking, ALICE).stake.unbonding.is_empty());
+    assert_eq!(view(&chain, staking).total_unbonding, 0);
+}
+
+/// Test that `viewMessageHashDetailed` returns the prepend bytes, the message
+/// length and the hash `viewMessageHash` returns for the same message.
+#[test]
+fn test_view_message_hash_detailed() {
+    let Setup { chain, staking, .. } = initialize();
+    let param = permit(&chain, staking, ALICE, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(1_000),
+    });
+    let detailed: MessageHashResult = invoke(&chain, staking, "viewMessageHashDetailed", &param)
+        .parse_return_value()
+        .expect("Parse detailed message hash");
+
+    assert_eq!(detailed.message_hash, view_message_hash(&chain, staking, &param));
+    assert_eq!(detailed.prepend[..32], ALICE.0);
+    assert_eq!(detailed.prepend[32..], [0u8; 8]);
+    assert_eq!(detailed.message_length as usize, to_bytes(&param.message).len());
+}
+
+/// Test that with an accrual cap the rewards of an inactive staker stop
+/// growing after the cap, while an active staker keeps earning, and that
+/// they match uncapped accrual without the cap.
+#[test]
+fn test_max_accrual_seconds() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 20_000_000);
+    let cap = |max_accrual_seconds: Option<u64>| SetMaxAccrualSecondsParams {
+        max_accrual_seconds,
+    };
+    update_contract(&mut chain, ADMIN, staking, "setMaxAccrualSeconds", &cap(Some(365 * 86_400)))
+        .expect("Set the accrual cap");
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 100_000_000, AdditionalData::empty()).expect("Stake");
+
+    // Alice claims every year, Bob does not interact.
+    for _ in 0..3 {
+        chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+        update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+            amount: None,
+            recipient: None,
+        }).expect("Claim");
+    }
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 3 * 1_390_000);
+    assert_eq!(earned_rewards(&chain, staking, BOB), 1_390_000);
+
+    update_contract(&mut chain, ADMIN, staking, "setMaxAccrualSeconds", &cap(None))
+        .expect("Clear the accrual cap");
+    assert_eq!(earned_rewards(&chain, staking, BOB), 3 * 1_390_000);
+}
+
+/// Test that a permit logs the entrypoint it invoked with the signer and
+/// nonce.
+#[test]
+fn test_permit_executed_event() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    let permit_executed = |update: &ContractInvokeSuccess| {
+        contract_events(update, staking)
+            .into_iter()
+            .find_map(|event| match event {
+                Event::PermitExecuted(event) => Some(event),
+                _ => None,
+            })
+            .expect("Permit event")
+    };
+
+    let param = permit(&chain, staking, ALICE, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(1_000),
+    });
+    let update = update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect("Unstake with a permit");
+    let event = permit_executed(&update);
+    assert_eq!(event.signer, ALICE);
+    assert_eq!(event.nonce, 0);
+    assert_eq!(event.entry_point, OwnedEntrypointName::new_unchecked("unstake".into()));
+
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    let param = permit(&chain, staking, ALICE, "completeUnstake", &());
+    let update = update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect("Complete unstake with a permit");
+    let event = permit_executed(&update);
+    assert_eq!(event.nonce, 1);
+    assert_eq!(event.entry_point, OwnedEntrypointName::new_unchecked("completeUnstake".into()));
+}
+
+/// Test with randomized sequences of stakes, unstakes, completions and
+/// claims that `total_participants` always equals the number of stakes with
+/// funds, and that no empty stake is left behind.
+#[test]
+fn test_participant_count_randomized() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 1_000_000_000);
+    let accounts: Vec<AccountAddress> = (10..14).map(|i| AccountAddress([i; 32])).collect();
+    for account in &accounts {
+        chain.create_account(self::account(*account));
+    }
+    // A fixed xorshift generator, so failures reproduce.
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut random = |bound: u64| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed % bound
+    };
+
+    for _ in 0..200 {
+        let account = accounts[random(accounts.len() as u64) as usize];
+        let staked = stake_info(&chain, staking, account).stake.amount;
+        // Rejected operations, e.g. claiming without rewards, are part of
+        // the sequence.
+        let _ = match random(5) {
+            0 => {
+                let amount = 1 + random(1_000_000);
+                stake(&mut chain, token, staking, account, amount, AdditionalData::empty())
+            }
+            1 => update_contract(&mut chain, account, staking, "unstake", &UnstakeParams {
+                // Unstake everything about half of the time.
+                amount: TokenAmountU64(if random(2) == 0 { staked } else { random(staked + 1) }),
+            }),
+            2 => update_contract(&mut chain, account, staking, "completeUnstake", &()),
+            3 => update_contract(&mut chain, account, staking, "claimRewards", &()),
+            _ => {
+                chain.tick_block_time(Duration::from_seconds(random(2 * UNBONDING_PERIOD)))
+                    .expect("Advance time");
+                continue;
+            }
+        };
+
+        let stakers: Vec<(AccountAddress, StakeInfo)> = invoke(
+            &chain,
+            staking,
+            "getStakers",
+            &PageParams { skip: 0, take: 50 }
+        )
+        .parse_return_value()
+        .expect("Parse stakers");
+        let participants = stakers.iter().filter(|(_, stake)| stake.is_participant()).count();
+        assert_eq!(view(&chain, staking).total_participants, participants as u64);
+        assert!(stakers.iter().all(|(_, stake)| !stake.is_empty()));
+    }
+}
+
+/// Test that `rewardsForecast` matches what a claim at the forecast time
+/// pays, and rejects a timestamp in the past.
+#[test]
+fn test_rewards_forecast() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 123_456_789, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(10)).expect("Advance time");
+    let now = chain.block_time().millis / 1000;
+
+    let update = chain
+        .contract_invoke(ALICE, ALICE_ADDR, ENERGY, UpdateContractPayload {
+            amount: Amount::zero(),
+            address: staking,
+            receive_name: receive_name(&chain, staking, "rewardsForecast"),
+            message: OwnedParameter::from_serial(&RewardsForecastParams {
+                user: ALICE,
+                at_timestamp: now - 1,
+            }).expect("Parameter within size bounds"),
+        })
+        .expect_err("Forecast in the past");
+    assert_eq!(contract_error(&update), Error::TimestampInPast);
+
+    let at_timestamp = now + 1_234_567;
+    let forecasted: u64 = invoke(&chain, staking, "rewardsForecast", &RewardsForecastParams {
+        user: ALICE,
+        at_timestamp,
+    })
+    .parse_return_value()
+    .expect("Parse forecast");
+    chain.tick_block_time(Duration::from_seconds(1_234_567)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &()).expect("Claim");
+    assert!(forecasted > 0);
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), forecasted);
+}
+
+/// Test that any account can fund the rewards pool, through `fundRewards`
+/// or by a transfer to `onReceivingCIS2`, and is logged as the funder.
+#[test]
+fn test_fund_rewards_by_anyone() {
+    let Setup { mut chain, token, staking } = initialize();
+    let funder = |update: &ContractInvokeSuccess| {
+        contract_events(update, staking)
+            .into_iter()
+            .find_map(|event| match event {
+                Event::RewardsFunded(event) => Some((event.funder, event.amount.0)),
+                _ => None,
+            })
+            .expect("Funding event")
+    };
+    mint(&mut chain, token, BOB_ADDR, 8_000_000);
+
+    let amount = TokenAmountU64(5_000_000);
+    let update = update_contract(&mut chain, BOB, staking, "fundRewards", &amount)
+        .expect("Fund rewards as a non-admin");
+    assert_eq!(funder(&update), (BOB_ADDR, 5_000_000));
+    assert_eq!(view(&chain, staking).rewards_pool, 5_000_000);
+
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(3_000_000),
+        from: BOB_ADDR,
+        to: Receiver::Contract(
+            staking,
+            OwnedEntrypointName::new_unchecked("onReceivingCIS2".into())
+        ),
+        data: AdditionalData::from(to_bytes(&DepositKind::FundRewards)),
+    };
+    let update = update_contract(&mut chain, BOB, token, "transfer", &TransferParams::from(
+        vec![transfer]
+    )).expect("Fund rewards with a transfer");
+    assert_eq!(funder(&update), (BOB_ADDR, 3_000_000));
+    assert_eq!(view(&chain, staking).rewards_pool, 8_000_000);
+    assert_eq!(balance_of(&chain, token, BOB_ADDR), 0);
+}
+
+/// Test that the lifetime rewards claimed by a staker add up across claims
+/// and survive a full exit and a new stake.
+#[test]
+fn test_lifetime_rewards_claimed() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    let lifetime = |chain: &Chain| stake_info(chain, staking, ALICE).stake.lifetime_rewards_claimed;
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+
+    for claims in 1..=2 {
+        chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+        update_contract(&mut chain, ALICE, staking, "claimRewards", &()).expect("Claim");
+        assert_eq!(lifetime(&chain), claims * 1_390_000);
+    }
+
+    // A full exit removes the stake, but not the counter.
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(100_000_000),
+    }).expect("Unstake");
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &()).expect("Complete unstake");
+    assert_eq!(view(&chain, staking).total_participants, 0);
+    assert_eq!(lifetime(&chain), 2 * 1_390_000);
+
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &()).expect("Claim");
+    assert_eq!(lifetime(&chain), 3 * 1_390_000);
+}
+
+/// Test that a slash voids the pending rewards of the staker, keeping them
+/// in the rewards pool with the slashed principal, and logs both.
+#[test]
+fn test_slash_forfeits_rewards() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 1_390_000);
+
+    let update = update_contract(&mut chain, ADMIN, staking, "slash", &SlashParams {
+        staker: ALICE,
+        amount: TokenAmountU64(10_000_000),
+        reason_code: 1,
+    }).expect("Slash");
+    let event = contract_events(&update, staking)
+        .into_iter()
+        .find_map(|event| match event {
+            Event::Slashed(event) => Some(event),
+            _ => None,
+        })
+        .expect("Slash event");
+    assert_eq!(event.slashed_amount, TokenAmountU64(10_000_000));
+    assert_eq!(event.forfeited_rewards, TokenAmountU64(1_390_000));
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.pending_rewards, 0);
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 0);
+    assert_eq!(view(&chain, staking).rewards_pool, 20_000_000);
+
+    // Only rewards accrued on the remaining stake after the slash are paid.
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &()).expect("Claim");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 1_251_000);
+}
+
+/// Test that the protocol fee is taken out of claimed rewards and paid to
+/// the fee recipient, and that no fee is taken by default.
+#[test]
+fn test_reward_fee() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    let fee_collected = |update: &ContractInvokeSuccess| {
+        contract_events(update, staking).into_iter().find_map(|event| match event {
+            Event::FeeCollected(event) => Some(event),
+            _ => None,
+        })
+    };
+
+    // Without a fee the whole claim is paid out.
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &())
+        .expect("Claim");
+    assert_eq!(fee_collected(&update), None);
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 1_390_000);
+
+    let fee = |reward_fee_bps: u64| SetRewardFeeParams {
+        reward_fee_bps,
+        fee_recipient: BOB,
+    };
+    let update = update_contract(&mut chain, ALICE, staking, "setRewardFee", &fee(1_000))
+        .expect_err("Set the fee as a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    let update = update_contract(&mut chain, ADMIN, staking, "setRewardFee", &fee(10_001))
+        .expect_err("Set a fee above 100%");
+    assert_eq!(contract_error(&update), Error::InvalidRewardFee);
+    update_contract(&mut chain, ADMIN, staking, "setRewardFee", &fee(1_000)).expect("Set the fee");
+
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &())
+        .expect("Claim");
+    let event = fee_collected(&update).expect("Fee event");
+    assert_eq!(event.user, ALICE);
+    assert_eq!(event.fee_recipient, BOB);
+    assert_eq!(event.fee, TokenAmountU64(139_000));
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 1_390_000 + 1_251_000);
+    assert_eq!(balance_of(&chain, token, BOB_ADDR), 139_000);
+    assert_eq!(view(&chain, staking).rewards_pool, 10_000_000 - 2 * 1_390_000);
+}
+
+/// Test that `getAprAt` returns the APR effective at points between several
+/// updates, and that the oldest entries are pruned from the bounded history.
+#[test]
+fn test_get_apr_at() {
+    let Setup { mut chain, staking, .. } = initialize();
+    let day = 24 * 60 * 60;
+    let apr_at = |chain: &Chain, timestamp: u64| -> Result<u64, Error> {
+        chain
+            .contract_invoke(ADMIN, ADMIN_ADDR, ENERGY, UpdateContractPayload {
+                amount: Amount::zero(),
+                address: staking,
+                receive_name: receive_name(chain, staking, "getAprAt"),
+                message: OwnedParameter::from_serial(&timestamp)
+                    .expect("Parameter within size bounds"),
+            })
+            .map(|invoke| invoke.parse_return_value().expect("Parse APR"))
+            .map_err(|invoke| contract_error(&invoke))
+    };
+    let update_apr = |chain: &mut Chain, new_apr: u64| {
+        update_contract(chain, ADMIN, staking, "updateApr", &UpdateAprParams { new_apr })
+            .expect("Update APR");
+    };
+
+    for new_apr in [200, 300, 150] {
+        chain.tick_block_time(Duration::from_days(10)).expect("Advance time");
+        update_apr(&mut chain, new_apr);
+    }
+    assert_eq!(apr_at(&chain, 0), Ok(139));
+    assert_eq!(apr_at(&chain, 10 * day - 1), Ok(139));
+    assert_eq!(apr_at(&chain, 10 * day), Ok(200));
+    assert_eq!(apr_at(&chain, 25 * day), Ok(300));
+    assert_eq!(apr_at(&chain, 40 * day), Ok(150));
+
+    // Filling the history with 100 entries prunes only the initial APR.
+    for i in 0..97 {
+        chain.tick_block_time(Duration::from_seconds(1)).expect("Advance time");
+        update_apr(&mut chain, 100 + i);
+    }
+    assert_eq!(apr_at(&chain, 0), Err(Error::AprHistoryUnavailable));
+    assert_eq!(apr_at(&chain, 10 * day), Ok(200));
+    assert_eq!(apr_at(&chain, 40 * day), Ok(196));
+}
+
+/// Test that stakes under and up to the total stake cap are accepted, and
+/// one beyond it is rejected before any state changes.
+#[test]
+fn test_max_total_staked() {
+    let Setup { mut chain, token, staking } = initialize();
+    let cap = TokenAmountU64(100_000_000);
+    let update = update_contract(&mut chain, ALICE, staking, "setMaxTotalStaked", &cap)
+        .expect_err("Set the cap as a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    update_contract(&mut chain, ADMIN, staking, "setMaxTotalStaked", &cap).expect("Set the cap");
+
+    stake(&mut chain, token, staking, ALICE, 60_000_000, AdditionalData::empty())
+        .expect("Stake under the cap");
+    let before = snapshot(&chain, staking, BOB);
+    let update = stake(&mut chain, token, staking, BOB, 40_000_001, AdditionalData::empty())
+        .expect_err("Stake over the cap");
+    assert_eq!(hook_error(&update, staking), Error::StakeCapExceeded);
+    assert_eq!(snapshot(&chain, staking, BOB), before);
+    stake(&mut chain, token, staking, BOB, 40_000_000, AdditionalData::empty())
+        .expect("Stake up to the cap");
+    assert_eq!(view(&chain, staking).total_staked, 100_000_000);
+
+    // A cap of 0 is unlimited.
+    update_contract(&mut chain, ADMIN, staking, "setMaxTotalStaked", &TokenAmountU64(0))
+        .expect("Remove the cap");
+    stake(&mut chain, token, staking, BOB, 1, AdditionalData::empty()).expect("Stake");
+}
+
+/// Test that `total_unbonding` equals the sum of the unbonding queues through
+/// unstakes, a slash and completions.
+#[test]
+fn test_total_unbonding() {
+    let Setup { mut chain, token, staking } = initialize();
+    let check = |chain: &Chain, expected: u64| {
+        let queued: u64 = [ALICE, BOB]
+            .iter()
+            .flat_map(|account| stake_info(chain, staking, *account).stake.unbonding)
+            .map(|unbonding| unbonding.amount.0)
+            .sum();
+        assert_eq!(queued, expected);
+        assert_eq!(view(chain, staking).total_unbonding, expected);
+    };
+    let unstake = |chain: &mut Chain, account: AccountAddress, amount: u64| {
+        update_contract(chain, account, staking, "unstake", &UnstakeParams {
+            amount: TokenAmountU64(amount),
+        }).expect("Unstake");
+    };
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 100_000_000, AdditionalData::empty()).expect("Stake");
+
+    unstake(&mut chain, ALICE, 10_000_000);
+    check(&chain, 10_000_000);
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    unstake(&mut chain, BOB, 20_000_000);
+    check(&chain, 30_000_000);
+
+    // The slash takes 10% of the unbonding funds of Bob.
+    update_contract(&mut chain, ADMIN, staking, "slash", &SlashParams {
+        staker: BOB,
+        amount: TokenAmountU64(1_000_000),
+        reason_code: 1,
+    }).expect("Slash");
+    check(&chain, 28_000_000);
+
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD - 24 * 60 * 60))
+        .expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &()).expect("Complete unstake");
+    check(&chain, 18_000_000);
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    update_contract(&mut chain, BOB, staking, "completeUnstake", &()).expect("Complete unstake");
+    check(&chain, 0);
+    assert_eq!(balance_of(&chain, token, BOB_ADDR), 18_000_000);
+}
+
+/// Test that with APR change limits a small change is accepted, while a
+/// large jump and an update too soon after the last one are rejected.
+#[test]
+fn test_apr_change_limits() {
+    let Setup { mut chain, staking, .. } = initialize();
+    let limits = SetAprChangeLimitsParams {
+        max_apr_change_bps: Some(50),
+        min_apr_update_interval: 24 * 60 * 60,
+    };
+    let update = update_contract(&mut chain, ALICE, staking, "setAprChangeLimits", &limits)
+        .expect_err("Set the limits as a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    update_contract(&mut chain, ADMIN, staking, "setAprChangeLimits", &limits)
+        .expect("Set the limits");
+    let update_apr = |chain: &mut Chain, new_apr: u64| {
+        update_contract(chain, ADMIN, staking, "updateApr", &UpdateAprParams { new_apr })
+    };
+
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    let update = update_apr(&mut chain, 190).expect_err("Raise the APR too much");
+    assert_eq!(contract_error(&update), Error::AprChangeTooLarge);
+    update_apr(&mut chain, 189).expect("Raise the APR within the limit");
+    assert_eq!(view_apr(&chain, staking), 189);
+
+    chain.tick_block_time(Duration::from_seconds(24 * 60 * 60 - 1)).expect("Advance time");
+    let update = update_apr(&mut chain, 180).expect_err("Update the APR too soon");
+    assert_eq!(contract_error(&update), Error::AprUpdateTooSoon);
+    chain.tick_block_time(Duration::from_seconds(1)).expect("Advance time");
+    update_apr(&mut chain, 139).expect("Cut the APR within the limit");
+    assert_eq!(view_apr(&chain, staking), 139);
+}
+
+/// Test that `supports` reports support for CIS-0, CIS-2 and CIS-3, and none
+/// for an unknown standard.
+#[test]
+fn test_supports() {
+    let Setup { chain, staking, .. } = initialize();
+    let queries = ["CIS-0", "CIS-2", "CIS-3", "CIS-4"]
+        .iter()
+        .map(|id| StandardIdentifierOwned::new_unchecked(id.to_string()))
+        .collect();
+    let response: SupportsQueryResponse = invoke(&chain, staking, "supports", &SupportsQueryParams {
+        queries,
+    })
+    .parse_return_value()
+    .expect("Parse supports");
+    assert!(matches!(response.results.as_slice(), [
+        SupportResult::Support,
+        SupportResult::Support,
+        SupportResult::Support,
+        SupportResult::NoSupport,
+    ]));
+}
+
+/// Test that the stake event marks a first deposit as new and any later
+/// deposit as a top-up.
+#[test]
+fn test_stake_is_top_up() {
+    let Setup { mut chain, token, staking } = initialize();
+    let is_top_up = |update: &ContractInvokeSuccess| {
+        contract_events(update, staking)
+            .into_iter()
+            .find_map(|event| match event {
+                Event::Staked(event) => Some(event.is_top_up),
+                _ => None,
+            })
+            .expect("Stake event")
+    };
+
+    let update = stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty())
+        .expect("Stake");
+    assert!(!is_top_up(&update));
+
+    let update = stake(&mut chain, token, staking, ALICE, 50_000_000, AdditionalData::empty())
+        .expect("Stake");
+    assert!(is_top_up(&update));
+
+    let update = stake(&mut chain, token, staking, BOB, 50_000_000, AdditionalData::empty())
+        .expect("Stake");
+    assert!(!is_top_up(&update));
+}
+
+/// Test the figures `globalStats` derives from the state, including with no
+/// participants.
+#[test]
+fn test_global_stats() {
+    let Setup { mut chain, token, staking } = initialize();
+    let global_stats = |chain: &Chain| -> GlobalStatsResult {
+        invoke(chain, staking, "globalStats", &()).parse_return_value().expect("Parse stats")
+    };
+    assert_eq!(global_stats(&chain), GlobalStatsResult {
+        apr: 139,
+        apy: 139,
+        utilization: 0,
+        average_stake: TokenAmountU64(0),
+    });
+
+    update_contract(&mut chain, ADMIN, staking, "updateApr", &UpdateAprParams { new_apr: 1_000 })
+        .expect("Update the APR");
+    fund_rewards(&mut chain, token, staking, 40_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 50_000_000, AdditionalData::empty()).expect("Stake");
+
+    // Alice claims 2 EUROe of the 40 funded after a fifth of a year at 10%.
+    chain.tick_block_time(Duration::from_days(73)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect("Claim rewards");
+
+    // 10% compounded daily is 10.5155%.
+    assert_eq!(global_stats(&chain), GlobalStatsResult {
+        apr: 1_000,
+        apy: 1_051,
+        utilization: 500,
+        average_stake: TokenAmountU64(75_000_000),
+    });
+}
+
+/// Test that rewards funded with vesting become claimable linearly over the
+/// vesting period.
+#[test]
+fn test_fund_rewards_vested() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 1_000_000_000, AdditionalData::empty())
+        .expect("Stake");
+
+    // Lock 1 EUROe vesting over 100 days.
+    mint(&mut chain, token, ADMIN_ADDR, 1_000_000);
+    let vest_until = chain.block_time().millis / 1000 + 100 * 24 * 60 * 60;
+    let params = FundRewardsVestedParams { amount: TokenAmountU64(1_000_000), vest_until };
+    update_contract(&mut chain, ADMIN, staking, "fundRewardsVested", &params)
+        .expect("Fund vested rewards");
+    assert_eq!(view(&chain, staking).locked_rewards_pool, 1_000_000);
+    assert_eq!(view(&chain, staking).rewards_pool, 0);
+    let claim = |chain: &mut Chain, amount: u64| {
+        update_contract(chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+            amount: Some(TokenAmountU64(amount)),
+            recipient: None,
+        })
+    };
+
+    // After 40 days only 40% has matured, although Alice earned more.
+    chain.tick_block_time(Duration::from_days(40)).expect("Advance time");
+    assert!(earned_rewards(&chain, staking, ALICE) > 1_000_000);
+    let update = claim(&mut chain, 400_001).expect_err("Claim more than has matured");
+    assert_eq!(contract_error(&update), Error::InsufficientRewardsPool);
+    claim(&mut chain, 400_000).expect("Claim the matured rewards");
+    assert_eq!(view(&chain, staking).locked_rewards_pool, 600_000);
+    assert_eq!(view(&chain, staking).rewards_pool, 0);
+
+    // After 70 days another 30% has matured.
+    chain.tick_block_time(Duration::from_days(30)).expect("Advance time");
+    let update = claim(&mut chain, 300_001).expect_err("Claim more than has matured");
+    assert_eq!(contract_error(&update), Error::InsufficientRewardsPool);
+    claim(&mut chain, 300_000).expect("Claim the matured rewards");
+    assert_eq!(view(&chain, staking).locked_rewards_pool, 300_000);
+
+    // Once vested everything is claimable.
+    chain.tick_block_time(Duration::from_days(30)).expect("Advance time");
+    claim(&mut chain, 300_000).expect("Claim the rest");
+    assert_eq!(view(&chain, staking).locked_rewards_pool, 0);
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 1_000_000);
+}
+
+/// Test that `isNonceUsed` reports the nonce of a permit as used once the
+/// permit is executed, and no later nonces.
+#[test]
+fn test_is_nonce_used() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty())
+        .expect("Stake");
+    let is_nonce_used = |chain: &Chain, nonce: u64| -> bool {
+        invoke(chain, staking, "isNonceUsed", &IsNonceUsedParams { account: ALICE, nonce })
+            .parse_return_value()
+            .expect("Parse nonce used")
+    };
+    assert!(!is_nonce_used(&chain, 0));
+
+    let param = permit(&chain, staking, ALICE, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(10_000_000),
+    });
+    assert_eq!(param.message.nonce, 0);
+    assert!(!is_nonce_used(&chain, 0));
+    update_contract(&mut chain, BOB, staking, "permit", &param).expect("Permit");
+    assert!(is_nonce_used(&chain, 0));
+    assert!(!is_nonce_used(&chain, 1));
+
+    // The nonces of other accounts are independent.
+    let params = IsNonceUsedParams { account: BOB, nonce: 0 };
+    let used: bool = invoke(&chain, staking, "isNonceUsed", &params)
+        .parse_return_value()
+        .expect("Parse nonce used");
+    assert!(!used);
+}
+
+/// Test that a claim within the cooldown after the previous claim is
+/// rejected while unstaking is not, and that claiming works again after it.
+#[test]
+fn test_claim_cooldown() {
+    let Setup { mut chain, token, staking } = initialize();
+    let update = update_contract(&mut chain, ALICE, staking, "setClaimCooldown", &86_400u64)
+        .expect_err("Set the cooldown as a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    update_contract(&mut chain, ADMIN, staking, "setClaimCooldown", &86_400u64)
+        .expect("Set the cooldown");
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    let claim = |chain: &mut Chain| {
+        update_contract(chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+            amount: None,
+            recipient: None,
+        })
+    };
+
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    claim(&mut chain).expect("First claim");
+
+    chain.tick_block_time(Duration::from_hours(1)).expect("Advance time");
+    let update = claim(&mut chain).expect_err("Claim within the cooldown");
+    assert_eq!(contract_error(&update), Error::ClaimCooldownActive);
+
+    // Unstaking still settles the rewards earned within the cooldown.
+    let earned = earned_rewards(&chain, staking, ALICE);
+    assert!(earned > 0);
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(50_000_000),
+    }).expect("Unstake within the cooldown");
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.pending_rewards, earned);
+
+    chain.tick_block_time(Duration::from_hours(23)).expect("Advance time");
+    claim(&mut chain).expect("Claim after the cooldown");
+}
+
+/// Test that unstaking directly and through a permit report the same rewards
+/// moved to pending in the unstake event.
+#[test]
+fn test_unstake_event_paths_agree() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let unstaked_event = |update: &ContractInvokeSuccess| {
+        contract_events(update, staking)
+            .into_iter()
+            .find_map(|event| match event {
+                Event::Unstaked(event) => Some(event),
+                _ => None,
+            })
+            .expect("Unstake event")
+    };
+    let params = UnstakeParams { amount: TokenAmountU64(40_000_000) };
+
+    let update = update_contract(&mut chain, ALICE, staking, "unstake", &params)
+        .expect("Unstake directly");
+    let direct = unstaked_event(&update);
+    let param = permit(&chain, staking, BOB, "unstake", &params);
+    let update = update_contract(&mut chain, ALICE, staking, "permit", &param)
+        .expect("Unstake through a permit");
+    let permitted = unstaked_event(&update);
+
+    assert_eq!(direct.rewards_earned.0, 1_390_000);
+    assert_eq!(permitted.rewards_earned, direct.rewards_earned);
+    assert_eq!(permitted.unstaked_amount, direct.unstaked_amount);
+    assert_eq!(permitted.total_staked_after.0, 120_000_000);
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.pending_rewards, 1_390_000);
+    assert_eq!(stake_info(&chain, staking, BOB).stake.pending_rewards, 1_390_000);
+}
+
+/// Test that `maturedUnbonding` returns only the stakers with matured
+/// unbonding entries, with the total matured amount of each.
+#[test]
+fn test_matured_unbonding() {
+    let Setup { mut chain, token, staking } = initialize();
+    let stakers: Vec<AccountAddress> = (10..14).map(|i| AccountAddress([i; 32])).collect();
+    for staker in &stakers {
+        chain.create_account(account(*staker));
+        stake(&mut chain, token, staking, *staker, 1_000_000, AdditionalData::empty())
+            .expect("Stake");
+    }
+    let unstake = |chain: &mut Chain, staker: AccountAddress, amount: u64| {
+        update_contract(chain, staker, staking, "unstake", &UnstakeParams {
+            amount: TokenAmountU64(amount),
+        })
+        .expect("Unstake");
+    };
+    let matured = |chain: &Chain, skip: u64, take: u64| -> Vec<(AccountAddress, TokenAmountU64)> {
+        invoke(chain, staking, "maturedUnbonding", &PageParams { skip, take })
+            .parse_return_value()
+            .expect("Parse matured unbonding")
+    };
+
+    // The entries unlock after 7, 9, 9 and 12 days.
+    unstake(&mut chain, stakers[0], 100_000);
+    unstake(&mut chain, stakers[1], 200_000);
+    chain.tick_block_time(Duration::from_days(2)).expect("Advance time");
+    unstake(&mut chain, stakers[1], 300_000);
+    unstake(&mut chain, stakers[2], 400_000);
+    chain.tick_block_time(Duration::from_days(3)).expect("Advance time");
+    unstake(&mut chain, stakers[3], 500_000);
+    assert!(matured(&chain, 0, 10).is_empty());
+
+    chain.tick_block_time(Duration::from_days(3)).expect("Advance time");
+    assert_eq!(matured(&chain, 0, 10), vec![
+        (stakers[0], TokenAmountU64(100_000)),
+        (stakers[1], TokenAmountU64(200_000)),
+    ]);
+
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    assert_eq!(matured(&chain, 0, 10), vec![
+        (stakers[0], TokenAmountU64(100_000)),
+        (stakers[1], TokenAmountU64(500_000)),
+        (stakers[2], TokenAmountU64(400_000)),
+    ]);
+    assert_eq!(matured(&chain, 1, 1), vec![(stakers[1], TokenAmountU64(500_000))]);
+}
+
+/// Test that the data from `expectedStakeData` parses as a stake instruction
+/// and stakes when attached to a transfer, while other data is rejected.
+#[test]
+fn test_expected_stake_data() {
+    let Setup { mut chain, token, staking } = initialize();
+    let expected_stake_data = |chain: &Chain, deposit_kind: &DepositKind| {
+        chain.contract_invoke(ALICE, ALICE_ADDR, ENERGY, UpdateContractPayload {
+            amount: Amount::zero(),
+            address: staking,
+            receive_name: receive_name(chain, staking, "expectedStakeData"),
+            message: OwnedParameter::from_serial(deposit_kind).expect("Serialize parameter"),
+        })
+    };
+    let deposit_kind = DepositKind::Stake(StakeData { lock_days: 0, beneficiary: Some(BOB) });
+    let data: AdditionalData = expected_stake_data(&chain, &deposit_kind)
+        .expect("Get the stake data")
+        .parse_return_value()
+        .expect("Parse stake data");
+    let parsed: DepositKind = from_bytes(data.as_ref()).expect("Parse deposit kind");
+    assert!(matches!(
+        parsed,
+        DepositKind::Stake(StakeData { lock_days: 0, beneficiary: Some(beneficiary) })
+            if beneficiary == BOB
+    ));
+
+    let deposit_kind = DepositKind::Stake(StakeData { lock_days: 5, beneficiary: None });
+    let update = expected_stake_data(&chain, &deposit_kind).expect_err("Unknown lock tier");
+    assert_eq!(contract_error(&update), Error::InvalidLockTier);
+
+    // The data stakes for Bob when attached to a transfer of Alice.
+    mint(&mut chain, token, ALICE_ADDR, 1_000_000);
+    let transfer = |data: AdditionalData| concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(1_000_000),
+        from: ALICE_ADDR,
+        to: Receiver::Contract(
+            staking,
+            OwnedEntrypointName::new_unchecked("onReceivingCIS2".into())
+        ),
+        data,
+    };
+    update_contract(&mut chain, ALICE, token, "transfer", &TransferParams::from(vec![
+        transfer(data.clone()),
+    ])).expect("Stake with the expected data");
+    assert_eq!(stake_info(&chain, staking, BOB).stake.amount, 1_000_000);
+
+    // Missing or malformed data is rejected rather than kept.
+    mint(&mut chain, token, ALICE_ADDR, 1_000_000);
+    for data in [AdditionalData::empty(), AdditionalData::from(vec![7u8])] {
+        let update = update_contract(&mut chain, ALICE, token, "transfer", &TransferParams::from(
+            vec![transfer(data)]
+        )).expect_err("Transfer without valid data");
+        assert_eq!(hook_error(&update, staking), Error::UnknownDeposit);
+    }
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 1_000_000);
+}
+
+/// Test that a staker can hold at most 32 unbonding entries and that
+/// completing sums all matured entries.
+#[test]
+fn test_unbonding_entry_cap() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 1_000_000, AdditionalData::empty()).expect("Stake");
+    let unstake = |chain: &mut Chain, amount: u64| {
+        update_contract(chain, ALICE, staking, "unstake", &UnstakeParams {
+            amount: TokenAmountU64(amount),
+        })
+    };
+
+    for i in 1..=32 {
+        unstake(&mut chain, i * 1_000).expect("Unstake");
+        chain.tick_block_time(Duration::from_hours(1)).expect("Advance time");
+    }
+    let update = unstake(&mut chain, 1_000).expect_err("Unstake beyond the cap");
+    assert_eq!(contract_error(&update), Error::TooManyUnbonding);
+
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect("Complete unstake");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 528_000);
+    assert_eq!(view(&chain, staking).total_unbonding, 0);
+    assert!(stake_info(&chain, staking, ALICE).stake.unbonding.is_empty());
+
+    // Completing frees the entries for new unstakes.
+    unstake(&mut chain, 1_000).expect("Unstake after completing");
+}
+
+/// Test that a frozen account cannot claim, directly or through a permit, and
+/// can claim again once unfrozen.
+#[test]
+fn test_frozen_account_claim() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let set_frozen = |chain: &mut Chain, sender: AccountAddress, frozen: bool| {
+        update_contract(chain, sender, staking, "setFrozen", &SetFrozenParams {
+            account: ALICE,
+            frozen,
+        })
+    };
+    let claim = ClaimRewardsParams { amount: None, recipient: None };
+
+    let update = set_frozen(&mut chain, BOB, true).expect_err("Freeze as a non-admin");
+    assert_eq!(contract_error(&update), Error::OnlyAdmin);
+    let update = set_frozen(&mut chain, ADMIN, true).expect("Freeze");
+    assert!(contract_events(&update, staking)
+        .iter()
+        .any(|event| matches!(event, Event::Frozen(event) if event.account == ALICE)));
+
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &claim)
+        .expect_err("Claim while frozen");
+    assert_eq!(contract_error(&update), Error::AccountFrozen);
+    let param = permit(&chain, staking, ALICE, "claimRewards", &claim);
+    let update = update_contract(&mut chain, BOB, staking, "permit", &param)
+        .expect_err("Claim through a permit while frozen");
+    assert_eq!(contract_error(&update), Error::AccountFrozen);
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 0);
+
+    let update = set_frozen(&mut chain, ADMIN, false).expect("Unfreeze");
+    assert!(contract_events(&update, staking)
+        .iter()
+        .any(|event| matches!(event, Event::Unfrozen(event) if event.account == ALICE)));
+    update_contract(&mut chain, ALICE, staking, "claimRewards", &claim)
+        .expect("Claim once unfrozen");
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 1_390_000);
+}
+
+/// Test that `topStakers` returns the largest stakes in descending order,
+/// breaking ties by account address and truncating at `n`.
+#[test]
+fn test_top_stakers() {
+    let Setup { mut chain, token, staking } = initialize();
+    let stakers: Vec<AccountAddress> = (10..15).map(|i| AccountAddress([i; 32])).collect();
+    for (staker, amount) in stakers.iter().zip([3_000, 5_000, 1_000, 5_000, 2_000]) {
+        chain.create_account(account(*staker));
+        stake(&mut chain, token, staking, *staker, amount, AdditionalData::empty())
+            .expect("Stake");
+    }
+    let top_stakers = |n: u16| -> Vec<(AccountAddress, TokenAmountU64)> {
+        invoke(&chain, staking, "topStakers", &n).parse_return_value().expect("Parse top stakers")
+    };
+
+    assert_eq!(top_stakers(3), vec![
+        (stakers[1], TokenAmountU64(5_000)),
+        (stakers[3], TokenAmountU64(5_000)),
+        (stakers[0], TokenAmountU64(3_000)),
+    ]);
+    assert_eq!(top_stakers(10), vec![
+        (stakers[1], TokenAmountU64(5_000)),
+        (stakers[3], TokenAmountU64(5_000)),
+        (stakers[0], TokenAmountU64(3_000)),
+        (stakers[4], TokenAmountU64(2_000)),
+        (stakers[2], TokenAmountU64(1_000)),
+    ]);
+    assert!(top_stakers(0).is_empty());
+}
+
+/// Test that `getEarnedRewards` includes the rewards banked by a top-up and
+/// agrees with `getStakeInfo`.
+#[test]
+fn test_earned_rewards_include_pending() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+
+    // The top-up banks the rewards earned so far.
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty())
+        .expect("Top up");
+    assert_eq!(stake_info(&chain, staking, ALICE).stake.pending_rewards, 1_390_000);
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 1_390_000);
+
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    assert_eq!(earned_rewards(&chain, staking, ALICE), 1_390_000 + 2_780_000);
+    assert_eq!(
+        earned_rewards(&chain, staking, ALICE),
+        stake_info(&chain, staking, ALICE).stake.pending_rewards
+    );
+}
+
+/// Test that `unstakeAll` moves the whole stake into unbonding and keeps the
+/// rewards earned so far pending.
+#[test]
+fn test_unstake_all() {
+    let Setup { mut chain, token, staking } = initialize();
+    let update = update_contract(&mut chain, ALICE, staking, "unstakeAll", &())
+        .expect_err("Unstake all without a stake");
+    assert_eq!(contract_error(&update), Error::NoStakeFound);
+
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    stake(&mut chain, token, staking, BOB, 50_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+
+    let update = update_contract(&mut chain, ALICE, staking, "unstakeAll", &())
+        .expect("Unstake all");
+    let unbonding: UnbondingInfo = update.parse_return_value().expect("Parse unbonding");
+    let now = chain.block_time().millis / 1000;
+    assert_eq!(unbonding, UnbondingInfo {
+        amount: TokenAmountU64(100_000_000),
+        unlock_time: now + UNBONDING_PERIOD,
+    });
+
+    let info = stake_info(&chain, staking, ALICE);
+    assert_eq!(info.stake.amount, 0);
+    assert_eq!(info.unbonding_amount, 100_000_000);
+    assert_eq!(info.stake.unbonding, vec![unbonding]);
+    assert_eq!(info.stake.pending_rewards, 1_390_000);
+    assert_eq!(view(&chain, staking).total_staked, 50_000_000);
+
+    let update = update_contract(&mut chain, ALICE, staking, "unstakeAll", &())
+        .expect_err("Unstake all again");
+    assert_eq!(contract_error(&update), Error::NoStakeFound);
+}
+
+/// Test the receipts returned by `stake`, `unstake` and `claimRewards`.
+#[test]
+fn test_mutation_receipts() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+
+    // The hook is called by the token, so its receipt is read by invoking it
+    // as the token.
+    let params = OnReceivingCis2Params {
+        token_id: TokenIdVec(Vec::new()),
+        amount: TokenAmountU64(50_000_000),
+        from: ALICE_ADDR,
+        data: AdditionalData::empty(),
+    };
+    let staked: TokenAmountU64 = chain
+        .contract_invoke(ALICE, Address::Contract(token), ENERGY, UpdateContractPayload {
+            amount: Amount::zero(),
+            address: staking,
+            receive_name: receive_name(&chain, staking, "stake"),
+            message: OwnedParameter::from_serial(&params).expect("Serialize parameter"),
+        })
+        .expect("Stake as the token")
+        .parse_return_value()
+        .expect("Parse staked amount");
+    assert_eq!(staked, TokenAmountU64(150_000_000));
+
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    let update = update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(40_000_000),
+    }).expect("Unstake");
+    let unbonding: UnbondingInfo = update.parse_return_value().expect("Parse unbonding");
+    assert_eq!(unbonding, UnbondingInfo {
+        amount: TokenAmountU64(40_000_000),
+        unlock_time: chain.block_time().millis / 1000 + UNBONDING_PERIOD,
+    });
+
+    let update = update_contract(&mut chain, ALICE, staking, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    }).expect("Claim rewards");
+    let claimed: TokenAmountU64 = update.parse_return_value().expect("Parse claimed rewards");
+    assert_eq!(claimed, TokenAmountU64(1_390_000));
+    assert_eq!(balance_of(&chain, token, ALICE_ADDR), 1_390_000);
+}
+
+/// Test that `hashPermitMessage` produces the same hash as `viewMessageHash`
+/// for the same signer and message.
+#[test]
+fn test_hash_permit_message() {
+    let Setup { chain, staking, .. } = initialize();
+    let hash_permit_message = |signer: AccountAddress, message: PermitMessage| -> [u8; 32] {
+        invoke(&chain, staking, "hashPermitMessage", &HashPermitMessageParams { signer, message })
+            .parse_return_value()
+            .expect("Parse message hash")
+    };
+    let unstake = UnstakeParams { amount: TokenAmountU64(1_000) };
+
+    let param = permit(&chain, staking, ALICE, "unstake", &unstake);
+    let expected = view_message_hash(&chain, staking, &param);
+    assert_eq!(hash_permit_message(ALICE, param.message), expected);
+
+    // The signer is part of the hash.
+    let param = permit(&chain, staking, ALICE, "unstake", &unstake);
+    assert_ne!(hash_permit_message(BOB, param.message), expected);
+
+    let param = permit(&chain, staking, BOB, "claimRewards", &ClaimRewardsParams {
+        amount: None,
+        recipient: None,
+    });
+    let expected = view_message_hash(&chain, staking, &param);
+    assert_eq!(hash_permit_message(BOB, param.message), expected);
+}
+
+/// Test that unstaking zero is rejected for a staker left with only pending
+/// rewards, and that the participant count stays right afterwards.
+#[test]
+fn test_unstake_zero_with_pending_rewards() {
+    let Setup { mut chain, token, staking } = initialize();
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    chain.tick_block_time(Duration::from_days(365)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "unstakeAll", &()).expect("Unstake all");
+    chain.tick_block_time(Duration::from_seconds(UNBONDING_PERIOD)).expect("Advance time");
+    update_contract(&mut chain, ALICE, staking, "completeUnstake", &CompleteUnstakeParams {
+        index: None,
+    }).expect("Complete unstake");
+    let info = stake_info(&chain, staking, ALICE);
+    assert_eq!(info.stake.amount, 0);
+    assert!(info.stake.unbonding.is_empty());
+    assert_eq!(info.stake.pending_rewards, 1_390_000);
+    assert_eq!(view(&chain, staking).total_participants, 0);
+
+    let update = update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(0),
+    }).expect_err("Unstake zero");
+    assert_eq!(contract_error(&update), Error::InvalidUnstakeAmount);
+    assert!(stake_info(&chain, staking, ALICE).stake.unbonding.is_empty());
+
+    stake(&mut chain, token, staking, ALICE, 1_000_000, AdditionalData::empty()).expect("Stake");
+    assert_eq!(view(&chain, staking).total_participants, 1);
+}
+
+/// Test that restaking rewards and cancelling an unbonding entry are rejected
+/// when they would raise the total stake past the cap.
+#[test]
+fn test_max_total_staked_restake_and_cancel() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    update_contract(&mut chain, ALICE, staking, "unstake", &UnstakeParams {
+        amount: TokenAmountU64(10_000_000),
+    }).expect("Unstake");
+    stake(&mut chain, token, staking, BOB, 10_000_000, AdditionalData::empty()).expect("Stake");
+    let cap = TokenAmountU64(100_000_000);
+    update_contract(&mut chain, ADMIN, staking, "setMaxTotalStaked", &cap).expect("Set the cap");
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+
+    let cancel = CancelUnbondingParams { index: 0 };
+    let before = snapshot(&chain, staking, ALICE);
+    let update = update_contract(&mut chain, ALICE, staking, "restake", &())
+        .expect_err("Restake at the cap");
+    assert_eq!(contract_error(&update), Error::StakeCapExceeded);
+    let update = update_contract(&mut chain, ALICE, staking, "cancelUnbonding", &cancel)
+        .expect_err("Cancel unbonding at the cap");
+    assert_eq!(contract_error(&update), Error::StakeCapExceeded);
+    assert_eq!(snapshot(&chain, staking, ALICE), before);
+
+    // Both go through once the cap leaves room.
+    let cap = TokenAmountU64(120_000_000);
+    update_contract(&mut chain, ADMIN, staking, "setMaxTotalStaked", &cap).expect("Raise the cap");
+    update_contract(&mut chain, ALICE, staking, "restake", &()).expect("Restake");
+    update_contract(&mut chain, ALICE, staking, "cancelUnbonding", &cancel)
+        .expect("Cancel unbonding");
+}
+
+/// Test that restaking rewards into an empty stake and cancelling a small
+/// unbonding entry are rejected below the minimum stake.
+#[test]
+fn test_minimum_stake_restake_and_cancel() {
+    let Setup { mut chain, token, staking } = initialize();
+    fund_rewards(&mut chain, token, staking, 10_000_000);
+    stake(&mut chain, token, staking, ALICE, 100_000_000, AdditionalData::empty()).expect("Stake");
+    let unstake = |chain: &mut Chain, amount: u64| {
+        update_contract(chain, ALICE, staking, "unstake", &UnstakeParams {
+            amount: TokenAmountU64(amount),
+        })
+        .expect("Unstake");
+    };
+    chain.tick_block_time(Duration::from_days(1)).expect("Advance time");
+    unstake(&mut chain, 1_000);
+    unstake(&mut chain, 99_999_000);
+    let minimum = TokenAmountU64(10_000_000);
+    update_contract(&mut chain, ADMIN, staking, "setMinimumStake", &minimum)
+        .expect("Set the minimum");
+
+    // Alice has nothing staked, so her rewards or the small entry alone would
+    // leave a dust position.
+    let before = snapshot(&chain, staking, ALICE);
+    let update = update_contract(&mut chain, ALICE, staking, "restake", &())
+        .expect_err("Restake below the minimum");
+    assert_eq!(contract_error(&update), Error::BelowMinimumStake);
+    let cancel = |index: u32| CancelUnbondingParams { index };
+    let update = update_contract(&mut chain, ALICE, staking, "cancelUnbonding", &cancel(0))
+        .expect_err("Cancel unbonding below the minimum");
+    assert_eq!(contract_error(&update), Error::BelowMinimumStake);
+    assert_eq!(snapshot(&chain, staking, ALICE), before);
+
+    // Cancelling the large entry reaches the minimum, after which both work.
+    update_contract(&mut chain, ALICE, staking, "cancelUnbonding", &cancel(1))
+        .expect("Cancel unbonding above the minimum");
+    update_contract(&mut chain, ALICE, staking, "cancelUnbonding", &cancel(0))
+        .expect("Cancel unbonding into a stake above the minimum");
+    update_contract(&mut chain, ALICE, staking, "restake", &()).expect("Restake");
+}
+
+/// Helper method for initializing the contracts.
+///
+/// Does the following:
+///  - Creates the [`Chain`]
+///  - Creates the admin, Alice and Bob accounts with `10_000` CCD each.
+///  - Deploys and initializes the mock EUROe token and the staking contract.
+fn initialize() -> Setup {
+    let mut chain = Chain::new();
+    chain.create_account(account(ADMIN));
+    chain.create_account(account(ALICE));
+    chain.create_account(account(BOB));
+
+    let token = init_module(
+        &mut chain,
+        "tests/euroe-mock/Cargo.toml",
+        "init_euroe_mock",
+        OwnedParameter::empty()
+    );
+
+    let params = InitContractParams {
+        admin: ADMIN,
+        token_address: token,
+        unbonding_period: UNBONDING_PERIOD,
+        slashing_rate: 1_000,
+    };
+    let staking = init_module(
+        &mut chain,
+        "Cargo.toml",
+        "init_concordium_staking",
+        OwnedParameter::from_serial(&params).expect("Parameter within size bounds")
+    );
+
+    Setup { chain, token, staking }
+}
+
+/// Builds an account at `address` with the initial balance and the keys of
+/// [`account_keys`], so that it can sign permits.
+fn account(address: AccountAddress) -> Account {
+    let balance = AccountBalance::new(ACC_INITIAL_BALANCE, Amount::zero(), Amount::zero())
+        .expect("Valid balance");
+    Account::new_with_keys(address, balance, account_keys(address))
+}
+
+/// Builds the module of the crate `name` at `manifest` and returns the path of
+/// the Wasm file.
+///
+/// The modules are built into their own target directory, since the one of
+/// the running `cargo test` is locked.
+fn build_module(manifest: &str, name: &str) -> PathBuf {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let target_dir = root.join("target").join("wasm-tests");
+    let status = Command::new("cargo")
+        .args(["+nightly", "build", "-Zbuild-std=std,panic_abort", "--release"])
+        .args(["--target", "wasm32-unknown-unknown", "--manifest-path"])
+        .arg(root.join(manifest))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .env("RUSTFLAGS", "-C target-cpu=mvp")
+        .status()
+        .expect("Run cargo build");
+    assert!(status.success(), "Building {manifest} for wasm32-unknown-unknown failed");
+    target_dir.join("wasm32-unknown-unknown").join("release").join(format!("{name}.wasm"))
+}
+
+/// Gets the Wasm file of the module of the crate at `manifest`, building the
+/// modules on first use.
+fn module_path(manifest: &str) -> PathBuf {
+    static MODULES: OnceLock<Vec<PathBuf>> = OnceLock::new();
+    let modules = MODULES.get_or_init(|| {
+        CRATES.iter().map(|(manifest, name)| build_module(manifest, name)).collect()
+    });
+    let index = CRATES.iter().position(|(crate_manifest, _)| *crate_manifest == manifest);
+    modules[index.expect("Crate of a test module")].clone()
+}
+
+/// Deploys the module of the crate at `manifest`.
+fn deploy_module(chain: &mut Chain, manifest: &str) -> ModuleReference {
+    let module = module_load_v1_raw(module_path(manifest)).expect("Module exists at path");
+    chain.module_deploy_v1(SIGNER, ADMIN, module).expect("Deploy valid module").module_reference
+}
+
+/// Deploys the module of the crate at `manifest` and initializes a contract
+/// instance of it.
+fn init_module(
+    chain: &mut Chain,
+    manifest: &str,
+    init_name: &str,
+    param: OwnedParameter
+) -> ContractAddress {
+    let mod_ref = deploy_module(chain, manifest);
+    chain
+        .contract_init(SIGNER, ADMIN, ENERGY, InitContractPayload {
+            amount: Amount::zero(),
+            mod_ref,
+            init_name: OwnedContractName::new_unchecked(init_name.to_string()),
+            param,
+        })
+        .expect("Initializing contract")
+        .contract_address
+}
+
+/// Updates `entrypoint` of `contract` as `invoker`.
+fn update_contract<P: concordium_std::Serial>(
+    chain: &mut Chain,
+    invoker: AccountAddress,
+    contract: ContractAddress,
+    entrypoint: &str,
+    parameter: &P
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    let payload = UpdateContractPayload {
+        amount: Amount::zero(),
+        address: contract,
+        receive_name: receive_name(chain, contract, entrypoint),
+        message: OwnedParameter::from_serial(parameter).expect("Parameter within size bounds"),
+    };
+    chain.contract_update(SIGNER, invoker, Address::Account(invoker), ENERGY, payload)
+}
+
+/// Invokes the view `entrypoint` of `contract` without changing the chain.
+fn invoke<P: concordium_std::Serial>(
+    chain: &Chain,
+    contract: ContractAddress,
+    entrypoint: &str,
+    parameter: &P
+) -> ContractInvokeSuccess {
+    chain
+        .contract_invoke(ADMIN, ADMIN_ADDR, ENERGY, UpdateContractPayload {
+            amount: Amount::zero(),
+            address: contract,
+            receive_name: receive_name(chain, contract, entrypoint),
+            message: OwnedParameter::from_serial(parameter).expect("Parameter within size bounds"),
+        })
+        .expect("Invoke view")
+}
+
+/// Builds the receive name of `entrypoint` of `contract`.
+fn receive_name(chain: &Chain, contract: ContractAddress, entrypoint: &str) -> OwnedReceiveName {
+    let name = chain.get_contract(contract).expect("Contract exists").contract_name.clone();
+    OwnedReceiveName::construct(name.as_contract_name(), EntrypointName::new_unchecked(entrypoint))
+        .expect("Valid receive name")
+}
+
+/// Mints `amount` of the mock token to `owner`.
+fn mint(chain: &mut Chain, token: ContractAddress, owner: Address, amount: u64) {
+    update_contract(chain, ADMIN, token, "mint", &MintParams {
+        owner,
+        amount: TokenAmountU64(amount),
+    }).expect("Mint");
+}
+
+/// Builds a transfer of `amount` receipt tokens from `from` to `to`.
+fn receipt_transfer(
+    from: AccountAddress,
+    to: AccountAddress,
+    amount: u64
+) -> concordium_cis2::Transfer<TokenIdUnit, TokenAmountU64> {
+    concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(amount),
+        from: Address::Account(from),
+        to: Receiver::Account(to),
+        data: AdditionalData::empty(),
+    }
+}
+
+/// Gets the CIS-2 token balance of `address` at `token`.
+fn balance_of(chain: &Chain, token: ContractAddress, address: Address) -> u64 {
+    let query = BalanceOfQueryParams {
+        queries: vec![BalanceOfQuery {
+            token_id: TokenIdUnit(),
+            address,
+        }],
+    };
+    let response: BalanceOfQueryResponse<TokenAmountU64> = invoke(chain, token, "balanceOf", &query)
+        .parse_return_value()
+        .expect("Parse balance");
+    response.0[0].0
+}
+
+/// Gets the events logged by `contract` in `update`. Standard events are
+/// logged as they are, custom events with a sequence number.
+fn contract_events(update: &ContractInvokeSuccess, contract: ContractAddress) -> Vec<Event> {
+    logged_events(update, contract)
+        .map(|event| {
+            if event.as_ref()[0] >= 250 {
+                event.parse().expect("Deserialize standard event")
+            } else {
+                event.parse::<SequencedEvent>().expect("Deserialize event").event
+            }
+        })
+        .collect()
+}
+
+/// Gets the custom events logged by `contract` in `update` with their
+/// sequence numbers.
+fn sequenced_events(
+    update: &ContractInvokeSuccess,
+    contract: ContractAddress
+) -> Vec<SequencedEvent> {
+    logged_events(update, contract)
+        .filter(|event| event.as_ref()[0] < 250)
+        .map(|event| event.parse().expect("Deserialize event"))
+        .collect()
+}
+
+/// Gets the raw events logged by `contract` in `update`.
+fn logged_events(
+    update: &ContractInvokeSuccess,
+    contract: ContractAddress
+) -> impl Iterator<Item = &ContractEvent> {
+    update
+        .events()
+        .filter(move |(address, _)| *address == contract)
+        .flat_map(|(_, events)| events.iter())
+}
+
+/// Gets the current APR of the staking contract.
+fn view_apr(chain: &Chain, staking: ContractAddress) -> u64 {
+    let view: ViewResult = invoke(chain, staking, "view", &())
+        .parse_return_value()
+        .expect("Parse view");
+    view.apr
+}
+
+/// Gets the stake of `account`.
+fn stake_info(chain: &Chain, staking: ContractAddress, account: AccountAddress) -> StakeInfoResult {
+    invoke(chain, staking, "getStakeInfo", &account)
+        .parse_return_value()
+        .expect("Parse stake info")
+}
+
+/// Mints `amount` of the mock token to `account` and stakes it with `data`
+/// as the additional data of the transfer.
+fn stake(
+    chain: &mut Chain,
+    token: ContractAddress,
+    staking: ContractAddress,
+    account: AccountAddress,
+    amount: u64,
+    data: AdditionalData
+) -> Result<ContractInvokeSuccess, ContractInvokeError> {
+    mint(chain, token, Address::Account(account), amount);
+    let transfer = concordium_cis2::Transfer {
+        token_id: TokenIdUnit(),
+        amount: TokenAmountU64(amount),
+        from: Address::Account(account),
+        to: Receiver::Contract(staking, OwnedEntrypointName::new_unchecked("stake".into())),
+        data,
+    };
+    update_contract(chain, account, token, "transfer", &TransferParams::from(vec![transfer]))
+}
+
+/// Mints `amount` of the mock token to the admin and funds the rewards pool
+/// with it.
+fn fund_rewards(chain: &mut Chain, token: ContractAddress, staking: ContractAddress, amount: u64) {
+    mint(chain, token, ADMIN_ADDR, amount);
+    update_contract(chain, ADMIN, staking, "fundRewards", &TokenAmountU64(amount))
+        .expect("Fund rewards");
+}
+
+/// Gets the error the staking contract rejected `update` with.
+fn contract_error(update: &ContractInvokeError) -> Error {
+    from_bytes(update.return_value().expect("Rejected by the contract")).expect("Parse error")
+}
+
+/// Gets the rewards of `account` accrued so far.
+fn earned_rewards(chain: &Chain, staking: ContractAddress, account: AccountAddress) -> u64 {
+    invoke(chain, staking, "getEarnedRewards", &account)
+        .parse_return_value()
+        .expect("Parse earned rewards")
+}
+
+/// Gets the view of the staking contract.
+fn view(chain: &Chain, staking: ContractAddress) -> ViewResult {
+    invoke(chain, staking, "view", &()).parse_return_value().expect("Parse view")
+}
+
+
+/// Gets the error the staking contract rejected with when it was called by
+/// the token contract in `update`.
+fn hook_error(update: &ContractInvokeError, staking: ContractAddress) -> Error {
+    find_rejection(&update.trace_elements, staking).expect("Rejected by the staking contract")
+}
+
+/// Finds the rejection of `contract` in the failed calls of `elements`.
+fn find_rejection(elements: &[DebugTraceElement], contract: ContractAddress) -> Option<Error> {
+    elements.iter().find_map(|element| match element {
+        DebugTraceElement::WithFailures {
+            contract_address,
+            error: InvokeExecutionError::Reject { return_value, .. },
+            ..
+        } if *contract_address == contract => from_bytes(return_value).ok(),
+        DebugTraceElement::WithFailures { trace_elements, .. } => {
+            find_rejection(trace_elements, contract)
+        }
+        _ => None,
+    })
+}
+
+
+/// Gets the serialized view of the staking contract and the stake of
+/// `account`, to compare the state before and after an update.
+fn snapshot(chain: &Chain, staking: ContractAddress, account: AccountAddress) -> Vec<Vec<u8>> {
+    vec![
+        invoke(chain, staking, "view", &()).return_value,
+        invoke(chain, staking, "getStakeInfo", &account).return_value,
+    ]
+}
+
+/// Gets the signing key of `account`, which is derived from its address.
+fn signing_key(account: AccountAddress) -> ed25519::SigningKey {
+    ed25519::SigningKey::from_bytes(&account.0)
+}
+
+/// Builds the keys of `account`, a single key from [`signing_key`].
+fn account_keys(account: AccountAddress) -> AccountAccessStructure {
+    let key = VerifyKey::Ed25519VerifyKey(signing_key(account).verifying_key());
+    AccountAccessStructure {
+        keys: BTreeMap::from([(CredentialIndex { index: 0 }, CredentialPublicKeys {
+            keys: BTreeMap::from([(KeyIndex(0), key)]),
+            threshold: SignatureThreshold::ONE,
+        })]),
+        threshold: AccountThreshold::ONE,
+    }
+}
+
+/// Signs `message_hash` with the key of `account`.
+fn sign(account: AccountAddress, message_hash: &[u8; 32]) -> AccountSignatures {
+    let signature = signing_key(account).sign(message_hash).to_bytes();
+    AccountSignatures {
+        sigs: BTreeMap::from([(0, CredentialSignatures {
+            sigs: BTreeMap::from([(0, Signature::Ed25519(SignatureEd25519(signature)))]),
+        })]),
+    }
+}
+
+/// Builds a permit of `signer` for `entry_point` with `payload`, signed with
+/// the key of `signer` and valid for an hour.
+fn permit<P: concordium_std::Serial>(
+    chain: &Chain,
+    staking: ContractAddress,
+    signer: AccountAddress,
+    entry_point: &str,
+    payload: &P
+) -> PermitParam {
+    let mut param = PermitParam {
+        signature: AccountSignatures { sigs: BTreeMap::new() },
+        signer,
+        message: PermitMessage {
+            contract_address: staking,
+            nonce: user_nonce(chain, staking, signer),
+            timestamp: chain
+                .block_time()
+                .checked_add(Duration::from_hours(1))
+                .expect("Timestamp in range"),
+            entry_point: OwnedEntrypointName::new_unchecked(entry_point.to_string()),
+            payload: to_bytes(payload),
+        },
+    };
+    param.signature = sign(signer, &view_message_hash(chain, staking, &param));
+    param
+}
+
+/// Gets the hash of the message of `param` to sign.
+fn view_message_hash(chain: &Chain, staking: ContractAddress, param: &PermitParam) -> [u8; 32] {
+    invoke(chain, staking, "viewMessageHash", param)
+        .parse_return_value()
+        .expect("Parse message hash")
+}
+
+/// Gets the permit nonce of `account`.
+fn user_nonce(chain: &Chain, staking: ContractAddress, account: AccountAddress) -> u64 {
+    invoke(chain, staking, "getUserNonce", &account).parse_return_value().expect("Parse nonce")
+}
+
+/// Initializes another instance of the module of `staking` with `params`,
+/// owned by the admin account.
+fn init_staking(
+    chain: &mut Chain,
+    staking: ContractAddress,
+    params: &InitContractParams
+) -> Result<ContractAddress, ContractInitError> {
+    let mod_ref = chain.get_contract(staking).expect("Contract exists").module_reference;
+    chain
+        .contract_init(SIGNER, ADMIN, ENERGY, InitContractPayload {
+            amount: Amount::zero(),
+            mod_ref,
+            init_name: OwnedContractName::new_unchecked("init_concordium_staking".to_string()),
+            param: OwnedParameter::from_serial(params).expect("Parameter within size bounds"),
+        })
+        .map(|init| init.contract_address)
+}
+
+/// Gets the reason an initialization was rejected with.
+fn init_reject_reason(init: ContractInitError) -> i32 {
+    let ContractInitErrorKind::ExecutionError {
+        error: InitExecutionError::Reject { reason, .. },
+        ..
+    } = init.kind else {
+        panic!("Initialization was not rejected");
+    };
+    reason
+}
+
+/// Gets the reject reason of `error`.
+fn reject_reason(error: Error) -> i32 {
+    concordium_std::Reject::from(error).error_code.get()
+}
@@ -1,7 +1,6 @@
 import { MAX_CONTRACT_EXECUTION_ENERGY, MICRO_CCD } from "@/config";
 import { useWallet } from "@/provider/WalletProvider";
 import { useStateProvider } from "@/provider/StateProvider";
-import { moduleSchemaFromBase64 } from "@concordium/react-components";
 import {
   AccountTransactionType,
   CcdAmount,
@@ -33,7 +32,9 @@ const FundContract = () => {
         throw new Error("Wallet not connected");
       }
 
-      // Step 1: Transfer EUROe to the contract
+      // Transfer EUROe to the contract. The data is the serialized
+      // `DepositKind::FundRewards`, so the contract adds the tokens to the
+      // rewards pool when they arrive.
       const transferParameter = [{
         from: {
           type: "Account",
@@ -44,11 +45,11 @@ const FundContract = () => {
           content: [ContractAddress.create(contract.index), "onReceivingCIS2"],
         },
         amount: BigInt(amount * MICRO_CCD),
-        data: "0000",
+        data: "01",
         token_id: "",
       }] as EUROe.TransferParameter;
 
-      const transaction1 = await connection.signAndSendTransaction(
+      const transaction = await connection.signAndSendTransaction(
         account,
         AccountTransactionType.Update,
         {
@@ -63,38 +64,8 @@ const FundContract = () => {
         EUROe.createTransferParameterWebWallet(transferParameter)
       );
 
-      if (transaction1 && rpc) {
-        await rpc.waitForTransactionFinalization(TransactionHash.fromHexString(transaction1));
-      }
-
-      // Step 2: Update contract state
-      const contract_schema = await rpc.getEmbeddedSchema(contract.sourceModule);
-      
-      const transaction2 = await connection.signAndSendTransaction(
-        account,
-        AccountTransactionType.Update,
-        {
-          address: ContractAddress.create(contract.index),
-          receiveName: ReceiveName.create(
-            contract.name,
-            EntrypointName.fromString("fundRewards")
-          ),
-          amount: CcdAmount.zero(),
-          maxContractExecutionEnergy: Energy.create(MAX_CONTRACT_EXECUTION_ENERGY),
-        },
-        {
-          parameters: (amount * MICRO_CCD).toString(),
-          schema: moduleSchemaFromBase64(btoa(
-            new Uint8Array(contract_schema).reduce(
-              (data, byte) => data + String.fromCharCode(byte),
-              ""
-            )
-          ))
-        }
-      );
-
-      if (transaction2 && rpc) {
-        const result = await rpc.waitForTransactionFinalization(TransactionHash.fromHexString(transaction2));
+      if (transaction && rpc) {
+        const result = await rpc.waitForTransactionFinalization(TransactionHash.fromHexString(transaction));
         if (result) {
           toast.success(`Successfully funded ${amount} EUROe to the rewards pool`);
           setFundAmount("");
@@ -106,7 +77,7 @@ const FundContract = () => {
       }
 
       setLoadingFundContract(false);
-      return transaction2;
+      return transaction;
     } catch (error: any) {
       if (error.message?.includes("OnlyAdmin")) {
         toast.error("Only admin can fund rewards");
@@ -49,7 +49,8 @@ const StakeForm = () => {
           content: [ContractAddress.create(contract.index), "stake"],
         },
         amount: BigInt(amount * MICRO_CCD),
-        data: "0000",
+        // No `StakeData`: an unlocked stake credited to the sender.
+        data: "",
         token_id: "",
       }] as EUROe.TransferParameter;
 
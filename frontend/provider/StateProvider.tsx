@@ -26,6 +26,8 @@ interface UnbondingInfo {
 
 interface StakerInfo {
   amount: string;
+  earning_amount: string;
+  unbonding_amount: string;
   pending_rewards: string;
   slashed: boolean;
   timestamp: bigint;
@@ -58,6 +60,8 @@ interface Context {
 const StateContext = createContext<Context>({
   stakerInfo: {
     amount: "",
+    earning_amount: "",
+    unbonding_amount: "",
     pending_rewards: "",
     slashed: false,
     timestamp: BigInt(0),
@@ -145,12 +149,15 @@ const StateProvider = ({ children }: State) => {
           SchemaVersion?.V1
         );
 
+        const stake = values.stake;
         const transformedStakerInfo: StakerInfo = {
-          amount: values.amount.toString(),
-          pending_rewards: values.pending_rewards.toString(),
-          slashed: values.slashed,
-          timestamp: BigInt(values.timestamp),
-          unbonding: values.unbonding.map((u: any) => ({
+          amount: stake.amount.toString(),
+          earning_amount: values.earning_amount.toString(),
+          unbonding_amount: values.unbonding_amount.toString(),
+          pending_rewards: stake.pending_rewards.toString(),
+          slashed: stake.slashed,
+          timestamp: BigInt(stake.timestamp),
+          unbonding: stake.unbonding.map((u: any) => ({
             amount: u.amount.toString(),
             unlock_time: u.unlock_time.toString()
           }))